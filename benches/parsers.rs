@@ -0,0 +1,67 @@
+use std::{fs, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use paul_scrape_rs::{
+    is_course_page,
+    links::{CourseUrl, TreeUrl},
+    parse_course_page, parse_courses_and_branches, Path,
+};
+use url::Url;
+
+// the bundled pages in benches/corpus, or every .html file in
+// $PAUL_BENCH_CORPUS (e.g. pages recorded with --embed-html all)
+fn load_corpus() -> (Vec<String>, Vec<String>) {
+    let dir = std::env::var("PAUL_BENCH_CORPUS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/corpus"));
+    let mut course_pages = Vec::new();
+    let mut tree_pages = Vec::new();
+    for entry in fs::read_dir(&dir).expect("Failed to read corpus directory") {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "html") {
+            continue;
+        }
+        let html = fs::read_to_string(&path).unwrap();
+        if is_course_page(&html) {
+            course_pages.push(html);
+        } else {
+            tree_pages.push(html);
+        }
+    }
+    (course_pages, tree_pages)
+}
+
+fn bench_parsers(c: &mut Criterion) {
+    let (course_pages, tree_pages) = load_corpus();
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let path = Path::new().push("Sommer 2023".to_string());
+
+    let mut group = c.benchmark_group("parse_course_page");
+    group.throughput(Throughput::Bytes(
+        course_pages.iter().map(|page| page.len() as u64).sum(),
+    ));
+    group.bench_function("corpus", |b| {
+        b.iter(|| {
+            for page in &course_pages {
+                parse_course_page(page.clone(), &CourseUrl::new(url.clone()), &path);
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("parse_courses_and_branches");
+    group.throughput(Throughput::Bytes(
+        tree_pages.iter().map(|page| page.len() as u64).sum(),
+    ));
+    group.bench_function("corpus", |b| {
+        b.iter(|| {
+            for page in &tree_pages {
+                parse_courses_and_branches(page.clone(), &TreeUrl::new(url.clone()), &path);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parsers);
+criterion_main!(benches);
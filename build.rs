@@ -0,0 +1,20 @@
+use std::{path::Path, process::Command};
+
+fn main() {
+    // the commit the scraper was built from, recorded in manifest.json
+    let hash = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=PAUL_SCRAPE_GIT_HASH={}", hash);
+    }
+    // a missing path would rerun this on every build, e.g. from a crates.io tarball
+    for path in [".git/HEAD", ".git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
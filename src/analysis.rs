@@ -0,0 +1,167 @@
+use std::{collections::HashMap, fmt};
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::{datetime::parse_datetime, Appointment, Course, SmallGroup, StateSerializable};
+
+// data quality checks over a finished scrape, and scheduling help built on it
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub course: String,
+    pub small_group: String,
+    pub course_appointment: Appointment,
+    pub small_group_appointment: Appointment,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: small group {} on {} {}-{} ({}) overlaps course appointment {}-{} ({})",
+            self.course.replace('\n', " "),
+            self.small_group,
+            self.small_group_appointment.start_time.0,
+            self.small_group_appointment.start_time.1,
+            self.small_group_appointment.end_time.1,
+            self.small_group_appointment.room,
+            self.course_appointment.start_time.1,
+            self.course_appointment.end_time.1,
+            self.course_appointment.room,
+        )
+    }
+}
+
+fn interval(appointment: &Appointment) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let start = parse_datetime(&appointment.start_time.0, &appointment.start_time.1)?;
+    let end = parse_datetime(&appointment.end_time.0, &appointment.end_time.1)?;
+    Some((start, end))
+}
+
+fn overlaps(a: &Appointment, b: &Appointment) -> bool {
+    if a.cancelled || b.cancelled {
+        return false;
+    }
+    match (interval(a), interval(b)) {
+        (Some((a_start, a_end)), Some((b_start, b_end))) => a_start < b_end && b_start < a_end,
+        _ => false,
+    }
+}
+
+/// Finds appointments of a course's small groups that overlap with the course's own appointments.
+pub fn find_course_conflicts(course: &Course, small_groups: &[&SmallGroup]) -> Vec<Conflict> {
+    let course_name = course.display_title();
+    let mut conflicts = Vec::new();
+    for small_group in small_groups {
+        let small_group_name = small_group
+            .path
+            .fragments
+            .last()
+            .cloned()
+            .unwrap_or_default();
+        for small_group_appointment in &small_group.appointments {
+            for course_appointment in &course.appointments {
+                if overlaps(course_appointment, small_group_appointment) {
+                    conflicts.push(Conflict {
+                        course: course_name.clone(),
+                        small_group: small_group_name.clone(),
+                        course_appointment: course_appointment.clone(),
+                        small_group_appointment: small_group_appointment.clone(),
+                    });
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+// a course that fits around the chosen ones, with the small groups that do too
+pub struct Fit<'a> {
+    pub course: &'a Course,
+    pub small_groups: Vec<&'a SmallGroup>,
+}
+
+// "L.079.05401\nGrundlagen der Programmierung 1" => L.079.05401
+fn course_number(course: &Course) -> String {
+    course.heading().into_iter().next().unwrap_or_default()
+}
+
+fn fits(appointments: &[Appointment], busy: &[&Appointment]) -> bool {
+    appointments
+        .iter()
+        .all(|appointment| !busy.iter().any(|taken| overlaps(appointment, taken)))
+}
+
+/// Courses whose appointments don't overlap any appointment of the `chosen` ones.
+///
+/// `chosen` holds course numbers, course urls or small group urls. A chosen
+/// course blocks its own appointments, its small groups only when chosen
+/// too. Courses without appointments and courses none of whose small groups
+/// fit are left out.
+pub fn find_free_slots<'a>(state: &'a StateSerializable, chosen: &[String]) -> Vec<Fit<'a>> {
+    let small_groups: HashMap<&str, &SmallGroup> = state
+        .small_groups
+        .iter()
+        .map(|sg| (sg.url.as_str(), sg))
+        .collect();
+    let is_chosen = |course: &Course| {
+        chosen
+            .iter()
+            .any(|wanted| *wanted == course.url || *wanted == course_number(course))
+    };
+
+    let mut busy = Vec::new();
+    for course in state.courses.iter().filter(|course| is_chosen(course)) {
+        busy.extend(&course.appointments);
+    }
+    for wanted in chosen {
+        if let Some(small_group) = small_groups.get(wanted.as_str()) {
+            busy.extend(&small_group.appointments);
+        }
+    }
+
+    state
+        .courses
+        .iter()
+        .filter(|course| !is_chosen(course) && !course.appointments.is_empty())
+        .filter(|course| fits(&course.appointments, &busy))
+        .filter_map(|course| {
+            let course_small_groups = course
+                .small_groups
+                .iter()
+                .filter_map(|url| small_groups.get(url.as_str()).copied())
+                .collect::<Vec<_>>();
+            let fitting = course_small_groups
+                .iter()
+                .filter(|small_group| fits(&small_group.appointments, &busy))
+                .copied()
+                .collect::<Vec<_>>();
+            (course_small_groups.is_empty() || !fitting.is_empty()).then_some(Fit {
+                course,
+                small_groups: fitting,
+            })
+        })
+        .collect()
+}
+
+/// Runs [`find_course_conflicts`] for every course of a scrape.
+pub fn find_conflicts(state: &StateSerializable) -> Vec<Conflict> {
+    let small_groups: HashMap<&str, &SmallGroup> = state
+        .small_groups
+        .iter()
+        .map(|sg| (sg.url.as_str(), sg))
+        .collect();
+    state
+        .courses
+        .iter()
+        .flat_map(|course| {
+            let course_small_groups = course
+                .small_groups
+                .iter()
+                .filter_map(|url| small_groups.get(url.as_str()).copied())
+                .collect::<Vec<_>>();
+            find_course_conflicts(course, &course_small_groups)
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+use clap::Parser;
+use paul_scrape_rs::{
+    config::{config_path, Config},
+    error::Error,
+    fetch::{fetch_page, ResponseLimits},
+    is_course_page,
+    snapshot::read_snapshot,
+    StateSerializable,
+};
+use reqwest::Url;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Check that the courses and small groups of a scrape still exist", long_about = None)]
+struct Args {
+    // scrape to check
+    #[clap(default_value = "state.json")]
+    state: String,
+    // requests per second, keep this low
+    #[clap(long, default_value_t = 2.0, parse(try_from_str = parse_rate))]
+    rate: f64,
+}
+
+fn parse_rate(rate: &str) -> Result<f64, String> {
+    match rate.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        Ok(_) => Err("must be more than 0".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args = Args::parse();
+
+    let state: StateSerializable = read_snapshot(&args.state).unwrap();
+
+    let pages = state
+        .courses
+        .iter()
+        .map(|course| (&course.url, &course.path))
+        .chain(state.small_groups.iter().map(|sg| (&sg.url, &sg.path)))
+        .filter(|(url, _)| !url.is_empty())
+        .collect::<Vec<_>>();
+
+    let client = Config::load(config_path())
+        .and_then(|config| config.client())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+    let mut removed = 0;
+    let mut failed = 0;
+    for (url, path) in &pages {
+        tokio::time::sleep(tokio::time::Duration::from_secs_f64(1.0 / args.rate)).await;
+        let name = path.fragments.join(" > ").replace('\n', " ");
+        let page = match Url::parse(url) {
+            Ok(url) => fetch_page(&client, &url, &ResponseLimits::default()).await,
+            Err(e) => {
+                failed += 1;
+                eprintln!("failed: {} ({}): {}", name, url, e);
+                continue;
+            }
+        };
+        let reason = match page {
+            Ok(text) if is_course_page(&text) => None,
+            Ok(_) => Some("no course on page".to_string()),
+            Err(Error::Status(status @ (404 | 410))) => Some(format!("status {}", status)),
+            // network errors, maintenance (5xx, 429) and other statuses don't
+            // tell us anything about the course
+            Err(e) => {
+                failed += 1;
+                eprintln!("failed: {} ({}): {}", name, url, e);
+                continue;
+            }
+        };
+        if let Some(reason) = reason {
+            removed += 1;
+            println!("removed: {} ({}): {}", name, url, reason);
+        }
+    }
+
+    eprintln!(
+        "{} of {} pages removed, {} could not be checked",
+        removed,
+        pages.len(),
+        failed
+    );
+    if removed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,63 @@
+use clap::Parser;
+use paul_scrape_rs::{diff::semester_churn, pauline::Semester, snapshot::read_snapshot};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Report courses new, discontinued or with other instructors between two semesters", long_about = None)]
+struct Args {
+    // semester.json of the earlier semester
+    old: String,
+    // semester.json of the later semester
+    new: String,
+    // write the report as json instead of printing it
+    #[clap(long)]
+    json: Option<String>,
+}
+
+fn load(path: &str) -> Semester {
+    read_snapshot(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    })
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let old = load(&args.old);
+    let new = load(&args.new);
+    let churn = semester_churn(&old, &new);
+
+    if let Some(json) = args.json {
+        std::fs::write(json, serde_json::to_string_pretty(&churn).unwrap()).unwrap();
+        return;
+    }
+    println!("{} -> {}", old.name, new.name);
+    for ou in &churn {
+        println!();
+        println!("{}", ou.ou.as_deref().unwrap_or("(no organisational unit)"));
+        for course in &ou.new {
+            println!("  new: {} {}", course.number, course.name);
+        }
+        for course in &ou.discontinued {
+            println!("  discontinued: {} {}", course.number, course.name);
+        }
+        for course in &ou.instructors_changed {
+            println!(
+                "  instructors: {} {}: {} -> {}",
+                course.number,
+                course.name,
+                course.old.join("; "),
+                course.new.join("; ")
+            );
+        }
+    }
+    eprintln!(
+        "{} new, {} discontinued, {} with other instructors",
+        churn.iter().map(|ou| ou.new.len()).sum::<usize>(),
+        churn.iter().map(|ou| ou.discontinued.len()).sum::<usize>(),
+        churn
+            .iter()
+            .map(|ou| ou.instructors_changed.len())
+            .sum::<usize>()
+    );
+}
@@ -0,0 +1,145 @@
+use clap::Parser;
+use paul_scrape_rs::{
+    config::{config_path, Config},
+    diff::{diff_semesters, SemesterDiff},
+    fetch::{fetch_bytes, ResponseLimits},
+    pauline::Semester,
+    snapshot::read_snapshot,
+};
+use reqwest::{Client, Url};
+use serde::Serialize;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Compare a published semester.json with a fresh one", long_about = None)]
+struct Args {
+    // published semester.json, url or path
+    published: String,
+    // freshly converted semester.json
+    #[clap(default_value = "semester.json")]
+    fresh: String,
+    // write the differences as json instead of printing them
+    #[clap(long)]
+    json: Option<String>,
+    // post the changes to the [[webhooks]] of the config file, each gets only
+    // what its filter subscribes to
+    #[clap(long)]
+    webhooks: bool,
+}
+
+// the body posted to a webhook
+#[derive(Serialize)]
+struct Payload<'a> {
+    semester: &'a str,
+    #[serde(flatten)]
+    diff: &'a SemesterDiff,
+}
+
+async fn post_webhooks(config: &Config, client: &Client, semester: &str, diff: &SemesterDiff) {
+    for webhook in &config.webhooks {
+        let diff = diff.filtered(&webhook.filter);
+        // nothing this subscriber cares about changed
+        if diff.is_empty() {
+            continue;
+        }
+        let payload = Payload {
+            semester,
+            diff: &diff,
+        };
+        let result = client
+            .post(webhook.url.clone())
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => eprintln!(
+                "Posted {} changes to {}",
+                diff.added.len() + diff.removed.len() + diff.changed.len(),
+                webhook.url
+            ),
+            Err(e) => eprintln!("Failed to post to {}: {}", webhook.url, e),
+        }
+    }
+}
+
+async fn load(client: &Client, source: &str) -> Semester {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return read_snapshot(source).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+    }
+    // a whole semester is far above the limit for a single page
+    let limits = ResponseLimits {
+        max_body_size: 1 << 30,
+    };
+    let semester = match Url::parse(source) {
+        Ok(url) => fetch_bytes(client, &url, &limits)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let semester = semester.unwrap_or_else(|e| {
+        eprintln!("Failed to fetch {}: {}", source, e);
+        std::process::exit(1)
+    });
+    serde_json::from_slice(&semester).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", source, e);
+        std::process::exit(1)
+    })
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args = Args::parse();
+    let config = Config::load(config_path()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    let client = config.client().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+
+    let published = load(&client, &args.published).await;
+    let fresh = load(&client, &args.fresh).await;
+
+    let diff = diff_semesters(&published, &fresh);
+    if args.webhooks {
+        post_webhooks(&config, &client, &fresh.name, &diff).await;
+    }
+
+    match args.json {
+        Some(json) => {
+            let diff_json = serde_json::to_string_pretty(&diff).unwrap();
+            std::fs::write(json, diff_json).unwrap();
+        }
+        None => {
+            for course in &diff.removed {
+                println!("only published: {} {}", course.cid, course.name);
+            }
+            for course in &diff.added {
+                println!("only fresh: {} {}", course.cid, course.name);
+            }
+            for course in &diff.changed {
+                for change in &course.changes {
+                    println!(
+                        "changed: {} {} {}: {:?} -> {:?}",
+                        course.cid, course.name, change.field, change.old, change.new
+                    );
+                }
+            }
+        }
+    }
+    eprintln!(
+        "{} published courses, {} fresh courses: {} only published, {} only fresh, {} changed",
+        published.courses.len(),
+        fresh.courses.len(),
+        diff.removed.len(),
+        diff.added.len(),
+        diff.changed.len()
+    );
+    if !diff.is_empty() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,38 @@
+use clap::Parser;
+use paul_scrape_rs::{analysis::find_conflicts, snapshot::read_snapshot, StateSerializable};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Report small groups that overlap with their course's appointments", long_about = None)]
+struct Args {
+    // scrape to check
+    #[clap(default_value = "state.json")]
+    state: String,
+    // write the conflicts as json instead of printing them
+    #[clap(long)]
+    json: Option<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let state: StateSerializable = read_snapshot(&args.state).unwrap();
+
+    let conflicts = find_conflicts(&state);
+
+    match args.json {
+        Some(json) => {
+            let conflicts_json = serde_json::to_string_pretty(&conflicts).unwrap();
+            std::fs::write(json, conflicts_json).unwrap();
+        }
+        None => {
+            for conflict in &conflicts {
+                println!("warning: {}", conflict);
+            }
+        }
+    }
+    eprintln!(
+        "{} conflicts in {} courses",
+        conflicts.len(),
+        state.courses.len()
+    );
+}
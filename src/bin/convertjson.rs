@@ -1,47 +1,122 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use chrono::{Datelike, Timelike};
-use paul_scrape_rs::{SmallGroup, StateSerializable};
+use clap::{ArgEnum, Parser};
+use paul_scrape_rs::{
+    cid::{CidStrategy, CourseNumber, NameHash, UuidV5},
+    datetime::parse_datetime,
+    matches_course_type, parse_small_group_title,
+    pauline::{PaulineAppointment, PaulineCourse, PaulineSmallGroup, Semester},
+    rooms::{RoomEntry, RoomNormalizer},
+    sink::slug,
+    snapshot::read_snapshot,
+    tags::{TagRule, TagRules},
+    SmallGroup, StateSerializable,
+};
 use serde::Serialize;
 use sha2::Digest;
+use uuid::Uuid;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Only keep courses whose organisational unit contains this
+    #[clap(long)]
+    filter_ou: Option<String>,
+    /// Only keep courses whose instructors contain this
+    #[clap(long)]
+    filter_instructor: Option<String>,
+    /// Only keep courses of these types, e.g. Vorlesung,Seminar
+    #[clap(long, use_value_delimiter = true)]
+    only_types: Vec<String>,
+    /// How cids are made from the course number
+    #[clap(long, arg_enum, default_value = "hash")]
+    cid_strategy: CidStrategyArg,
+    /// Namespace of --cid-strategy uuid, a fixed one unless given
+    #[clap(long)]
+    cid_namespace: Option<uuid::Uuid>,
+    /// What to do when several courses end up with the same cid
+    #[clap(long, arg_enum, default_value = "index")]
+    duplicate_cids: DuplicateCidPolicy,
+    /// Write one file per value instead of semester.json, plus semester.index.json
+    #[clap(long, arg_enum)]
+    split_by: Option<SplitBy>,
+    /// JSON file with tag rules, see paul_scrape_rs::tags
+    #[clap(long)]
+    tag_rules: Option<String>,
+    /// Replace personal data with pseudonyms before writing
+    #[clap(long, arg_enum)]
+    anonymize: Option<Anonymize>,
+    /// Salt for the pseudonyms, keeps them stable across runs. random if not given,
+    /// unsalted hashes of public staff lists would be easy to reverse
+    #[clap(long)]
+    anonymize_salt: Option<String>,
+    /// Add the canonical room next to the room as written in PAUL
+    #[clap(long)]
+    normalize_rooms: bool,
+    /// JSON file with rooms of other campuses, same format as src/rooms.json.
+    /// its entries are used in addition to the bundled ones
+    #[clap(long, requires = "normalize-rooms")]
+    rooms_dictionary: Option<String>,
+    /// SQLite file with the cid every course got when it was first converted,
+    /// keeps cids the same when names or instructors change. needs the sqlite feature
+    #[clap(long)]
+    id_map: Option<String>,
+}
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Clone)]
-struct Semester {
-    name: String,
-    created: String,
-    courses: Vec<PaulineCourse>,
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum CidStrategyArg {
+    /// The course number, "L.079.05401"
+    Number,
+    /// The course number and a hash of name and instructors, "L.079.05401|3f"
+    Hash,
+    /// A UUIDv5 of course number and name
+    Uuid,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Clone)]
-struct PaulineCourse {
-    cid: String,
-    name: String,
-    description: Option<String>,
-    ou: Option<String>,
-    instructors: Option<String>,
-    small_groups: Vec<PaulineSmallGroup>,
-    appointments: Vec<PaulineAppointment>,
+// the default of --cid-namespace, uuids stay the same across runs without one
+const CID_NAMESPACE: Uuid = Uuid::from_u128(0x5c1e_7d2a_8f0b_4e6c_9a3d_1b2c_4d5e_6f70);
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum Anonymize {
+    /// Instructor names of courses, small groups and appointments
+    Instructors,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum SplitBy {
+    /// One file per organisational unit
+    Ou,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Clone)]
-struct PaulineSmallGroup {
-    name: String,
-    appointments: Vec<PaulineAppointment>,
+#[derive(Serialize)]
+struct IndexEntry {
+    ou: Option<String>,
+    file: String,
+    courses: usize,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Clone)]
-struct PaulineAppointment {
-    start_time: String,
-    end_time: String,
-    room: String,
-    instructors: String,
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum DuplicateCidPolicy {
+    /// Sort the colliding courses and append ":0", ":1", ...
+    Index,
+    /// Append ":" and 8 hex chars of a hash over the whole course
+    Hash,
+    /// Merge the colliding courses' appointments and small groups into one course
+    Merge,
+    /// Abort the conversion
+    Error,
 }
 
 fn main() {
-    // read state.json
-    let state = std::fs::read_to_string("state.json").unwrap();
-    // parse as StateSerializable
-    let state: StateSerializable = serde_json::from_str(&state).unwrap();
+    let args = Args::parse();
+
+    // read state.json, or the state.msgpack of --format msgpack
+    let path = ["state.json", "state.msgpack"]
+        .into_iter()
+        .find(|path| std::path::Path::new(path).exists())
+        .unwrap_or("state.json");
+    let state: StateSerializable = read_snapshot(path).unwrap();
 
     // our goal is to convert this into a whole Semester.
     // pub struct StateSerializable {
@@ -51,6 +126,33 @@ fn main() {
     //     pub small_groups: Vec<SmallGroup>,
     // }
 
+    let tag_rules = args.tag_rules.as_ref().map(|path| {
+        let rules: Vec<TagRule> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        TagRules::compile(rules).unwrap()
+    });
+
+    let cid_strategy: Box<dyn CidStrategy> = match args.cid_strategy {
+        CidStrategyArg::Number => Box::new(CourseNumber),
+        CidStrategyArg::Hash => Box::new(NameHash),
+        CidStrategyArg::Uuid => Box::new(UuidV5 {
+            namespace: args.cid_namespace.unwrap_or(CID_NAMESPACE),
+        }),
+    };
+
+    #[cfg(feature = "sqlite")]
+    let id_map = args.id_map.as_ref().map(|path| {
+        paul_scrape_rs::cid::IdMap::open(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        })
+    });
+    #[cfg(not(feature = "sqlite"))]
+    if args.id_map.is_some() {
+        eprintln!("--id-map: built without the sqlite feature");
+        std::process::exit(1);
+    }
+
     // we'll index small_groups by their url:
     let small_groups: HashMap<String, PaulineSmallGroup> = state
         .small_groups
@@ -60,92 +162,133 @@ fn main() {
 
     // now we can convert the courses:
     let mut courses = HashSet::new();
-    let mut seen_cids = HashSet::new();
     for course in state.courses {
+        if !matches_filter(course.ou.as_deref().unwrap_or_default(), &args.filter_ou)
+            || !matches_filter(&course.instructors, &args.filter_instructor)
+            || !matches_course_type(course.course_type.as_deref(), &args.only_types)
+        {
+            continue;
+        }
+
         let appointments = course
             .appointments
             .iter()
             .map(convert_appointment)
             .collect();
+        // cid,name are the first two lines of the heading
+        let heading = course.heading();
 
+        // small groups whose page failed are missing, see the repair binary
         let small_groups = course
             .small_groups
             .into_iter()
-            .map(|sg| small_groups.get(&sg).unwrap().clone())
+            .filter_map(|sg| {
+                let small_group = small_groups.get(&sg).cloned();
+                if small_group.is_none() {
+                    eprintln!(
+                        "Skipping missing small group {}, run repair to fetch it",
+                        sg
+                    );
+                }
+                small_group
+            })
             .collect();
 
-        // cid,name comes from splitting the last path entry to a newline
-        let cid_title = course
-            .path
-            .fragments
-            .last()
-            .unwrap()
-            .lines()
-            .collect::<Vec<&str>>();
-        let mut cid = cid_title[0].to_string();
-        let name = cid_title[1].to_string();
-
-        // hash name+instructors
-        let name_hash = format!(
-            "{:x}",
-            sha2::Sha256::digest(format!("{}{}", name, course.instructors).as_bytes())
+        let name = heading.get(1).cloned().unwrap_or_default();
+        let cid = cid_strategy.cid(
+            heading.first().map_or("", String::as_str),
+            &name,
+            &course.instructors,
         );
-
-        // add 2 chars of hash to cid
-        cid.push('|');
-        cid.push_str(&name_hash[..2]);
-
-        // // if we've seen this cid before, add a number to it
-        // let mut cid = o_cid.clone();
-        // let mut i = 0;
-        // while seen_cids.contains(&cid) {
-        //     cid = format!("{}:{}", o_cid, i);
-        //     i += 1;
-        // }
-
-        // add to seen_cids
-        seen_cids.insert(cid.clone());
+        #[cfg(feature = "sqlite")]
+        let cid = match &id_map {
+            Some(id_map) => id_map
+                .cid(&paul_scrape_rs::cid::event_id(&course.url), &cid)
+                .unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1)
+                }),
+            None => cid,
+        };
+
+        let description = "".to_string();
+        let tags = tag_rules
+            .as_ref()
+            .map(|rules| rules.tags(&[&name, &description]))
+            .unwrap_or_default();
 
         courses.insert(PaulineCourse {
             cid,
             name,
-            description: Some("".to_string()),
+            description: Some(description),
             ou: course.ou,
             instructors: Some(course.instructors),
             small_groups,
             appointments,
+            tags,
+            source_updated_at: course
+                .source_updated_at
+                .map(|updated| updated.format("%Y-%m-%dT%H:%M:%S").to_string()),
         });
     }
 
-    let mut courses_hashmap: HashMap<String, Vec<PaulineCourse>> = HashMap::new();
-
+    // group by cid, sorted so the output doesn't depend on hash set order
+    let mut courses_by_cid: BTreeMap<String, Vec<PaulineCourse>> = BTreeMap::new();
     for course in courses {
-        if let std::collections::hash_map::Entry::Vacant(e) =
-            courses_hashmap.entry(course.cid.clone())
-        {
-            e.insert(Vec::new());
-            courses_hashmap.get_mut(&course.cid).unwrap().push(course);
-        } else {
-            let vec = courses_hashmap.get_mut(&course.cid).unwrap();
-            // push, sort and adjust cid s
-            vec.push(course.clone());
-            // set all cid s to the key
-            for c in vec.iter_mut() {
-                c.cid = course.cid.clone();
+        courses_by_cid
+            .entry(course.cid.clone())
+            .or_default()
+            .push(course);
+    }
+
+    let mut courses_vec = Vec::new();
+    for (cid, mut group) in courses_by_cid {
+        group.sort();
+        if group.len() == 1 {
+            courses_vec.extend(group);
+            continue;
+        }
+        match args.duplicate_cids {
+            DuplicateCidPolicy::Index => {
+                for (i, mut course) in group.into_iter().enumerate() {
+                    course.cid = format!("{}:{}", cid, i);
+                    courses_vec.push(course);
+                }
             }
-            // sort
-            vec.sort();
-            // adjust cid s
-            for (i, c) in vec.iter_mut().enumerate() {
-                c.cid = format!("{}:{}", course.cid, i);
+            DuplicateCidPolicy::Hash => {
+                for mut course in group {
+                    let course_hash = format!(
+                        "{:x}",
+                        sha2::Sha256::digest(serde_json::to_string(&course).unwrap().as_bytes())
+                    );
+                    course.cid = format!("{}:{}", cid, &course_hash[..8]);
+                    courses_vec.push(course);
+                }
+            }
+            DuplicateCidPolicy::Merge => courses_vec.push(merge_courses(group)),
+            DuplicateCidPolicy::Error => {
+                eprintln!(
+                    "cid {} is used by {} courses: {}",
+                    cid,
+                    group.len(),
+                    group
+                        .iter()
+                        .map(|course| course.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                std::process::exit(1);
             }
         }
     }
 
-    let courses_vec = courses_hashmap
-        .into_iter()
-        .flat_map(|(_, v)| v)
-        .collect::<Vec<PaulineCourse>>();
+    #[cfg(feature = "sqlite")]
+    if let Some(id_map) = id_map {
+        id_map.save().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+    }
 
     let semester = Semester {
         name: state.semester,
@@ -161,50 +304,153 @@ fn main() {
         courses: courses_vec,
     };
 
-    let semester_json = serde_json::to_string_pretty(&semester).unwrap();
+    let semester = match args.anonymize {
+        Some(Anonymize::Instructors) => {
+            let salt = args.anonymize_salt.clone().unwrap_or_else(|| {
+                let salt: [u8; 16] = rand::random();
+                salt.iter().map(|byte| format!("{:02x}", byte)).collect()
+            });
+            anonymize_instructors(semester, &salt)
+        }
+        None => semester,
+    };
+
+    let semester = if args.normalize_rooms {
+        let normalizer = match &args.rooms_dictionary {
+            Some(path) => {
+                let entries: Vec<RoomEntry> =
+                    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+                RoomNormalizer::extended(entries)
+            }
+            None => RoomNormalizer::bundled(),
+        };
+        normalize_rooms(semester, &normalizer)
+    } else {
+        semester
+    };
 
-    std::fs::write("semester.json", semester_json).unwrap();
+    match args.split_by {
+        Some(SplitBy::Ou) => write_split_by_ou(semester),
+        None => {
+            let semester_json = serde_json::to_string_pretty(&semester).unwrap();
+            std::fs::write("semester.json", semester_json).unwrap();
+        }
+    }
 }
 
-fn convert_time(date_str: &str, time: &str) -> String {
-    // month_dict = {
-    //     'Jan': 1, 'Feb': 2, 'Mrz': 3, 'Mär': 3, 'Apr': 4, 'Mai': 5, 'Jun': 6, 'Jul': 7, 'Aug': 8, 'Sep': 9, 'Okt': 10,
-    //     'Nov': 11, 'Dez': 12
-    // }
+fn write_split_by_ou(semester: Semester) {
+    let mut courses_by_ou: BTreeMap<Option<String>, Vec<PaulineCourse>> = BTreeMap::new();
+    for course in semester.courses {
+        courses_by_ou
+            .entry(course.ou.clone())
+            .or_default()
+            .push(course);
+    }
+    let mut index = Vec::new();
+    for (ou, courses) in courses_by_ou {
+        // "Institut für Informatik" => semester.Institut-für-Informatik.json
+        let file = format!("semester.{}.json", slug(ou.as_deref().unwrap_or("unknown")));
+        index.push(IndexEntry {
+            ou,
+            file: file.clone(),
+            courses: courses.len(),
+        });
+        let slice = Semester {
+            name: semester.name.clone(),
+            created: semester.created.clone(),
+            courses,
+        };
+        std::fs::write(&file, serde_json::to_string_pretty(&slice).unwrap()).unwrap();
+    }
+    std::fs::write(
+        "semester.index.json",
+        serde_json::to_string_pretty(&index).unwrap(),
+    )
+    .unwrap();
+}
 
-    // split_date = date_str.strip().split(" ")
-    // day = int(split_date[1].replace(".", ""))
-    // month = month_dict[split_date[2].replace(".", "")]
-    // year = int(split_date[3])
-
-    // if time == '24:00':
-    //     time = '23:59'
-    // split_time = time.split(":")
-    // hour = int(split_time[0])
-    // minute = int(split_time[1])
-    let split_date = date_str.split(' ').collect::<Vec<&str>>();
-    let day = split_date[1].replace('.', "").parse::<i32>().unwrap();
-    let month = match split_date[2].replace('.', "").as_str() {
-        "Jan" => 1,
-        "Feb" => 2,
-        "Mrz" => 3,
-        "Mär" => 3,
-        "Apr" => 4,
-        "Mai" => 5,
-        "Jun" => 6,
-        "Jul" => 7,
-        "Aug" => 8,
-        "Sep" => 9,
-        "Okt" => 10,
-        "Nov" => 11,
-        "Dez" => 12,
-        _ => panic!("Unknown month"),
+fn anonymize_instructors(mut semester: Semester, salt: &str) -> Semester {
+    // "Prof. Dr. Meyer; Dr. Schmidt (fällt aus)" => "Instructor 1a2b3c4d; Instructor 5e6f7a8b (fällt aus)"
+    let pseudonym = |name: &str| {
+        let hash = format!(
+            "{:x}",
+            sha2::Sha256::digest(format!("{}{}", salt, name).as_bytes())
+        );
+        format!("Instructor {}", &hash[..8])
+    };
+    let anonymize = |instructors: &str| {
+        instructors
+            .split(';')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| match name.find(" (") {
+                Some(suffix) => format!("{}{}", pseudonym(&name[..suffix]), &name[suffix..]),
+                None => pseudonym(name),
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
     };
-    let year = split_date[3].parse::<i32>().unwrap();
+    for course in &mut semester.courses {
+        course.instructors = course.instructors.as_deref().map(anonymize);
+        for appointment in course.appointments.iter_mut().chain(
+            course
+                .small_groups
+                .iter_mut()
+                .flat_map(|sg| &mut sg.appointments),
+        ) {
+            appointment.instructors = anonymize(&appointment.instructors);
+        }
+    }
+    semester
+}
+
+fn normalize_rooms(mut semester: Semester, normalizer: &RoomNormalizer) -> Semester {
+    for course in &mut semester.courses {
+        for appointment in course.appointments.iter_mut().chain(
+            course
+                .small_groups
+                .iter_mut()
+                .flat_map(|sg| &mut sg.appointments),
+        ) {
+            appointment.room_id = normalizer.canonical(&appointment.room);
+        }
+    }
+    semester
+}
 
-    let time = if time == "24:00" { "23:59" } else { time };
+fn merge_courses(group: Vec<PaulineCourse>) -> PaulineCourse {
+    // the first course of the sorted group wins, the rest only contribute dates and groups
+    let mut group = group.into_iter();
+    let mut merged = group.next().unwrap();
+    for course in group {
+        merged.appointments.extend(course.appointments);
+        merged.small_groups.extend(course.small_groups);
+        merged.tags.extend(course.tags);
+        // the latest edit of any part
+        merged.source_updated_at = merged.source_updated_at.max(course.source_updated_at);
+    }
+    merged.appointments.sort();
+    merged.appointments.dedup();
+    merged.small_groups.sort();
+    merged.small_groups.dedup();
+    merged.tags.sort();
+    merged.tags.dedup();
+    merged
+}
 
-    format!("{}-{:02}-{:02}T{}:00", year, month, day, time)
+fn matches_filter(value: &str, filter: &Option<String>) -> bool {
+    // case insensitive substring match, no filter matches everything
+    match filter {
+        Some(filter) => value.to_lowercase().contains(&filter.to_lowercase()),
+        None => true,
+    }
+}
+
+fn convert_time(date_str: &str, time: &str) -> String {
+    parse_datetime(date_str, time)
+        .unwrap_or_else(|| panic!("Unknown date {} {}", date_str, time))
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
 }
 
 fn convert_appointment(appointment: &paul_scrape_rs::Appointment) -> PaulineAppointment {
@@ -213,13 +459,17 @@ fn convert_appointment(appointment: &paul_scrape_rs::Appointment) -> PaulineAppo
     //     pub end_time: (String, String),
     //     pub room: String,
     //     pub instructors: String,
+    //     pub cancelled: bool,
     // }
 
     PaulineAppointment {
         start_time: convert_time(&appointment.start_time.0, &appointment.start_time.1),
         end_time: convert_time(&appointment.end_time.0, &appointment.end_time.1),
         room: appointment.room.clone(),
+        room_id: None,
         instructors: appointment.instructors.clone(),
+        location: appointment.location.clone(),
+        cancelled: appointment.cancelled,
     }
 }
 
@@ -230,17 +480,16 @@ fn convert_small_group(sg: &SmallGroup) -> PaulineSmallGroup {
     //     pub appointments: Vec<Appointment>,
     // }
 
-    // remove 13 chars from the last part of the path
-    let name = sg
-        .path
-        .fragments
-        .last()
-        .unwrap()
-        .clone()
-        .replace("Kleingruppe:\u{a0}", "");
+    // state files from before the name was parsed only have the title in the path
+    let (name, group_number) = if sg.name.is_empty() {
+        parse_small_group_title(sg.path.fragments.last().unwrap())
+    } else {
+        (sg.name.clone(), sg.group_number)
+    };
 
     PaulineSmallGroup {
         name,
         appointments: sg.appointments.iter().map(convert_appointment).collect(),
+        group_number,
     }
 }
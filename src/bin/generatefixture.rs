@@ -0,0 +1,152 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use clap::Parser;
+use paul_scrape_rs::{
+    pauline::{PaulineAppointment, PaulineCourse, PaulineSmallGroup, Semester},
+    Location,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Generate a synthetic semester.json for testing", long_about = None)]
+struct Args {
+    // seed for the random number generator, same seed => same output
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+    // number of courses to generate
+    #[clap(long, default_value_t = 50)]
+    courses: usize,
+    // maximum number of small groups per course
+    #[clap(long, default_value_t = 3)]
+    small_groups: usize,
+    // number of weekly appointments per course and small group
+    #[clap(long, default_value_t = 14)]
+    appointments: usize,
+    // semester name
+    #[clap(long, default_value = "Sommer 2023")]
+    semester: String,
+    // output file
+    #[clap(long, default_value = "semester.json")]
+    output: String,
+}
+
+const NAMES: &[&str] = &[
+    "Grundlagen der Programmierung",
+    "Datenbanksysteme",
+    "Modellierung",
+    "Analysis",
+    "Lineare Algebra",
+    "Rechnernetze",
+    "Softwaretechnik",
+    "Theoretische Informatik",
+    "Stochastik",
+    "Betriebssysteme",
+];
+
+const KINDS: &[&str] = &["Vorlesung", "Übung", "Seminar", "Projektgruppe"];
+
+const OUS: &[&str] = &[
+    "Institut für Informatik",
+    "Institut für Mathematik",
+    "Institut für Elektrotechnik und Informationstechnik",
+];
+
+const INSTRUCTORS: &[&str] = &[
+    "Prof. Dr. Meyer",
+    "Dr. Schmidt",
+    "Prof. Dr. Müller",
+    "Dr. Weber",
+    "Dr. Fischer",
+];
+
+const ROOMS: &[&str] = &["O2.267", "A3", "C1", "H1", "L1.202", "D1.303", "F0.530"];
+
+fn main() {
+    let args = Args::parse();
+    let mut rng = StdRng::seed_from_u64(args.seed);
+
+    // every semester starts on a monday in april
+    let semester_start = NaiveDate::from_ymd_opt(2023, 4, 17).unwrap();
+
+    let courses = (0..args.courses)
+        .map(|i| {
+            let name = format!(
+                "{} {}",
+                KINDS.choose(&mut rng).unwrap(),
+                NAMES.choose(&mut rng).unwrap()
+            );
+            let instructors = INSTRUCTORS.choose(&mut rng).unwrap().to_string();
+            let appointments =
+                generate_appointments(&mut rng, semester_start, args.appointments, &instructors);
+            let small_groups = (0..rng.gen_range(0..=args.small_groups))
+                .map(|j| PaulineSmallGroup {
+                    name: format!("Übung {:02}", j + 1),
+                    appointments: generate_appointments(
+                        &mut rng,
+                        semester_start,
+                        args.appointments,
+                        &instructors,
+                    ),
+                    group_number: Some(j as u32 + 1),
+                })
+                .collect();
+            let cid = format!(
+                "L.{:03}.{:05}|{:02x}",
+                rng.gen_range(0..1000),
+                i,
+                rng.gen::<u8>()
+            );
+            PaulineCourse {
+                cid,
+                name,
+                description: Some("".to_string()),
+                ou: Some(OUS.choose(&mut rng).unwrap().to_string()),
+                instructors: Some(instructors),
+                small_groups,
+                appointments,
+                tags: Vec::new(),
+                source_updated_at: None,
+            }
+        })
+        .collect();
+
+    let semester = Semester {
+        name: args.semester,
+        created: format_time(semester_start.and_hms_opt(0, 0, 0).unwrap()),
+        courses,
+    };
+
+    let semester_json = serde_json::to_string_pretty(&semester).unwrap();
+
+    std::fs::write(args.output, semester_json).unwrap();
+}
+
+fn generate_appointments(
+    rng: &mut StdRng,
+    semester_start: NaiveDate,
+    count: usize,
+    instructors: &str,
+) -> Vec<PaulineAppointment> {
+    // pick a weekday and a two hour slot between 8 and 18 o'clock
+    let first_day = semester_start + Duration::days(rng.gen_range(0..5));
+    let start = NaiveTime::from_hms_opt(rng.gen_range(4..9) * 2, 0, 0).unwrap();
+    let room = ROOMS.choose(rng).unwrap().to_string();
+    (0..count)
+        .map(|week| {
+            let start_time = (first_day + Duration::weeks(week as i64)).and_time(start);
+            PaulineAppointment {
+                start_time: format_time(start_time),
+                end_time: format_time(start_time + Duration::hours(2)),
+                room: room.clone(),
+                room_id: None,
+                instructors: instructors.to_string(),
+                location: Location::Room,
+                cancelled: false,
+            }
+        })
+        .collect()
+}
+
+fn format_time(time: NaiveDateTime) -> String {
+    // same format as convertjson produces
+    time.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
@@ -0,0 +1,151 @@
+use std::{env, time::Instant};
+
+use clap::Parser;
+use paul_scrape_rs::{
+    canary::{compare, extract_fields, CanaryPage},
+    config::{config_path, load_dotenv, Config},
+    error::Error,
+    fetch::{fetch_page, ResponseLimits},
+    get_semesters,
+};
+use reqwest::Url;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Check that PAUL's landing pages still lead to the semester listing", long_about = None)]
+struct Args {
+    // base url, falls back to $BASE_URL, then paul-scrape.toml
+    base_url: Option<Url>,
+    // fail unless this semester is listed
+    #[clap(long)]
+    expect_semester: Option<String>,
+    #[clap(long, default_value_t = 5)]
+    max_redirects: usize,
+    // json list of representative pages and their expected fields, parsed
+    // strictly and compared after the semester check
+    #[clap(long)]
+    canary: Option<String>,
+    // write the fields found now as the expected ones instead of comparing
+    #[clap(long, requires = "canary")]
+    record: bool,
+}
+
+// exit codes for monitoring: 0 healthy, 1 markup changed, 2 unreachable
+const MARKUP_CHANGED: i32 = 1;
+const UNREACHABLE: i32 = 2;
+
+const DEFAULT_BASE_URL: &str = "https://paul.uni-paderborn.de";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    // before parsing, BASE_URL may come from .env
+    load_dotenv();
+    let args = Args::parse();
+
+    let config = Config::load(config_path()).unwrap_or_else(|e| {
+        println!("unusable config: {}", e);
+        std::process::exit(UNREACHABLE)
+    });
+    let client = config.client().unwrap_or_else(|e| {
+        println!("unusable config: {}", e);
+        std::process::exit(UNREACHABLE)
+    });
+    let base_url = args
+        .base_url
+        .or_else(|| {
+            env::var("BASE_URL")
+                .ok()
+                .and_then(|url| Url::parse(&url).ok())
+        })
+        .or(config.base_url)
+        .unwrap_or_else(|| Url::parse(DEFAULT_BASE_URL).unwrap());
+    let started = Instant::now();
+    let result = get_semesters(client.clone(), &base_url, args.max_redirects).await;
+    let latency = started.elapsed();
+
+    let semesters = match result {
+        Ok(semesters) => semesters,
+        Err(e @ Error::Request(_)) | Err(e @ Error::Status(_)) => {
+            println!("unreachable after {:?}: {}", latency, e);
+            std::process::exit(UNREACHABLE)
+        }
+        Err(e) => {
+            println!("unexpected markup after {:?}: {}", latency, e);
+            std::process::exit(MARKUP_CHANGED)
+        }
+    };
+    if semesters.is_empty() {
+        println!("no semesters on the main page after {:?}", latency);
+        std::process::exit(MARKUP_CHANGED)
+    }
+    if let Some(expected) = &args.expect_semester {
+        if !semesters.iter().any(|(semester, _)| semester == expected) {
+            println!("{} is not listed after {:?}", expected, latency);
+            std::process::exit(MARKUP_CHANGED)
+        }
+    }
+    println!(
+        "ok after {:?}: {}",
+        latency,
+        semesters
+            .iter()
+            .map(|(semester, _)| semester.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    if let Some(path) = &args.canary {
+        canary(&client, path, args.record).await;
+    }
+}
+
+async fn canary(client: &reqwest::Client, path: &str, record: bool) {
+    let pages = std::fs::read_to_string(path).expect("Failed to read canary file");
+    let mut pages: Vec<CanaryPage> =
+        serde_json::from_str(&pages).expect("Failed to parse canary file");
+
+    let mut changed = false;
+    for page in &mut pages {
+        let html = match fetch_page(client, &page.url, &ResponseLimits::default()).await {
+            Ok(html) => html,
+            Err(e) => {
+                println!("unreachable {:?} page {}: {}", page.kind, page.url, e);
+                std::process::exit(UNREACHABLE)
+            }
+        };
+        let fields = match extract_fields(page.kind, &page.url, html) {
+            Ok(fields) => fields,
+            Err(e) => {
+                println!(
+                    "unexpected markup on {:?} page {}: {}",
+                    page.kind, page.url, e
+                );
+                changed = true;
+                continue;
+            }
+        };
+        if record {
+            page.expected = fields;
+            continue;
+        }
+        let mismatches = compare(page, &fields);
+        for mismatch in &mismatches {
+            println!(
+                "{:?} page {}: {} was {:?}, expected {:?}",
+                page.kind, page.url, mismatch.field, mismatch.actual, mismatch.expected
+            );
+        }
+        changed |= !mismatches.is_empty();
+    }
+
+    if record {
+        let json = serde_json::to_string_pretty(&pages).unwrap();
+        std::fs::write(path, json).expect("Failed to write canary file");
+        println!("recorded the fields of {} canary pages", pages.len());
+    }
+    if changed {
+        std::process::exit(MARKUP_CHANGED)
+    }
+    if !record {
+        println!("ok: {} canary pages match", pages.len());
+    }
+}
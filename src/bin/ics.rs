@@ -0,0 +1,43 @@
+use chrono::NaiveDate;
+use clap::Parser;
+use paul_scrape_rs::{
+    ics::{semester_to_ics_in, TimeWindow},
+    pauline::Semester,
+};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Export the appointments of a semester.json as an iCalendar file", long_about = None)]
+struct Args {
+    // converted semester to export
+    #[clap(default_value = "semester.json")]
+    semester: String,
+    // only export these courses, all if none are given
+    #[clap(long = "cid", multiple_occurrences = true)]
+    cids: Vec<String>,
+    // only appointments on or after this day, e.g. 2024-04-01
+    #[clap(long)]
+    from: Option<NaiveDate>,
+    // only appointments on or before this day
+    #[clap(long)]
+    to: Option<NaiveDate>,
+    #[clap(long, default_value = "semester.ics")]
+    output: String,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let semester = std::fs::read_to_string(&args.semester).unwrap();
+    let semester: Semester = serde_json::from_str(&semester).unwrap();
+
+    let window = TimeWindow {
+        from: args.from,
+        to: args.to,
+    };
+    let ics = semester_to_ics_in(
+        &semester,
+        |cid| args.cids.is_empty() || args.cids.iter().any(|wanted| wanted == cid),
+        &window,
+    );
+    std::fs::write(&args.output, ics).unwrap();
+}
@@ -0,0 +1,41 @@
+use clap::Parser;
+use paul_scrape_rs::{
+    pauline::Semester,
+    query::{parse_query, run_query},
+};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Query a semester.json, e.g. \"select name, room from appointments where room like 'O2%'\"", long_about = None)]
+struct Args {
+    // select <columns|*> from <courses|small_groups|appointments> [where ...] [limit n]
+    query: String,
+    // converted semester to query
+    #[clap(default_value = "semester.json")]
+    semester: String,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let semester = std::fs::read_to_string(&args.semester).unwrap();
+    let semester: Semester = serde_json::from_str(&semester).unwrap();
+
+    let (columns, rows) = parse_query(&args.query)
+        .and_then(|query| run_query(&semester, &query))
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+
+    // tab separated, so the output can be piped into other tools
+    println!("{}", columns.join("\t"));
+    for row in rows {
+        println!(
+            "{}",
+            row.iter()
+                .map(|field| field.replace(['\t', '\n'], " "))
+                .collect::<Vec<_>>()
+                .join("\t")
+        );
+    }
+}
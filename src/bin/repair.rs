@@ -0,0 +1,135 @@
+use std::{collections::HashSet, path::Path};
+
+use clap::Parser;
+use paul_scrape_rs::{
+    config::{config_path, Config},
+    fetch::{fetch_page_retrying, ResponseLimits, Timings},
+    links::SmallGroupUrl,
+    parse_small_group, parse_small_group_title,
+    snapshot::{read_snapshot, snapshot_format, write_snapshot},
+    SmallGroup, StateSerializable, Warning,
+};
+use reqwest::Url;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Fetch the small groups missing from a state file", long_about = None)]
+struct Args {
+    #[clap(default_value = "state.json")]
+    state: String,
+    // where to write the patched state, defaults to overwriting the input
+    #[clap(long)]
+    output: Option<String>,
+    // add empty small groups for pages that still fail, so convertjson keeps the links
+    #[clap(long)]
+    placeholders: bool,
+    #[clap(long, default_value_t = 3)]
+    retries: u32,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args = Args::parse();
+    // the patched state is written in the format of the input
+    let format = snapshot_format(&args.state).expect("Failed to read state file");
+    let mut state: StateSerializable =
+        read_snapshot(&args.state).expect("Failed to read state file");
+
+    // links of courses without a matching small group, in course order
+    let known = state
+        .small_groups
+        .iter()
+        .map(|sg| sg.url.clone())
+        .collect::<HashSet<_>>();
+    let mut queued = HashSet::new();
+    let missing = state
+        .courses
+        .iter()
+        .flat_map(|course| {
+            course
+                .small_groups
+                .iter()
+                .enumerate()
+                .map(move |(i, url)| (course, i, url))
+        })
+        .filter(|(_, _, url)| !known.contains(*url) && queued.insert(url.to_string()))
+        .map(|(course, i, url)| {
+            // the link text isn't kept in the state, number the groups instead
+            let path = course.path.push(format!("Kleingruppe:\u{a0}{}", i + 1));
+            (url.clone(), path)
+        })
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        eprintln!("No small groups missing");
+        return;
+    }
+    eprintln!("Fetching {} missing small groups", missing.len());
+
+    let client = Config::load(config_path())
+        .and_then(|config| config.client())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+    let (mut fetched, mut placeholders, mut failed) = (0, 0, 0);
+    for (url, path) in missing {
+        let page = match Url::parse(&url) {
+            Ok(parsed) => fetch_page_retrying(
+                &client,
+                &parsed,
+                &ResponseLimits::default(),
+                &Timings::default(),
+                args.retries,
+            )
+            .await
+            .map(|html| (SmallGroupUrl::new(parsed), html))
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let small_group = match page {
+            Ok((parsed, html)) => {
+                let parsed = std::panic::catch_unwind(|| parse_small_group(html, &parsed, &path));
+                match parsed {
+                    Ok(small_group) => Ok(small_group),
+                    Err(_) => Err("parser panicked".to_string()),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        match small_group {
+            Ok(small_group) => {
+                fetched += 1;
+                state.failed_pages.retain(|page| page.url != url);
+                state.small_groups.push(small_group);
+            }
+            Err(e) if args.placeholders => {
+                eprintln!("Failed to get {}: {}, adding a placeholder", url, e);
+                placeholders += 1;
+                // the number in the title is the position in the listing, not the group's
+                let title = path.fragments.last().cloned().unwrap_or_default();
+                let (name, _) = parse_small_group_title(&title);
+                state.small_groups.push(SmallGroup {
+                    url,
+                    path,
+                    name,
+                    group_number: None,
+                    appointments: Vec::new(),
+                    appointments_table: None,
+                    my_status: None,
+                    html: None,
+                    warnings: vec![Warning::Placeholder],
+                });
+            }
+            Err(e) => {
+                eprintln!("Failed to get {}: {}", url, e);
+                failed += 1;
+            }
+        }
+    }
+
+    let output = args.output.as_ref().unwrap_or(&args.state);
+    write_snapshot(Path::new(output), &state, format).expect("Failed to write state file");
+    eprintln!(
+        "Fetched {}, added {} placeholders, {} still missing",
+        fetched, placeholders, failed
+    );
+}
@@ -0,0 +1,270 @@
+use std::{
+    collections::BTreeSet,
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use clap::Parser;
+use paul_scrape_rs::{
+    config::{config_path, load_dotenv, Config},
+    diff::diff_semesters,
+    get_semesters,
+    pauline::Semester,
+    sink::slug,
+    validate::validate_semester,
+};
+use reqwest::Url;
+use serde::Serialize;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about = "Watch PAUL for new semesters", long_about = None)]
+struct Args {
+    // base url, falls back to $BASE_URL, then paul-scrape.toml
+    base_url: Option<Url>,
+    // time between checks
+    #[clap(long, default_value = "1h", parse(try_from_str = humantime::parse_duration))]
+    interval: Duration,
+    // semesters seen on earlier checks, the first check only fills it
+    #[clap(long, default_value = "semesters.seen.json")]
+    seen_file: String,
+    // run this shell command for every new semester, with PAUL_SEMESTER set
+    #[clap(long)]
+    on_new: Option<String>,
+    // scrape new semesters with paul-scrape-rs
+    #[clap(long)]
+    scrape_new: bool,
+    // check once and exit, for cron
+    #[clap(long)]
+    once: bool,
+    #[clap(long, default_value_t = 5)]
+    max_redirects: usize,
+    // scrape and convert these semesters again on every check and publish
+    // the result as <publish-dir>/<semester>.json
+    #[clap(long, multiple_occurrences = true)]
+    refresh: Vec<String>,
+    #[clap(long, default_value = "published")]
+    publish_dir: String,
+    // withhold a snapshot changing more than this percentage of the courses,
+    // that is more likely a parser broken by a PAUL change than real edits.
+    // it is kept as <semester>.withheld.json for a look, the previous one stays
+    #[clap(long)]
+    confirm_threshold: Option<f64>,
+    // a snapshot with fewer courses than this percentage of the published
+    // ones fails validation, see paul_scrape_rs::validate
+    #[clap(long, default_value_t = 50.0)]
+    min_course_share: f64,
+}
+
+// printed to stdout as one json line per event
+#[derive(Serialize)]
+struct Event<'a> {
+    event: &'static str,
+    semester: &'a str,
+    url: &'a str,
+    detected: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize)]
+struct PublishEvent<'a> {
+    // published, withheld or rejected
+    event: &'static str,
+    semester: &'a str,
+    // added, removed and changed courses in percent of the published ones
+    changed_percent: f64,
+    // why a rejected snapshot failed validation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    problems: Vec<String>,
+    detected: chrono::DateTime<chrono::Utc>,
+}
+
+const DEFAULT_BASE_URL: &str = "https://paul.uni-paderborn.de";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    // before parsing, BASE_URL may come from .env
+    load_dotenv();
+    let args = Args::parse();
+    let config = Config::load(config_path()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    let base_url = args
+        .base_url
+        .clone()
+        .or_else(|| {
+            env::var("BASE_URL")
+                .ok()
+                .and_then(|url| Url::parse(&url).ok())
+        })
+        .or_else(|| config.base_url.clone())
+        .unwrap_or_else(|| Url::parse(DEFAULT_BASE_URL).unwrap());
+    let client = config.client().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+
+    loop {
+        check(&args, &base_url, &client).await;
+        for semester in &args.refresh {
+            refresh(&args, &base_url, semester);
+        }
+        if args.once {
+            break;
+        }
+        tokio::time::sleep(args.interval).await;
+    }
+}
+
+async fn check(args: &Args, base_url: &Url, client: &reqwest::Client) {
+    let semesters = match get_semesters(client.clone(), base_url, args.max_redirects).await {
+        Ok(semesters) => semesters,
+        Err(e) => {
+            // PAUL being down for a while is no reason to stop watching
+            eprintln!("[{}] Failed to get semesters: {}", chrono::Utc::now(), e);
+            return;
+        }
+    };
+
+    let seen: Option<BTreeSet<String>> = std::fs::read_to_string(&args.seen_file)
+        .ok()
+        .map(|seen| serde_json::from_str(&seen).expect("Failed to parse seen file"));
+    let Some(mut seen) = seen else {
+        // nothing to compare against yet, everything would count as new
+        let seen = semesters
+            .into_iter()
+            .map(|(semester, _)| semester)
+            .collect::<BTreeSet<_>>();
+        eprintln!("Recorded {} semesters in {}", seen.len(), args.seen_file);
+        save_seen(&args.seen_file, &seen);
+        return;
+    };
+
+    for (semester, url) in semesters {
+        if seen.contains(&semester) {
+            continue;
+        }
+        let event = Event {
+            event: "new_semester",
+            semester: &semester,
+            url: url.as_str(),
+            detected: chrono::Utc::now(),
+        };
+        println!("{}", serde_json::to_string(&event).unwrap());
+        if let Some(command) = &args.on_new {
+            run(Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("PAUL_SEMESTER", &semester));
+        }
+        if args.scrape_new {
+            // the scraper is installed next to this binary
+            let scraper = env::current_exe().unwrap().with_file_name("paul-scrape-rs");
+            run(Command::new(scraper).arg(base_url.as_str()).arg(&semester));
+        }
+        // saved per semester, a restart only announces the ones not handled yet
+        seen.insert(semester);
+        save_seen(&args.seen_file, &seen);
+    }
+}
+
+fn run(command: &mut Command) -> bool {
+    match command.status() {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            eprintln!("{:?} exited with {}", command, status);
+            false
+        }
+        Err(e) => {
+            eprintln!("Failed to run {:?}: {}", command, e);
+            false
+        }
+    }
+}
+
+// a broken file is reported and treated like a missing one, watch keeps running
+fn read_semester(path: &Path) -> Option<Semester> {
+    let semester = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&semester) {
+        Ok(semester) => Some(semester),
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn refresh(args: &Args, base_url: &Url, semester: &str) {
+    let slug = slug(semester);
+    let publish_dir = PathBuf::from(&args.publish_dir);
+    let work_dir = publish_dir.join(".work").join(&slug);
+    std::fs::create_dir_all(&work_dir).expect("Failed to create work directory");
+
+    // the tools are installed next to this binary, convertjson reads and writes the working directory.
+    // a scrape exiting with 2 (degraded, see --alert-threshold) is not published either
+    let exe = env::current_exe().unwrap();
+    let scraped = run(Command::new(exe.with_file_name("paul-scrape-rs"))
+        .arg(base_url.as_str())
+        .arg(semester)
+        .arg("--output-dir")
+        .arg(&work_dir));
+    if !scraped || !run(Command::new(exe.with_file_name("convertjson")).current_dir(&work_dir)) {
+        return;
+    }
+    let Some(fresh) = read_semester(&work_dir.join("semester.json")) else {
+        eprintln!("convertjson wrote no semester.json for {}", semester);
+        return;
+    };
+
+    let published_path = publish_dir.join(format!("{}.json", slug));
+    let published = read_semester(&published_path);
+    let changed_percent = match &published {
+        Some(published) => diff_semesters(published, &fresh).changed_share(published),
+        // the first snapshot has nothing to compare against
+        None => 0.0,
+    };
+    let problems = validate_semester(&fresh, published.as_ref(), args.min_course_share)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let withheld = args
+        .confirm_threshold
+        .is_some_and(|threshold| changed_percent > threshold);
+    let event = PublishEvent {
+        event: if !problems.is_empty() {
+            "rejected"
+        } else if withheld {
+            "withheld"
+        } else {
+            "published"
+        },
+        semester,
+        changed_percent,
+        problems,
+        detected: chrono::Utc::now(),
+    };
+    println!("{}", serde_json::to_string(&event).unwrap());
+    if !event.problems.is_empty() {
+        // the published snapshot stays, the rejected one is left in the work directory
+        for problem in &event.problems {
+            eprintln!("Not publishing {}: {}", semester, problem);
+        }
+        return;
+    }
+
+    let target = if withheld {
+        publish_dir.join(format!("{}.withheld.json", slug))
+    } else {
+        published_path
+    };
+    // renamed into place, readers never see half a file
+    let temporary = target.with_extension("json.tmp");
+    std::fs::copy(work_dir.join("semester.json"), &temporary).expect("Failed to copy snapshot");
+    std::fs::rename(&temporary, &target).expect("Failed to publish snapshot");
+}
+
+fn save_seen(path: &str, seen: &BTreeSet<String>) {
+    std::fs::write(path, serde_json::to_string_pretty(seen).unwrap())
+        .expect("Failed to write seen file");
+}
@@ -0,0 +1,54 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+use url::Url;
+
+// fetched pages on disk, keyed by a hash of the url, so repeated runs
+// don't have to hit PAUL for pages that rarely change
+
+#[derive(Clone, Debug)]
+pub struct HtmlCache {
+    pub dir: PathBuf,
+    // older pages are fetched again, no limit if None
+    pub max_age: Option<Duration>,
+}
+
+impl HtmlCache {
+    pub fn new(dir: impl Into<PathBuf>, max_age: Option<Duration>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_age })
+    }
+
+    fn path(&self, url: &Url) -> PathBuf {
+        let hash = format!("{:x}", Sha256::digest(url.as_str().as_bytes()));
+        self.dir.join(format!("{}.html", hash))
+    }
+
+    /// Returns the cached page if there is one that isn't older than `max_age`.
+    pub fn get(&self, url: &Url) -> Option<String> {
+        let path = self.path(url);
+        if let Some(max_age) = self.max_age {
+            let modified = fs::metadata(&path).ok()?.modified().ok()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > max_age {
+                return None;
+            }
+        }
+        fs::read_to_string(path).ok()
+    }
+
+    pub fn put(&self, url: &Url, html: &str) -> std::io::Result<()> {
+        // write and rename, so a crash never leaves half a page behind
+        let path = self.path(url);
+        let partial = path.with_extension("partial");
+        fs::write(&partial, html)?;
+        fs::rename(partial, path)
+    }
+}
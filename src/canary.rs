@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    links::{CourseUrl, SmallGroupUrl, TreeUrl},
+    parse_course_page, parse_small_group, parse_tree_page, Path, Severity, Warning,
+};
+
+// a handful of representative pages whose extracted fields are known, for
+// `healthcheck --canary`. a markup change in PAUL shows up as a mismatch
+// here before a full scrape quietly loses data
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanaryKind {
+    Tree,
+    Course,
+    SmallGroup,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CanaryPage {
+    pub kind: CanaryKind,
+    pub url: Url,
+    // field => value, fields left out aren't compared
+    #[serde(default)]
+    pub expected: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Mismatch {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+fn strict(warnings: &[Warning]) -> Result<(), String> {
+    match warnings
+        .iter()
+        .find(|warning| warning.severity() == Severity::Markup)
+    {
+        Some(warning) => Err(warning.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Parses `html` like the crawler does and returns the fields a canary
+/// compares, or why parsing failed. Markup warnings count as failures.
+pub fn extract_fields(
+    kind: CanaryKind,
+    url: &Url,
+    html: String,
+) -> Result<BTreeMap<String, String>, String> {
+    let parsed = std::panic::catch_unwind(|| {
+        let mut fields = BTreeMap::new();
+        match kind {
+            CanaryKind::Tree => {
+                let (courses, branches, next_page) =
+                    parse_tree_page(html, &TreeUrl::new(url.clone()), &Path::new());
+                fields.insert("courses".to_string(), courses.len().to_string());
+                fields.insert("branches".to_string(), branches.len().to_string());
+                fields.insert("next_page".to_string(), next_page.is_some().to_string());
+                if let Some(course) = courses.first() {
+                    let title = course.path.fragments.last().cloned().unwrap_or_default();
+                    fields.insert("first_course".to_string(), title);
+                }
+            }
+            CanaryKind::Course => {
+                let (course, small_groups) =
+                    parse_course_page(html, &CourseUrl::new(url.clone()), &Path::new());
+                strict(&course.warnings)?;
+                let title = course.path.fragments.last().cloned().unwrap_or_default();
+                fields.insert("title".to_string(), title);
+                fields.insert("instructors".to_string(), course.instructors);
+                fields.insert("ou".to_string(), course.ou.unwrap_or_default());
+                fields.insert(
+                    "appointments".to_string(),
+                    course.appointments.len().to_string(),
+                );
+                fields.insert("small_groups".to_string(), small_groups.len().to_string());
+                fields.insert(
+                    "course_type".to_string(),
+                    course.course_type.unwrap_or_default(),
+                );
+            }
+            CanaryKind::SmallGroup => {
+                let small_group =
+                    parse_small_group(html, &SmallGroupUrl::new(url.clone()), &Path::new());
+                strict(&small_group.warnings)?;
+                let title = small_group
+                    .path
+                    .fragments
+                    .last()
+                    .cloned()
+                    .unwrap_or_default();
+                fields.insert("title".to_string(), title);
+                fields.insert(
+                    "appointments".to_string(),
+                    small_group.appointments.len().to_string(),
+                );
+            }
+        }
+        Ok(fields)
+    });
+    parsed.unwrap_or_else(|_| Err("parser panicked".to_string()))
+}
+
+/// The expected fields of `page` that differ from `actual`.
+pub fn compare(page: &CanaryPage, actual: &BTreeMap<String, String>) -> Vec<Mismatch> {
+    page.expected
+        .iter()
+        .filter(|(field, expected)| actual.get(*field) != Some(expected))
+        .map(|(field, expected)| Mismatch {
+            field: field.clone(),
+            expected: expected.clone(),
+            actual: actual.get(field).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
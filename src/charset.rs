@@ -0,0 +1,64 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+// PAUL mostly sends utf-8, but some pages declare ISO-8859-1 and some mix
+// both. decoding everything as utf-8 turns "Übung" into "�bung"
+
+/// The encoding named by a `charset=` parameter in `text`, a content type
+/// header or the start of a page.
+fn charset_label(text: &[u8]) -> Option<&'static Encoding> {
+    let lowercase = text.to_ascii_lowercase();
+    let start = lowercase
+        .windows(8)
+        .position(|window| window == b"charset=")?
+        + 8;
+    let label = lowercase[start..]
+        .iter()
+        .skip_while(|byte| matches!(byte, b'"' | b'\'' | b' '))
+        .take_while(|byte| {
+            byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b':' | b'.')
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    Encoding::for_label(&label)
+}
+
+/// Decodes a page, trying a byte order mark, the content type header and a
+/// `<meta>` charset in the first kilobyte, in that order.
+///
+/// Utf-8 (also the default) is decoded leniently: bytes that aren't valid
+/// utf-8 are read as windows-1252, which is what ISO-8859-1 means in practice.
+pub fn decode_html(body: &[u8], content_type: Option<&str>) -> String {
+    let declared = Encoding::for_bom(body)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| content_type.and_then(|header| charset_label(header.as_bytes())))
+        .or_else(|| charset_label(&body[..body.len().min(1024)]))
+        .unwrap_or(UTF_8);
+    if declared != UTF_8 {
+        return declared.decode(body).0.into_owned();
+    }
+    decode_mixed(body)
+}
+
+fn decode_mixed(mut body: &[u8]) -> String {
+    let mut text = String::with_capacity(body.len());
+    // a utf-8 bom would otherwise end up in the text
+    if body.starts_with(b"\xEF\xBB\xBF") {
+        body = &body[3..];
+    }
+    loop {
+        match std::str::from_utf8(body) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return text;
+            }
+            Err(e) => {
+                let (valid, rest) = body.split_at(e.valid_up_to());
+                text.push_str(std::str::from_utf8(valid).unwrap());
+                // an incomplete sequence at the end is as broken as an invalid one
+                let invalid = e.error_len().unwrap_or(rest.len());
+                text.push_str(&WINDOWS_1252.decode_without_bom_handling(&rest[..invalid]).0);
+                body = &rest[invalid..];
+            }
+        }
+    }
+}
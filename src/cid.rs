@@ -0,0 +1,167 @@
+use sha2::Digest;
+use uuid::Uuid;
+
+// how convertjson turns a course into the cid of semester.json. downstream
+// systems key their data by it, so the strategies never change their output
+
+pub trait CidStrategy {
+    /// `number` and `name` as split from the course listing, e.g.
+    /// "L.079.05401" and "Grundlagen der Programmierung 1".
+    fn cid(&self, number: &str, name: &str, instructors: &str) -> String;
+}
+
+/// The course number as is, courses sharing it are told apart by --duplicate-cids.
+pub struct CourseNumber;
+
+impl CidStrategy for CourseNumber {
+    fn cid(&self, number: &str, _name: &str, _instructors: &str) -> String {
+        number.to_string()
+    }
+}
+
+/// The course number and 2 hex chars of a hash of name and instructors,
+/// "L.079.05401|3f". What convertjson has always written.
+pub struct NameHash;
+
+impl CidStrategy for NameHash {
+    fn cid(&self, number: &str, name: &str, instructors: &str) -> String {
+        let name_hash = format!(
+            "{:x}",
+            sha2::Sha256::digest(format!("{}{}", name, instructors).as_bytes())
+        );
+        format!("{}|{}", number, &name_hash[..2])
+    }
+}
+
+/// A UUIDv5 of course number and name in `namespace`, for systems expecting uuids.
+pub struct UuidV5 {
+    pub namespace: Uuid,
+}
+
+impl CidStrategy for UuidV5 {
+    fn cid(&self, number: &str, name: &str, _instructors: &str) -> String {
+        Uuid::new_v5(&self.namespace, format!("{}\n{}", number, name).as_bytes()).to_string()
+    }
+}
+
+/// The id PAUL knows a course by, taken from its url so it survives renames.
+/// The session argument of `ARGUMENTS` changes with every visit and is dropped,
+/// `coursedetails:000443,379207311355588,0,0,0,0` is left. Urls without
+/// arguments are kept as they are.
+pub fn event_id(url: &str) -> String {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    let pairs = parsed.query_pairs().collect::<Vec<_>>();
+    let value = |name: &str| {
+        pairs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.to_string())
+    };
+    let (Some(program), Some(arguments)) = (value("PRGNAME"), value("ARGUMENTS")) else {
+        return url.to_string();
+    };
+    let arguments = arguments
+        .split(',')
+        .skip(1)
+        .map(|argument| {
+            argument
+                .trim()
+                .trim_start_matches("-N")
+                .trim_start_matches("-A")
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{}:{}", program.to_lowercase(), arguments)
+}
+
+// the cid an event got the first time it was converted, kept in a sqlite file
+// next to semester.json. strategies may change their output when a course is
+// renamed, the map doesn't. changes are only kept once `save` is called, a
+// conversion that aborts halfway leaves the map as it was
+#[cfg(feature = "sqlite")]
+pub struct IdMap {
+    path: std::path::PathBuf,
+    connection: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl IdMap {
+    pub fn open(path: impl Into<std::path::PathBuf>) -> Result<Self, crate::error::Error> {
+        let path = path.into();
+        let connection = rusqlite::Connection::open(&path).map_err(|e| map_error(&path, e))?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS cids (
+                    event_id TEXT PRIMARY KEY,
+                    cid TEXT NOT NULL UNIQUE,
+                    first_seen TEXT NOT NULL
+                );",
+            )
+            .map_err(|e| map_error(&path, e))?;
+        connection
+            .execute_batch("BEGIN")
+            .map_err(|e| map_error(&path, e))?;
+        Ok(Self { path, connection })
+    }
+
+    /// The cid `event_id` was given before, or `derived` recorded as its cid.
+    /// A derived cid another event already has gets ":1", ":2", ... appended.
+    pub fn cid(&self, event_id: &str, derived: &str) -> Result<String, crate::error::Error> {
+        use rusqlite::OptionalExtension;
+        let error = |e: rusqlite::Error| map_error(&self.path, e);
+        let known = self
+            .connection
+            .query_row(
+                "SELECT cid FROM cids WHERE event_id = ?1",
+                [event_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(error)?;
+        if let Some(cid) = known {
+            return Ok(cid);
+        }
+        let taken = |cid: &str| {
+            self.connection
+                .query_row("SELECT 1 FROM cids WHERE cid = ?1", [cid], |_| Ok(()))
+                .optional()
+                .map(|row| row.is_some())
+        };
+        let mut cid = derived.to_string();
+        let mut i = 0;
+        while taken(&cid).map_err(error)? {
+            i += 1;
+            cid = format!("{}:{}", derived, i);
+        }
+        self.connection
+            .execute(
+                "INSERT INTO cids (event_id, cid, first_seen) VALUES (?1, ?2, ?3)",
+                [event_id, &cid, &chrono::Utc::now().to_rfc3339()],
+            )
+            .map_err(error)?;
+        Ok(cid)
+    }
+
+    pub fn save(self) -> Result<(), crate::error::Error> {
+        self.connection
+            .execute_batch("COMMIT")
+            .map_err(|e| map_error(&self.path, e))
+    }
+
+    pub fn len(&self) -> Result<usize, crate::error::Error> {
+        self.connection
+            .query_row("SELECT COUNT(*) FROM cids", [], |row| row.get(0))
+            .map_err(|e| map_error(&self.path, e))
+    }
+
+    pub fn is_empty(&self) -> Result<bool, crate::error::Error> {
+        self.len().map(|len| len == 0)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn map_error(path: &std::path::Path, e: impl std::fmt::Display) -> crate::error::Error {
+    crate::error::Error::Output(format!("{}: {}", path.display(), e))
+}
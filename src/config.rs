@@ -0,0 +1,289 @@
+use std::path::PathBuf;
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, FROM},
+    Certificate, Client, ClientBuilder,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{diff::DiffFilter, error::Error, AppointmentColumns};
+
+// paul-scrape.toml, written by `paul-scrape-rs init` and read on every run.
+// command line arguments and environment variables take precedence over it.
+// without one in the working directory ~/.config/paul-scrape/config.toml is used
+
+pub const CONFIG_FILE: &str = "paul-scrape.toml";
+
+/// `paul-scrape` in the XDG config directory, usually `~/.config/paul-scrape`.
+pub fn user_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("paul-scrape"))
+}
+
+/// `paul-scrape` in the XDG cache directory, usually `~/.cache/paul-scrape`.
+pub fn user_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("paul-scrape"))
+}
+
+/// The config file to read, `paul-scrape.toml` in the working directory if it
+/// exists and the user's config.toml otherwise.
+pub fn config_path() -> PathBuf {
+    let local = PathBuf::from(CONFIG_FILE);
+    if local.exists() {
+        return local;
+    }
+    user_config_dir()
+        .map(|dir| dir.join("config.toml"))
+        .filter(|path| path.exists())
+        .unwrap_or(local)
+}
+
+/// Loads `.env` from the working directory (or a parent) into the environment,
+/// variables that are already set win.
+pub fn load_dotenv() {
+    // a missing .env is the normal case
+    if let Err(e) = dotenvy::dotenv() {
+        if !e.not_found() {
+            eprintln!("Ignoring .env: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub base_url: Option<Url>,
+    // comma separated, like the semester argument
+    pub semester: Option<String>,
+    pub requests_per_second: Option<u64>,
+    // directory for the outputs
+    pub output_dir: Option<String>,
+    pub cache_dir: Option<String>,
+    // [http] table, for experimenting with keep-alive and http/2 against PAUL
+    #[serde(default, skip_serializing_if = "HttpConfig::is_default")]
+    pub http: HttpConfig,
+    // [politeness] table, how the crawler identifies itself to PAUL
+    #[serde(default, skip_serializing_if = "Politeness::is_default")]
+    pub politeness: Politeness,
+    // [tls] table, --ca-cert and --insecure add to it
+    #[serde(default, skip_serializing_if = "TlsConfig::is_default")]
+    pub tls: TlsConfig,
+    // [appointment_columns] table, the header texts of the appointment
+    // columns, for when PAUL renames one before a release catches up
+    #[serde(default, skip_serializing_if = "AppointmentColumns::is_default")]
+    pub appointment_columns: AppointmentColumns,
+    // [[webhooks]] tables, compare --webhooks posts the matching changes to each
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<Webhook>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpVersion {
+    // whatever the connection negotiates. the default native-tls backend
+    // doesn't offer h2 via alpn, so against https this is http/1.1
+    #[default]
+    Auto,
+    Http1,
+    // http/2 without negotiation, the server has to speak it
+    Http2,
+}
+
+// everything left out keeps reqwest's default
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpConfig {
+    #[serde(default)]
+    pub version: HttpVersion,
+    // idle connections kept per host, 0 opens a new connection per request
+    pub pool_max_idle_per_host: Option<usize>,
+    // e.g. "90s", how long idle connections are kept
+    pub pool_idle_timeout: Option<String>,
+    // e.g. "60s", tcp keep-alive probes on open connections
+    pub tcp_keepalive: Option<String>,
+    // false asks for uncompressed responses, to look at what PAUL sends.
+    // gzip, deflate and brotli are accepted otherwise
+    pub compression: Option<bool>,
+}
+
+impl HttpConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Applies the settings to a client builder.
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, Error> {
+        let duration = |text: &str| {
+            humantime::parse_duration(text)
+                .map_err(|e| Error::Config(format!("[http] {}: {}", text, e)))
+        };
+        let mut builder = match self.version {
+            HttpVersion::Auto => builder,
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Http2 => builder.http2_prior_knowledge(),
+        };
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(timeout) = &self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(duration(timeout)?);
+        }
+        if let Some(keepalive) = &self.tcp_keepalive {
+            builder = builder.tcp_keepalive(duration(keepalive)?);
+        }
+        // reqwest is built without its decompression, fetch_page_timed
+        // decompresses itself to count the bytes on the wire
+        if self.compression != Some(false) {
+            builder = builder.default_headers(HeaderMap::from_iter([(
+                ACCEPT_ENCODING,
+                HeaderValue::from_static("gzip, deflate, br"),
+            )]));
+        }
+        Ok(builder)
+    }
+}
+
+// some university IT policies want crawlers to say who runs them:
+//
+// [politeness]
+// contact = "fachschaft@example.org"
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Politeness {
+    // email address or url of whoever runs the crawler, added to the User-Agent
+    pub contact: Option<String>,
+    // the From header, defaults to the contact if that is an email address
+    pub from: Option<String>,
+    // replaces the generated User-Agent
+    pub user_agent: Option<String>,
+}
+
+impl Politeness {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The User-Agent to send, e.g. `paul-scrape-rs/0.1.0 (+mailto:fachschaft@example.org)`.
+    pub fn user_agent(&self) -> String {
+        if let Some(user_agent) = &self.user_agent {
+            return user_agent.clone();
+        }
+        let tool = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        match &self.contact {
+            Some(contact) if contact.contains('@') && !contact.contains("://") => {
+                format!("{} (+mailto:{})", tool, contact)
+            }
+            Some(contact) => format!("{} (+{})", tool, contact),
+            None => tool,
+        }
+    }
+
+    /// The From header to send, if any.
+    pub fn from(&self) -> Option<&str> {
+        self.from.as_deref().or_else(|| {
+            self.contact
+                .as_deref()
+                .filter(|contact| contact.contains('@') && !contact.contains("://"))
+        })
+    }
+
+    /// Sets the User-Agent and From headers of a client builder.
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, Error> {
+        let header = |text: &str| {
+            HeaderValue::from_str(text)
+                .map_err(|e| Error::Config(format!("[politeness] {}: {}", text, e)))
+        };
+        let mut headers = HeaderMap::new();
+        if let Some(from) = self.from() {
+            headers.insert(FROM, header(from)?);
+        }
+        Ok(builder
+            .user_agent(header(&self.user_agent())?)
+            .default_headers(headers))
+    }
+}
+
+// for a tls intercepting proxy in front of PAUL:
+//
+// [tls]
+// ca_cert = "proxy.pem"
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    // pem file with extra root certificates
+    pub ca_cert: Option<String>,
+    // accept any certificate. anyone on the way can then read and change
+    // the pages, only for debugging
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+impl TlsConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Adds the extra root certificates to a client builder, or turns off
+    /// certificate checks.
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, Error> {
+        let mut builder = builder;
+        if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read(path).map_err(|e| Error::Config(format!("{}: {}", path, e)))?;
+            let certificate = Certificate::from_pem(&pem)
+                .map_err(|e| Error::Config(format!("{}: {}", path, e)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        Ok(builder.danger_accept_invalid_certs(self.insecure))
+    }
+}
+
+/// A client builder with the `[http]`, `[politeness]` and `[tls]` settings applied.
+///
+/// Every client talking to PAUL starts from this, so all of them identify
+/// themselves and trust the same certificates.
+pub fn client_builder(
+    http: &HttpConfig,
+    politeness: &Politeness,
+    tls: &TlsConfig,
+) -> Result<ClientBuilder, Error> {
+    let builder = http.apply(reqwest::Client::builder())?;
+    tls.apply(politeness.apply(builder)?)
+}
+
+// e.g. a Fachschaft only following its own courses:
+//
+// [[webhooks]]
+// url = "https://example.org/paul-changes"
+// ous = ["Institut für Informatik"]
+// changes = ["added", "changed"]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: Url,
+    #[serde(flatten)]
+    pub filter: DiffFilter,
+}
+
+impl Config {
+    /// [`client_builder`] with the settings of this config.
+    pub fn client_builder(&self) -> Result<ClientBuilder, Error> {
+        client_builder(&self.http, &self.politeness, &self.tls)
+    }
+
+    /// A client built from [`Config::client_builder`] without further settings.
+    pub fn client(&self) -> Result<Client, Error> {
+        Ok(self.client_builder()?.build()?)
+    }
+
+    /// Loads a config file, a missing file gives the empty config.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(Error::Config(format!("{}: {}", path.display(), e))),
+        };
+        toml::from_str(&text).map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let text = toml::to_string(self).map_err(|e| Error::Config(e.to_string()))?;
+        std::fs::write(path, text).map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    sync::RwLock,
+};
+
+use reqwest::header::HeaderValue;
+use url::Url;
+
+// cookie jar for the crawler, can be saved to disk so the next run
+// reuses the PAUL session instead of going through the redirects again
+
+#[derive(Debug, Default)]
+pub struct CookieJar(RwLock<cookie_store::CookieStore>);
+
+impl CookieJar {
+    /// Loads a jar saved by [`CookieJar::save`], a missing file gives an empty jar.
+    pub fn load(path: &str) -> Result<Self, cookie_store::Error> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        // expired cookies are dropped here
+        let store = cookie_store::CookieStore::load_json(BufReader::new(file))?;
+        Ok(Self(RwLock::new(store)))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), cookie_store::Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        // the PAUL session cookie has no expiry, keep it anyway
+        self.0
+            .read()
+            .unwrap()
+            .save_incl_expired_and_nonpersistent_json(&mut writer)
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies = cookie_headers
+            .filter_map(|header| header.to_str().ok())
+            .filter_map(|header| cookie_store::RawCookie::parse(header.to_string()).ok())
+            .collect::<Vec<_>>();
+        self.0
+            .write()
+            .unwrap()
+            .store_response_cookies(cookies.into_iter(), url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .0
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
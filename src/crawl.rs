@@ -0,0 +1,559 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use futures::{Stream, StreamExt};
+use reqwest::Client;
+use scraper::Html;
+use tokio::time::Instant;
+pub use tokio_util::sync::CancellationToken;
+use url::Url;
+
+use crate::{
+    combined_small_groups_link_document,
+    config::{client_builder, HttpConfig, Politeness, TlsConfig},
+    error::{Error, ErrorKind},
+    excerpt::{page_excerpt, COURSE_PAGE, SMALL_GROUP_LISTING, TREE_PAGE},
+    fetch::{fetch_page_retrying, ResponseLimits, Timings},
+    get_semesters,
+    links::{CourseUrl, LinkClassifier, SmallGroupUrl, TreeUrl},
+    pacing::Pacer,
+    parse_combined_small_groups_with, parse_course_document, parse_small_group_document,
+    parse_small_group_listing, parse_small_group_listing_document, parse_tree_page_document,
+    postprocess::{apply_post_processors, CoursePostProcessor},
+    progress::{FacultyEvent, FacultyTally, NoProgress, ProgressKind, ProgressSink},
+    sink::{AsyncSink, Item},
+    AppointmentColumns, Course, FailedPage, Path, PathSegmentKind, StateSerializable, TreePage,
+};
+
+// a plain crawl of one semester for embedding the scraper in other programs,
+// without the progress bars, deadlines and checkpoints of the command line crawler
+
+#[derive(Clone, Debug)]
+pub struct ScrapeConfig {
+    pub base_url: Url,
+    // as listed on the main page, e.g. "Sommer 2023"
+    pub semester: String,
+    pub max_redirects: usize,
+    pub response_limits: ResponseLimits,
+    // pages fetched at the same time
+    pub concurrency: usize,
+    // how fast requests are sent, whatever the concurrency
+    pub pacer: Pacer,
+    // extra attempts for pages failing with a retryable error
+    pub retries: u32,
+    // tree branches nested deeper than this (the semester is depth 1) are
+    // not followed but recorded in failed_pages
+    pub max_depth: usize,
+    // which listing links are categories and which are courses, links to
+    // other kinds of pages are not followed
+    pub classifier: LinkClassifier,
+    // the header texts of the appointment columns
+    pub appointment_columns: AppointmentColumns,
+    // run on every course after it is parsed, in order
+    pub post_processors: Arc<Vec<Box<dyn CoursePostProcessor>>>,
+    // like the [http], [politeness] and [tls] tables of paul-scrape.toml
+    pub http: HttpConfig,
+    pub politeness: Politeness,
+    pub tls: TlsConfig,
+}
+
+impl ScrapeConfig {
+    pub fn new(base_url: Url, semester: impl Into<String>) -> Self {
+        Self {
+            base_url,
+            semester: semester.into(),
+            max_redirects: 5,
+            response_limits: ResponseLimits::default(),
+            concurrency: 8,
+            // like the command line crawler's default
+            pacer: Pacer::constant(20.0),
+            retries: 3,
+            max_depth: 20,
+            classifier: LinkClassifier::default(),
+            appointment_columns: AppointmentColumns::default(),
+            post_processors: Arc::new(Vec::new()),
+            http: HttpConfig::default(),
+            politeness: Politeness::default(),
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+// a page waiting in the frontier, like the command line crawler's queue entries
+#[derive(Debug)]
+enum Page {
+    Tree(TreeUrl, Path),
+    Course(CourseUrl, Path),
+    SmallGroup(SmallGroupUrl, Path),
+    // course url, page url
+    SmallGroupListing(CourseUrl, Url, Path),
+    // a page with every small group of a course and the links it replaces
+    SmallGroupsCombined(Url, Vec<(SmallGroupUrl, Path)>, Path),
+}
+
+impl Page {
+    fn url(&self) -> &Url {
+        match self {
+            Page::Tree(url, _) => url,
+            Page::Course(url, _) => url,
+            Page::SmallGroup(url, _) => url,
+            Page::SmallGroupListing(_, url, _) => url,
+            Page::SmallGroupsCombined(url, _, _) => url,
+        }
+    }
+
+    fn progress_kind(&self) -> ProgressKind {
+        match self {
+            Page::Tree(_, _) => ProgressKind::Tree,
+            _ => ProgressKind::Leaf,
+        }
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Page::Tree(_, path)
+            | Page::Course(_, path)
+            | Page::SmallGroup(_, path)
+            | Page::SmallGroupListing(_, _, path)
+            | Page::SmallGroupsCombined(_, _, path) => path,
+        }
+    }
+
+    // where the parser starts, for the excerpt of a page it fails on
+    fn expected(&self) -> &'static [&'static str] {
+        match self {
+            Page::Tree(_, _) => TREE_PAGE,
+            Page::Course(_, _) | Page::SmallGroup(_, _) => COURSE_PAGE,
+            Page::SmallGroupListing(_, _, _) | Page::SmallGroupsCombined(_, _, _) => {
+                SMALL_GROUP_LISTING
+            }
+        }
+    }
+
+    // the small group pages to fetch instead if a combined page fails
+    fn fallback(&self) -> Vec<Page> {
+        match self {
+            Page::SmallGroupsCombined(_, links, _) => links
+                .iter()
+                .map(|(url, path)| Page::SmallGroup(url.clone(), path.clone()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Crawls every course and small group of a semester.
+///
+/// Pages that can't be fetched or parsed end up in `failed_pages`, network
+/// errors included. Only failing to find the semester aborts the crawl.
+pub async fn scrape_semester(config: &ScrapeConfig) -> Result<StateSerializable, Error> {
+    scrape_semester_cancellable(config, &CancellationToken::new()).await
+}
+
+/// Like [`scrape_semester`], stopping once `cancel` is cancelled.
+///
+/// Pages in flight are dropped and everything parsed until then is returned.
+pub async fn scrape_semester_cancellable(
+    config: &ScrapeConfig,
+    cancel: &CancellationToken,
+) -> Result<StateSerializable, Error> {
+    scrape_semester_with(config, cancel, &NoProgress).await
+}
+
+/// Like [`scrape_semester_cancellable`], reporting every queued and fetched page to `progress`.
+pub async fn scrape_semester_with(
+    config: &ScrapeConfig,
+    cancel: &CancellationToken,
+    progress: &dyn ProgressSink,
+) -> Result<StateSerializable, Error> {
+    let mut state = StateSerializable {
+        semester: config.semester.clone(),
+        start_time: chrono::Utc::now(),
+        courses: Vec::new(),
+        small_groups: Vec::new(),
+        failed_pages: Vec::new(),
+    };
+    scrape_semester_into(config, cancel, progress, &mut state).await?;
+    Ok(state)
+}
+
+/// Like [`scrape_semester_with`], handing every course, small group and failed
+/// page to `sink` as soon as it is parsed instead of collecting them.
+///
+/// The crawl waits for each write, a slow sink slows it down. Courses whose
+/// small groups are listed on several pages are written after the last one.
+pub async fn scrape_semester_into(
+    config: &ScrapeConfig,
+    cancel: &CancellationToken,
+    progress: &dyn ProgressSink,
+    sink: &mut dyn AsyncSink,
+) -> Result<(), Error> {
+    let client = client_builder(&config.http, &config.politeness, &config.tls)?.build()?;
+    let (semester, url) = get_semesters(client.clone(), &config.base_url, config.max_redirects)
+        .await?
+        .into_iter()
+        .find(|(semester, _)| *semester == config.semester)
+        .ok_or_else(|| Error::UnknownSemester(config.semester.clone()))?;
+    let path = Path::new().push_segment(PathSegmentKind::Semester, semester, Some(&url));
+    let mut seen = HashSet::from([url.clone()]);
+    let faculties = FacultyTally::default();
+    progress.queued(ProgressKind::Tree, &config.semester);
+    let mut pages = vec![Page::Tree(TreeUrl::new(url), path)];
+    // courses waiting for more small group listing pages, by url
+    let mut pending = HashMap::new();
+    let pace = Pace::new(&config.pacer);
+
+    // breadth first, one level of the tree at a time
+    while !pages.is_empty() && !cancel.is_cancelled() {
+        let fetched = futures::stream::iter(pages.drain(..))
+            .map(|page| {
+                let (client, faculties, pace) = (&client, &faculties, &pace);
+                async move {
+                    pace.wait(page.progress_kind() == ProgressKind::Leaf).await;
+                    progress.started(page.progress_kind());
+                    faculties.record(page.path(), FacultyEvent::Started, progress);
+                    let html = fetch_page_retrying(
+                        client,
+                        page.url(),
+                        &config.response_limits,
+                        &Timings::default(),
+                        config.retries,
+                    )
+                    .await;
+                    pace.record(html.is_ok());
+                    (page, html)
+                }
+            })
+            .buffer_unordered(config.concurrency.max(1))
+            .take_until(cancel.cancelled())
+            .collect::<Vec<_>>()
+            .await;
+        for (page, html) in fetched {
+            let mut ready = Vec::new();
+            let next = match html {
+                Ok(html) => {
+                    let parsed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        parse_page(&page, &html, config, &mut pending, &mut seen, &mut ready)
+                    }));
+                    parsed.unwrap_or_else(|panic| {
+                        ready.push(Item::FailedPage(parse_failure(&page, &html, panic)));
+                        page.fallback()
+                    })
+                }
+                Err(e) => {
+                    ready.push(Item::FailedPage(failed_page(
+                        &page,
+                        e.kind(),
+                        e.to_string(),
+                    )));
+                    page.fallback()
+                }
+            };
+            // pages linked from several places are only fetched once
+            for page in next {
+                if matches!(page, Page::Tree(_, _))
+                    && page.path().fragments.len() > config.max_depth
+                {
+                    ready.push(Item::FailedPage(failed_page(
+                        &page,
+                        ErrorKind::Skipped,
+                        format!("deeper than max depth {}", config.max_depth),
+                    )));
+                } else if seen.insert(page.url().clone()) {
+                    progress.queued(
+                        page.progress_kind(),
+                        page.path().fragments.last().map_or("", String::as_str),
+                    );
+                    faculties.record(page.path(), FacultyEvent::Queued, progress);
+                    pages.push(page);
+                }
+            }
+            for item in ready {
+                match &item {
+                    Item::Course(course) => {
+                        faculties.record(&course.path, FacultyEvent::Course, progress)
+                    }
+                    Item::FailedPage(failed) => {
+                        faculties.record(&failed.path, FacultyEvent::Failed, progress)
+                    }
+                    Item::SmallGroup(_) => {}
+                }
+                sink.write(&config.semester, item).await?;
+            }
+        }
+    }
+    // listing pages that failed or were cancelled, the course is still worth having
+    for (_, course) in pending {
+        faculties.record(&course.path, FacultyEvent::Course, progress);
+        sink.write(&config.semester, Item::Course(Box::new(course)))
+            .await?;
+    }
+    progress.finish();
+    sink.finish().await
+}
+
+/// Fetches the given course pages without walking the tree, e.g. to refresh
+/// the courses of an earlier shallow crawl.
+///
+/// Courses come in the order of `urls`, with the small groups of every
+/// listing page but without fetching the groups themselves. Their paths only
+/// hold the course, there is no tree to take the categories from. Of
+/// `config` only the limits, retries, concurrency, appointment columns and
+/// post processors are used. A page that can't be fetched or parsed is a [`FailedPage`],
+/// network errors included, the other courses are still fetched.
+pub fn scrape_courses<'a>(
+    client: &'a Client,
+    urls: Vec<Url>,
+    config: &'a ScrapeConfig,
+) -> impl Stream<Item = Result<Course, FailedPage>> + 'a {
+    let pace = Arc::new(Pace::new(&config.pacer));
+    futures::stream::iter(urls)
+        .map(move |url| {
+            let pace = pace.clone();
+            async move { scrape_course(client, CourseUrl::new(url), config, &pace).await }
+        })
+        .buffered(config.concurrency.max(1))
+}
+
+// spaces out the requests of concurrent fetches as the pacer says
+struct Pace {
+    // the pacer and when the next request may start
+    state: Mutex<(Pacer, Instant)>,
+}
+
+impl Pace {
+    fn new(pacer: &Pacer) -> Self {
+        Self {
+            state: Mutex::new((pacer.clone(), Instant::now())),
+        }
+    }
+
+    async fn wait(&self, leaves_only: bool) {
+        let start = {
+            let mut state = self.state.lock().unwrap();
+            let (pacer, next) = &mut *state;
+            let start = (*next).max(Instant::now());
+            *next = start + pacer.delay(leaves_only);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+
+    fn record(&self, ok: bool) {
+        self.state.lock().unwrap().0.record(ok);
+    }
+}
+
+async fn scrape_course(
+    client: &Client,
+    url: CourseUrl,
+    config: &ScrapeConfig,
+    pace: &Pace,
+) -> Result<Course, FailedPage> {
+    let fetch = |url: Url| async move {
+        pace.wait(true).await;
+        let html = fetch_page_retrying(
+            client,
+            &url,
+            &config.response_limits,
+            &Timings::default(),
+            config.retries,
+        )
+        .await;
+        pace.record(html.is_ok());
+        html
+    };
+    let page = Page::Course(url.clone(), Path::new());
+    let failed = |kind, error| failed_page(&page, kind, error);
+    let html = fetch(url.as_url().clone())
+        .await
+        .map_err(|e| failed(e.kind(), e.to_string()))?;
+    let columns = &config.appointment_columns;
+    let classifier = &config.classifier;
+    let parsed = std::panic::catch_unwind(|| {
+        let document = Html::parse_document(&html);
+        let (_, next_listing) = parse_small_group_listing_document(&document, &url, &Path::new());
+        let (course, _) = parse_course_document(&document, &url, &Path::new(), columns, classifier);
+        (course, next_listing)
+    });
+    let (mut course, mut next_listing) =
+        parsed.map_err(|panic| parse_failure(&page, &html, panic))?;
+    // a listing page that fails leaves the course with the groups found so far
+    while let Some(listing) = next_listing.take() {
+        let Ok(html) = fetch(listing.clone()).await else {
+            break;
+        };
+        let (small_groups, next) = parse_small_group_listing(&html, &listing, &course.path);
+        course
+            .small_groups
+            .extend(small_groups.iter().map(|(url, _)| url.as_str().to_string()));
+        next_listing = next;
+    }
+    apply_post_processors(&config.post_processors, &mut course);
+    Ok(course)
+}
+
+fn failed_page(page: &Page, kind: ErrorKind, error: String) -> FailedPage {
+    FailedPage {
+        url: page.url().to_string(),
+        path: page.path().clone(),
+        error,
+        kind: Some(kind),
+        html: None,
+        excerpt: None,
+    }
+}
+
+// the parsers panic on unexpected markup
+fn parse_failure(page: &Page, html: &str, panic: Box<dyn std::any::Any + Send>) -> FailedPage {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "parser panicked".to_string());
+    FailedPage {
+        excerpt: Some(page_excerpt(html, page.expected())),
+        ..failed_page(page, ErrorKind::Parse, message)
+    }
+}
+
+fn parse_page(
+    page: &Page,
+    html: &str,
+    config: &ScrapeConfig,
+    pending: &mut HashMap<String, Course>,
+    seen: &mut HashSet<Url>,
+    ready: &mut Vec<Item>,
+) -> Vec<Page> {
+    let mut next = Vec::new();
+    match page {
+        Page::Tree(url, path) => {
+            let TreePage {
+                courses,
+                branches,
+                next_page,
+                ..
+            } = parse_tree_page_document(
+                &Html::parse_document(html),
+                url,
+                path,
+                &config.classifier,
+            );
+            next.extend(
+                branches
+                    .into_iter()
+                    .map(|(url, path)| Page::Tree(url, path)),
+            );
+            next.extend(
+                courses
+                    .into_iter()
+                    .map(|course| Page::Course(course.url, course.path)),
+            );
+            next.extend(next_page.map(|url| Page::Tree(url, path.clone())));
+        }
+        Page::Course(url, path) => {
+            let document = Html::parse_document(html);
+            let (_, next_listing) = parse_small_group_listing_document(&document, url, path);
+            let combined = combined_small_groups_link_document(&document, url);
+            let (mut course, small_groups) = parse_course_document(
+                &document,
+                url,
+                path,
+                &config.appointment_columns,
+                &config.classifier,
+            );
+            apply_post_processors(&config.post_processors, &mut course);
+            if next_listing.is_some() {
+                pending.insert(course.url.clone(), course);
+            } else {
+                ready.push(Item::Course(Box::new(course)));
+            }
+            // one request for all groups instead of one per group
+            match combined.filter(|_| small_groups.len() > 1) {
+                Some(combined) => next.push(Page::SmallGroupsCombined(
+                    combined,
+                    small_groups,
+                    path.clone(),
+                )),
+                None => next.extend(
+                    small_groups
+                        .into_iter()
+                        .map(|(url, path)| Page::SmallGroup(url, path)),
+                ),
+            }
+            next.extend(
+                next_listing.map(|next| Page::SmallGroupListing(url.clone(), next, path.clone())),
+            );
+        }
+        Page::SmallGroupListing(course_url, url, path) => {
+            let (small_groups, next_listing) = parse_small_group_listing(html, url, path);
+            if let Some(course) = pending.get_mut(course_url.as_str()) {
+                course
+                    .small_groups
+                    .extend(small_groups.iter().map(|(url, _)| url.as_str().to_string()));
+            }
+            if next_listing.is_none() {
+                ready.extend(
+                    pending
+                        .remove(course_url.as_str())
+                        .map(|course| Item::Course(Box::new(course))),
+                );
+            }
+            next.extend(
+                small_groups
+                    .into_iter()
+                    .map(|(url, path)| Page::SmallGroup(url, path)),
+            );
+            next.extend(
+                next_listing
+                    .map(|next| Page::SmallGroupListing(course_url.clone(), next, path.clone())),
+            );
+        }
+        Page::SmallGroup(url, path) => {
+            ready.push(Item::SmallGroup(parse_small_group_document(
+                &Html::parse_document(html),
+                url,
+                path,
+                &config.appointment_columns,
+            )));
+        }
+        Page::SmallGroupsCombined(url, links, path) => {
+            let mut small_groups =
+                parse_combined_small_groups_with(html, url, path, &config.appointment_columns);
+            small_groups.retain(|small_group| {
+                links
+                    .iter()
+                    .any(|(link, _)| link.as_str() == small_group.url)
+            });
+            // groups the page doesn't show are fetched one by one
+            next.extend(
+                links
+                    .iter()
+                    .filter(|(link, _)| {
+                        !small_groups
+                            .iter()
+                            .any(|small_group| small_group.url == link.as_str())
+                    })
+                    .map(|(url, path)| Page::SmallGroup(url.clone(), path.clone())),
+            );
+            // a group already fetched through another link isn't written twice
+            small_groups.retain(|small_group| {
+                Url::parse(&small_group.url).is_ok_and(|url| seen.insert(url))
+            });
+            ready.extend(small_groups.into_iter().map(Item::SmallGroup));
+        }
+    }
+    next
+}
+
+/// Blocking version of [`scrape_semester`] for programs without a tokio runtime.
+#[cfg(feature = "blocking")]
+pub fn scrape_semester_blocking(config: &ScrapeConfig) -> Result<StateSerializable, Error> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(Error::Runtime)?
+        .block_on(scrape_semester(config))
+}
@@ -0,0 +1,54 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+// PAUL shows dates like "Di, 18. Apr. 2023" and times like "11:00",
+// english pages use english month abbreviations
+
+pub(crate) fn parse_month(month: &str) -> Option<u32> {
+    // month_dict = {
+    //     'Jan': 1, 'Feb': 2, 'Mrz': 3, 'Mär': 3, 'Apr': 4, 'Mai': 5, 'Jun': 6, 'Jul': 7, 'Aug': 8, 'Sep': 9, 'Okt': 10,
+    //     'Nov': 11, 'Dez': 12
+    // }
+    match month.replace('.', "").as_str() {
+        "Jan" => Some(1),
+        "Feb" => Some(2),
+        "Mrz" => Some(3),
+        "Mär" => Some(3),
+        "Mar" => Some(3),
+        "Apr" => Some(4),
+        "Mai" => Some(5),
+        "May" => Some(5),
+        "Jun" => Some(6),
+        "Jul" => Some(7),
+        "Aug" => Some(8),
+        "Sep" => Some(9),
+        "Okt" => Some(10),
+        "Oct" => Some(10),
+        "Nov" => Some(11),
+        "Dez" => Some(12),
+        "Dec" => Some(12),
+        _ => None,
+    }
+}
+
+pub fn parse_date(date_str: &str) -> Option<NaiveDate> {
+    // split_date = date_str.strip().split(" ")
+    // day = int(split_date[1].replace(".", ""))
+    // month = month_dict[split_date[2].replace(".", "")]
+    // year = int(split_date[3])
+    let split_date = date_str.split(' ').collect::<Vec<&str>>();
+    let day = split_date.get(1)?.replace('.', "").parse::<u32>().ok()?;
+    let month = parse_month(split_date.get(2)?)?;
+    let year = split_date.get(3)?.parse::<i32>().ok()?;
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+    // if time == '24:00':
+    //     time = '23:59'
+    let time = if time == "24:00" { "23:59" } else { time };
+    NaiveTime::parse_from_str(time, "%H:%M").ok()
+}
+
+pub fn parse_datetime(date_str: &str, time: &str) -> Option<NaiveDateTime> {
+    Some(parse_date(date_str)?.and_time(parse_time(time)?))
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pauline::{PaulineAppointment, PaulineCourse, PaulineSmallGroup, Semester};
+
+// differences between two semester.json files, courses are matched by cid
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CourseChange {
+    pub cid: String,
+    pub name: String,
+    pub ou: Option<String>,
+    pub changes: Vec<FieldChange>,
+    // whether PAUL's "last modified" moved, none if either side doesn't have it.
+    // false means the change comes from scraping, not from an edit in PAUL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_updated: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SemesterDiff {
+    pub added: Vec<PaulineCourse>,
+    pub removed: Vec<PaulineCourse>,
+    pub changed: Vec<CourseChange>,
+}
+
+impl SemesterDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Added, removed and changed courses in percent of the `old` semester's courses.
+    pub fn changed_share(&self, old: &Semester) -> f64 {
+        let changed = self.added.len() + self.removed.len() + self.changed.len();
+        100.0 * changed as f64 / old.courses.len().max(1) as f64
+    }
+
+    /// The part of the diff `filter` subscribes to.
+    pub fn filtered(&self, filter: &DiffFilter) -> SemesterDiff {
+        let courses = |kind: ChangeKind, courses: &[PaulineCourse]| {
+            courses
+                .iter()
+                .filter(|course| filter.matches(kind, &course.cid, &course.ou))
+                .cloned()
+                .collect()
+        };
+        SemesterDiff {
+            added: courses(ChangeKind::Added, &self.added),
+            removed: courses(ChangeKind::Removed, &self.removed),
+            changed: self
+                .changed
+                .iter()
+                .filter(|course| filter.matches(ChangeKind::Changed, &course.cid, &course.ou))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+// which changes a subscriber wants, an empty list doesn't restrict anything.
+// cids match exactly or by their course number, which stays the same when
+// the name hash changes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ous: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<ChangeKind>,
+}
+
+impl DiffFilter {
+    pub fn matches(&self, kind: ChangeKind, cid: &str, ou: &Option<String>) -> bool {
+        (self.changes.is_empty() || self.changes.contains(&kind))
+            && (self.ous.is_empty() || ou.as_ref().is_some_and(|ou| self.ous.contains(ou)))
+            && (self.cids.is_empty()
+                || self
+                    .cids
+                    .iter()
+                    .any(|wanted| wanted == cid || wanted == course_number(cid)))
+    }
+}
+
+fn format_appointment(appointment: &PaulineAppointment) -> String {
+    format!(
+        "{} - {} {}",
+        appointment.start_time, appointment.end_time, appointment.room
+    )
+}
+
+fn format_small_group(small_group: &PaulineSmallGroup) -> String {
+    format!(
+        "{} ({} appointments)",
+        small_group.name,
+        small_group.appointments.len()
+    )
+}
+
+fn diff_lists<T: Ord>(
+    field: &str,
+    old: &[T],
+    new: &[T],
+    format: impl Fn(&T) -> String,
+) -> Option<FieldChange> {
+    // only report what was removed and what was added
+    let mut old = old.iter().collect::<Vec<_>>();
+    let mut new = new.iter().collect::<Vec<_>>();
+    old.sort();
+    new.sort();
+    if old == new {
+        return None;
+    }
+    let removed = old
+        .iter()
+        .filter(|item| !new.contains(item))
+        .map(|item| format(item))
+        .collect::<Vec<_>>();
+    let added = new
+        .iter()
+        .filter(|item| !old.contains(item))
+        .map(|item| format(item))
+        .collect::<Vec<_>>();
+    Some(FieldChange {
+        field: field.to_string(),
+        old: removed.join("; "),
+        new: added.join("; "),
+    })
+}
+
+fn diff_field(field: &str, old: &Option<String>, new: &Option<String>) -> Option<FieldChange> {
+    (old != new).then(|| FieldChange {
+        field: field.to_string(),
+        old: old.clone().unwrap_or_default(),
+        new: new.clone().unwrap_or_default(),
+    })
+}
+
+pub fn diff_courses(old: &PaulineCourse, new: &PaulineCourse) -> Vec<FieldChange> {
+    [
+        diff_field("name", &Some(old.name.clone()), &Some(new.name.clone())),
+        diff_field("description", &old.description, &new.description),
+        diff_field("ou", &old.ou, &new.ou),
+        diff_field("instructors", &old.instructors, &new.instructors),
+        diff_lists(
+            "appointments",
+            &old.appointments,
+            &new.appointments,
+            format_appointment,
+        ),
+        diff_lists(
+            "small_groups",
+            &old.small_groups,
+            &new.small_groups,
+            format_small_group,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+pub fn diff_semesters(old: &Semester, new: &Semester) -> SemesterDiff {
+    let old_courses: BTreeMap<&str, &PaulineCourse> = old
+        .courses
+        .iter()
+        .map(|course| (course.cid.as_str(), course))
+        .collect();
+    let new_courses: BTreeMap<&str, &PaulineCourse> = new
+        .courses
+        .iter()
+        .map(|course| (course.cid.as_str(), course))
+        .collect();
+
+    let mut diff = SemesterDiff::default();
+    for (cid, old_course) in &old_courses {
+        match new_courses.get(cid) {
+            Some(new_course) => {
+                let changes = diff_courses(old_course, new_course);
+                if !changes.is_empty() {
+                    diff.changed.push(CourseChange {
+                        cid: cid.to_string(),
+                        name: new_course.name.clone(),
+                        ou: new_course.ou.clone(),
+                        changes,
+                        source_updated: old_course
+                            .source_updated_at
+                            .as_ref()
+                            .zip(new_course.source_updated_at.as_ref())
+                            .map(|(old, new)| old != new),
+                    });
+                }
+            }
+            None => diff.removed.push((*old_course).clone()),
+        }
+    }
+    for (cid, new_course) in &new_courses {
+        if !old_courses.contains_key(cid) {
+            diff.added.push((*new_course).clone());
+        }
+    }
+    diff
+}
+
+// offering churn between two different semesters. cids carry a hash of the
+// name and a suffix for duplicates ("L.079.05401|3f:0"), across semesters
+// courses are matched by the course number in front of them
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChurnCourse {
+    pub number: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstructorChange {
+    pub number: String,
+    pub name: String,
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OuChurn {
+    pub ou: Option<String>,
+    pub new: Vec<ChurnCourse>,
+    pub discontinued: Vec<ChurnCourse>,
+    pub instructors_changed: Vec<InstructorChange>,
+}
+
+pub fn course_number(cid: &str) -> &str {
+    cid.split(['|', ':']).next().unwrap_or(cid)
+}
+
+fn instructor_names(course: &PaulineCourse) -> Vec<String> {
+    let mut names = course
+        .instructors
+        .as_deref()
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn courses_by_number(semester: &Semester) -> BTreeMap<&str, &PaulineCourse> {
+    // a course split into several cids in one semester counts once
+    let mut courses = BTreeMap::new();
+    for course in &semester.courses {
+        courses.entry(course_number(&course.cid)).or_insert(course);
+    }
+    courses
+}
+
+fn ou_churn<'a>(
+    churn: &'a mut BTreeMap<Option<String>, OuChurn>,
+    ou: &Option<String>,
+) -> &'a mut OuChurn {
+    churn.entry(ou.clone()).or_insert_with(|| OuChurn {
+        ou: ou.clone(),
+        ..OuChurn::default()
+    })
+}
+
+/// Courses new in, missing from or taught by other instructors in `new`
+/// compared to `old`, grouped by organisational unit.
+pub fn semester_churn(old: &Semester, new: &Semester) -> Vec<OuChurn> {
+    let old_courses = courses_by_number(old);
+    let new_courses = courses_by_number(new);
+    let summary = |number: &str, course: &PaulineCourse| ChurnCourse {
+        number: number.to_string(),
+        name: course.name.clone(),
+    };
+
+    let mut churn = BTreeMap::new();
+    for (number, course) in &old_courses {
+        if !new_courses.contains_key(number) {
+            ou_churn(&mut churn, &course.ou)
+                .discontinued
+                .push(summary(number, course));
+        }
+    }
+    for (number, course) in &new_courses {
+        match old_courses.get(number) {
+            None => ou_churn(&mut churn, &course.ou)
+                .new
+                .push(summary(number, course)),
+            Some(old_course) => {
+                let (old, new) = (instructor_names(old_course), instructor_names(course));
+                if old != new {
+                    ou_churn(&mut churn, &course.ou)
+                        .instructors_changed
+                        .push(InstructorChange {
+                            number: number.to_string(),
+                            name: course.name.clone(),
+                            old,
+                            new,
+                        });
+                }
+            }
+        }
+    }
+    churn.into_values().collect()
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[cfg(feature = "scrape")]
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("unexpected status {0}")]
+    Status(u16),
+    #[error("response larger than {limit} bytes")]
+    TooLarge { limit: usize },
+    #[error("unexpected content type {0}")]
+    ContentType(String),
+    #[error("landed on the login page")]
+    LoginPage,
+    #[error("redirect loop at {0}")]
+    RedirectLoop(String),
+    #[error("more than {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("no redirect found on {0}")]
+    NoRedirect(String),
+    #[error("semester {0} is not listed on the main page")]
+    UnknownSemester(String),
+    #[error("failed to start the runtime: {0}")]
+    Runtime(std::io::Error),
+    #[error("invalid config {0}")]
+    Config(String),
+    #[error("failed to write {0}")]
+    Output(String),
+    #[error("failed to read {0}")]
+    Input(String),
+}
+
+// what went wrong with a page, for counting failures by cause
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Network,
+    Status,
+    TooLarge,
+    ContentType,
+    LoginPage,
+    Redirect,
+    // the page was fetched but the parser gave up on it
+    Parse,
+    // not fetched because of a limit like --max-depth
+    Skipped,
+    Other,
+}
+
+impl std::str::FromStr for ErrorKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(kind.to_string()))
+            .map_err(|_| format!("unknown error kind {}", kind))
+    }
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "scrape")]
+            Error::Request(_) => ErrorKind::Network,
+            Error::Status(_) => ErrorKind::Status,
+            Error::TooLarge { .. } => ErrorKind::TooLarge,
+            Error::ContentType(_) => ErrorKind::ContentType,
+            Error::LoginPage => ErrorKind::LoginPage,
+            Error::RedirectLoop(_) | Error::TooManyRedirects(_) | Error::NoRedirect(_) => {
+                ErrorKind::Redirect
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Whether fetching the page again later can succeed: network trouble and server
+    /// errors are retryable, a 404, the login page or broken markup is not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "scrape")]
+            Error::Request(e) => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body(),
+            Error::Status(status) => *status >= 500 || *status == 408 || *status == 429,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use scraper::{Html, Selector};
+
+// a piece of a page a parser gave up on, small enough for a bug report and
+// without what identifies the session or the user
+
+pub const EXCERPT_CHARS: usize = 500;
+
+// the elements the parsers start from, an excerpt shows the first one found
+pub const TREE_PAGE: &[&str] = &["table.nb.eventTable", "ul.dl-ul-listview"];
+pub const COURSE_PAGE: &[&str] = &["form[name=courseform]"];
+pub const SMALL_GROUP_LISTING: &[&str] = &["div.tb"];
+pub const MODULE_PAGE: &[&str] = &["h1"];
+
+/// The first `EXCERPT_CHARS` characters of the first element matching one of
+/// `expected`, or of the body if none is there, sanitized.
+pub fn page_excerpt(html: &str, expected: &[&str]) -> String {
+    let document = Html::parse_document(html);
+    let region = expected
+        .iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| {
+            document
+                .select(&selector)
+                .next()
+                .map(|element| element.html())
+        })
+        .or_else(|| {
+            document
+                .select(&Selector::parse("body").unwrap())
+                .next()
+                .map(|body| body.inner_html())
+        })
+        .unwrap_or_default();
+    let excerpt = sanitize(&region);
+    match excerpt.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &excerpt[..end]),
+        None => excerpt,
+    }
+}
+
+// scripts, styles and comments
+static HIDDEN_MARKUP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<script.*?</script>|<style.*?</style>|<!--.*?-->").unwrap());
+// the first ARGUMENTS value is the session, see cid::event_id
+static SESSION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(ARGUMENTS=)-N\d+").unwrap());
+// form tokens
+static HIDDEN_INPUT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)<input[^>]*type="hidden"[^>]*>"#).unwrap());
+static VALUE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"value="[^"]*""#).unwrap());
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w.+-]+@[\w-]+(\.[\w-]+)+").unwrap());
+
+fn sanitize(html: &str) -> String {
+    let html = HIDDEN_MARKUP.replace_all(html, "");
+    let html = SESSION.replace_all(&html, "${1}-N0");
+    let html = HIDDEN_INPUT.replace_all(&html, |input: &Captures| {
+        VALUE.replace_all(&input[0], r#"value="""#).into_owned()
+    });
+    let html = EMAIL.replace_all(&html, "…@…");
+    html.split_whitespace().collect::<Vec<_>>().join(" ")
+}
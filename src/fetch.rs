@@ -0,0 +1,318 @@
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header::{CONTENT_ENCODING, CONTENT_TYPE},
+    Client,
+};
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::{charset::decode_html, error::Error};
+
+// sanity checks before anything is handed to Html::parse_document
+
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug)]
+pub struct ResponseLimits {
+    pub max_body_size: usize,
+}
+
+impl Default for ResponseLimits {
+    fn default() -> Self {
+        Self {
+            // course pages are well below 1MB
+            max_body_size: 5_000_000,
+        }
+    }
+}
+
+/// Detects PAUL's login form being served instead of the requested page.
+pub(crate) fn is_login_page(url: &Url, html: &str) -> bool {
+    if url.as_str().contains("LOGINCHECK") {
+        return true;
+    }
+    let document = Html::parse_document(html);
+    let has_password = document
+        .select(&Selector::parse("input[type=password]").unwrap())
+        .next()
+        .is_some();
+    // every page has a login box in the header, the login page also says so in its heading
+    has_password
+        && document.select(&Selector::parse("h1").unwrap()).any(|h1| {
+            let text = h1.text().collect::<String>().to_lowercase();
+            text.contains("anmeldung") || text.contains("login")
+        })
+}
+
+// durations per phase of a request (dns, ttfb, body, ...), shared between tasks
+
+// durations per phase, the http version of every response and the bytes
+// transferred for them
+#[derive(Clone, Debug, Default)]
+pub struct Timings {
+    durations: Arc<Mutex<BTreeMap<&'static str, Vec<Duration>>>>,
+    protocols: Arc<Mutex<BTreeMap<String, usize>>>,
+    transfer: Arc<Mutex<Transfer>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Transfer {
+    // as sent by PAUL, compressed or not
+    pub transferred: u64,
+    // after decompression
+    pub decoded: u64,
+    // responses PAUL compressed
+    pub compressed_responses: usize,
+}
+
+impl Timings {
+    pub fn record(&self, phase: &'static str, duration: Duration) {
+        self.durations
+            .lock()
+            .unwrap()
+            .entry(phase)
+            .or_default()
+            .push(duration);
+    }
+
+    /// Returns the sorted durations of every phase.
+    pub fn phases(&self) -> Vec<(&'static str, Vec<Duration>)> {
+        self.durations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(phase, durations)| {
+                let mut durations = durations.clone();
+                durations.sort();
+                (*phase, durations)
+            })
+            .collect()
+    }
+
+    pub fn record_protocol(&self, version: reqwest::Version) {
+        *self
+            .protocols
+            .lock()
+            .unwrap()
+            .entry(format!("{:?}", version))
+            .or_default() += 1;
+    }
+
+    pub fn record_transfer(&self, transferred: usize, decoded: usize, compressed: bool) {
+        let mut transfer = self.transfer.lock().unwrap();
+        transfer.transferred += transferred as u64;
+        transfer.decoded += decoded as u64;
+        transfer.compressed_responses += usize::from(compressed);
+    }
+
+    /// Body bytes over the wire and after decompression, summed over every response.
+    pub fn transfer(&self) -> Transfer {
+        *self.transfer.lock().unwrap()
+    }
+
+    /// Responses per negotiated http version, e.g. `("HTTP/1.1", 1200)`.
+    pub fn protocols(&self) -> Vec<(String, usize)> {
+        self.protocols
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(version, count)| (version.clone(), *count))
+            .collect()
+    }
+}
+
+// the body limit holds for the decompressed page too, a small response can
+// unpack to gigabytes
+fn decompress(encoding: &str, body: &[u8], limits: &ResponseLimits) -> Result<Vec<u8>, Error> {
+    let decoder: Box<dyn Read + '_> = match encoding {
+        "gzip" | "x-gzip" => Box::new(flate2::read::MultiGzDecoder::new(body)),
+        // meant to be zlib wrapped, some servers send it raw
+        "deflate" if body.first().is_some_and(|byte| byte & 0x0f == 8) => {
+            Box::new(flate2::read::ZlibDecoder::new(body))
+        }
+        "deflate" => Box::new(flate2::read::DeflateDecoder::new(body)),
+        "br" => Box::new(brotli_decompressor::Decompressor::new(body, 4096)),
+        _ => return Err(Error::ContentType(format!("content encoding {}", encoding))),
+    };
+    let mut decoded = Vec::new();
+    decoder
+        .take(limits.max_body_size as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| Error::ContentType(format!("broken {} body: {}", encoding, e)))?;
+    if decoded.len() > limits.max_body_size {
+        return Err(Error::TooLarge {
+            limit: limits.max_body_size,
+        });
+    }
+    Ok(decoded)
+}
+
+/// Nearest-rank percentile of sorted durations, `p` in 0..=100.
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+// reqwest doesn't report connection phases, so dns lookups are timed
+// by resolving through this instead of the default resolver. with a family
+// only its addresses are used, for networks with broken ipv6
+pub struct TimedResolver {
+    pub timings: Timings,
+    pub family: Option<IpFamily>,
+}
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let timings = self.timings.clone();
+        let family = self.family;
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| match family {
+                    Some(IpFamily::V4) => addr.is_ipv4(),
+                    Some(IpFamily::V6) => addr.is_ipv6(),
+                    None => true,
+                })
+                .collect::<Vec<_>>();
+            timings.record("dns", started.elapsed());
+            if let (true, Some(family)) = (addrs.is_empty(), family) {
+                let error = format!("{} has no {:?} address", name.as_str(), family);
+                return Err(error.into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Fetches a page, rejecting non-html responses, oversized bodies and the login page.
+pub async fn fetch_page(
+    client: &Client,
+    url: &Url,
+    limits: &ResponseLimits,
+) -> Result<String, Error> {
+    fetch_page_timed(client, url, limits, &Timings::default()).await
+}
+
+/// Like [`fetch_page_timed`], retrying up to `retries` times while the error is retryable.
+pub async fn fetch_page_retrying(
+    client: &Client,
+    url: &Url,
+    limits: &ResponseLimits,
+    timings: &Timings,
+    retries: u32,
+) -> Result<String, Error> {
+    let mut attempt = 0;
+    loop {
+        match fetch_page_timed(client, url, limits, timings).await {
+            Err(e) if e.is_retryable() && attempt < retries => {
+                // 1s, 2s, 4s, ...
+                tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Like [`fetch_page`], recording time to first byte and body download in `timings`.
+pub async fn fetch_page_timed(
+    client: &Client,
+    url: &Url,
+    limits: &ResponseLimits,
+    timings: &Timings,
+) -> Result<String, Error> {
+    let started = Instant::now();
+    let response = client.get(url.clone()).send().await?;
+    timings.record("ttfb", started.elapsed());
+    timings.record_protocol(response.version());
+    if !response.status().is_success() {
+        return Err(Error::Status(response.status().as_u16()));
+    }
+    // a missing content type is fine, anything but html is not
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .map(|content_type| content_type.to_str().unwrap_or_default().to_string());
+    if let Some(content_type) = &content_type {
+        if !content_type.starts_with("text/html") {
+            return Err(Error::ContentType(content_type.clone()));
+        }
+    }
+    let final_url = response.url().clone();
+    let body = read_body(response, limits, timings).await?;
+    let html = decode_html(&body, content_type.as_deref());
+    if is_login_page(&final_url, &html) {
+        return Err(Error::LoginPage);
+    }
+    Ok(html)
+}
+
+/// Fetches any file, e.g. a published semester.json, decompressed but not decoded.
+pub async fn fetch_bytes(
+    client: &Client,
+    url: &Url,
+    limits: &ResponseLimits,
+) -> Result<Vec<u8>, Error> {
+    let response = client.get(url.clone()).send().await?;
+    if !response.status().is_success() {
+        return Err(Error::Status(response.status().as_u16()));
+    }
+    read_body(response, limits, &Timings::default()).await
+}
+
+async fn read_body(
+    mut response: reqwest::Response,
+    limits: &ResponseLimits,
+    timings: &Timings,
+) -> Result<Vec<u8>, Error> {
+    if response
+        .content_length()
+        .is_some_and(|length| length as usize > limits.max_body_size)
+    {
+        return Err(Error::TooLarge {
+            limit: limits.max_body_size,
+        });
+    }
+    // the header can lie or be missing, so count while reading
+    let mut body = Vec::new();
+    let started = Instant::now();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > limits.max_body_size {
+            return Err(Error::TooLarge {
+                limit: limits.max_body_size,
+            });
+        }
+        body.extend_from_slice(&chunk);
+    }
+    timings.record("body", started.elapsed());
+    // set when the client asked for compression, see HttpConfig
+    let encoding = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .map(|encoding| encoding.to_str().unwrap_or_default().trim().to_lowercase())
+        .filter(|encoding| !encoding.is_empty() && encoding != "identity");
+    let transferred = body.len();
+    if let Some(encoding) = &encoding {
+        body = decompress(encoding, &body, limits)?;
+    }
+    timings.record_transfer(transferred, body.len(), encoding.is_some());
+    Ok(body)
+}
@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use serde::{Deserialize, Serialize};
+
+// the pages discovered during a crawl and the links between them,
+// exported as dot or graphml to look at the catalog structure
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Main,
+    Tree,
+    Course,
+    SmallGroup,
+}
+
+impl NodeKind {
+    fn name(&self) -> &'static str {
+        match self {
+            NodeKind::Main => "main",
+            NodeKind::Tree => "tree",
+            NodeKind::Course => "course",
+            NodeKind::SmallGroup => "small_group",
+        }
+    }
+
+    fn shape(&self) -> &'static str {
+        match self {
+            NodeKind::Main => "doublecircle",
+            NodeKind::Tree => "folder",
+            NodeKind::Course => "box",
+            NodeKind::SmallGroup => "ellipse",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    // the link text
+    pub label: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CrawlGraph {
+    // keyed by url
+    pub nodes: BTreeMap<String, NodeKind>,
+    pub edges: Vec<Edge>,
+}
+
+impl CrawlGraph {
+    pub fn add_node(&mut self, url: &str, kind: NodeKind) {
+        self.nodes.entry(url.to_string()).or_insert(kind);
+    }
+
+    /// Adds a link from an already known page to a (possibly new) page of `kind`.
+    pub fn add_edge(&mut self, from: &str, to: &str, kind: NodeKind, label: &str) {
+        self.add_node(to, kind);
+        self.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            label: label.trim().to_string(),
+        });
+    }
+
+    fn ids(&self) -> BTreeMap<&str, usize> {
+        // urls make terrible node ids, number them instead
+        self.nodes
+            .keys()
+            .enumerate()
+            .map(|(id, url)| (url.as_str(), id))
+            .collect()
+    }
+
+    // edges by node id. an edge to or from a page that isn't a node, as in
+    // checkpoints written before the source page was added, is left out
+    fn numbered_edges<'a>(
+        &'a self,
+        ids: &'a BTreeMap<&str, usize>,
+    ) -> impl Iterator<Item = (usize, usize, &'a str)> + 'a {
+        self.edges.iter().filter_map(|edge| {
+            Some((
+                *ids.get(edge.from.as_str())?,
+                *ids.get(edge.to.as_str())?,
+                edge.label.as_str(),
+            ))
+        })
+    }
+
+    pub fn to_dot(&self) -> String {
+        let ids = self.ids();
+        let mut dot = String::from("digraph paul {\n");
+        for (url, kind) in &self.nodes {
+            writeln!(
+                dot,
+                "  n{} [shape={}, kind=\"{}\", url=\"{}\"];",
+                ids[url.as_str()],
+                kind.shape(),
+                kind.name(),
+                escape_dot(url)
+            )
+            .unwrap();
+        }
+        for (from, to, label) in self.numbered_edges(&ids) {
+            writeln!(
+                dot,
+                "  n{} -> n{} [label=\"{}\"];",
+                from,
+                to,
+                escape_dot(label)
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let ids = self.ids();
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"url\" for=\"node\" attr.name=\"url\" attr.type=\"string\"/>\n",
+            "  <key id=\"label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <graph id=\"paul\" edgedefault=\"directed\">\n",
+        ));
+        for (url, kind) in &self.nodes {
+            writeln!(
+                xml,
+                "    <node id=\"n{}\"><data key=\"kind\">{}</data><data key=\"url\">{}</data></node>",
+                ids[url.as_str()],
+                kind.name(),
+                escape_xml(url)
+            )
+            .unwrap();
+        }
+        for (from, to, label) in self.numbered_edges(&ids) {
+            writeln!(
+                xml,
+                "    <edge source=\"n{}\" target=\"n{}\"><data key=\"label\">{}</data></edge>",
+                from,
+                to,
+                escape_xml(label)
+            )
+            .unwrap();
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,166 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::{
+    pauline::{PaulineAppointment, Semester},
+    Location,
+};
+
+// calendar export of a semester.json, one event per appointment
+//
+// PAUL times are Paderborn wall clock times, so they are written as local
+// times with a TZID instead of being converted to UTC. A calendar client
+// that knows the zone then keeps 11:00 at 11:00 across the DST switch.
+
+const TZID: &str = "Europe/Berlin";
+
+// the EU rules since 1996: last sunday of march and october at 01:00 UTC
+const VTIMEZONE: &str = "BEGIN:VTIMEZONE\r
+TZID:Europe/Berlin\r
+BEGIN:DAYLIGHT\r
+TZOFFSETFROM:+0100\r
+TZOFFSETTO:+0200\r
+TZNAME:CEST\r
+DTSTART:19700329T020000\r
+RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r
+END:DAYLIGHT\r
+BEGIN:STANDARD\r
+TZOFFSETFROM:+0200\r
+TZOFFSETTO:+0100\r
+TZNAME:CET\r
+DTSTART:19701025T030000\r
+RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r
+END:STANDARD\r
+END:VTIMEZONE\r
+";
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn fold(line: &str) -> String {
+    // lines are limited to 75 octets, continuations start with a space
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn local_time(time: &str) -> Option<String> {
+    let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S").ok()?;
+    Some(time.format("%Y%m%dT%H%M%S").to_string())
+}
+
+fn event(uid: &str, summary: &str, appointment: &PaulineAppointment, stamp: &str) -> String {
+    let (Some(start), Some(end)) = (
+        local_time(&appointment.start_time),
+        local_time(&appointment.end_time),
+    ) else {
+        return String::new();
+    };
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART;TZID={}:{}", TZID, start),
+        format!("DTEND;TZID={}:{}", TZID, end),
+        format!("SUMMARY:{}", escape(summary)),
+    ];
+    // kept rather than left out, so subscribed calendars drop the date
+    // instead of keeping the one they got before it was cancelled
+    if appointment.cancelled {
+        lines.push("STATUS:CANCELLED".to_string());
+    }
+    // calendar clients show URL as a join button, LOCATION keeps what PAUL says
+    let url = appointment.location.url();
+    match (&appointment.location, url) {
+        (Location::Online { .. }, Some(url)) if appointment.room.trim() == url => {
+            lines.push(format!("LOCATION:{}", escape(url)));
+        }
+        (Location::Online { .. }, None) if appointment.room.trim().is_empty() => {
+            lines.push("LOCATION:Online".to_string());
+        }
+        _ => lines.push(format!("LOCATION:{}", escape(&appointment.room))),
+    }
+    if let Some(url) = url {
+        // a uri value, not text, so it isn't escaped
+        lines.push(format!("URL:{}", url));
+    }
+    lines.push(format!("DESCRIPTION:{}", escape(&appointment.instructors)));
+    lines.push("END:VEVENT".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+// the days to export, both ends included, e.g. only the exam period
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, appointment: &PaulineAppointment) -> bool {
+        // appointments without a readable start aren't exported anyway
+        let Ok(start) = NaiveDateTime::parse_from_str(&appointment.start_time, "%Y-%m-%dT%H:%M:%S")
+        else {
+            return false;
+        };
+        self.from.is_none_or(|from| start.date() >= from)
+            && self.to.is_none_or(|to| start.date() <= to)
+    }
+}
+
+/// Renders the appointments of the courses matching `filter` as an iCalendar file.
+pub fn semester_to_ics(semester: &Semester, filter: impl Fn(&str) -> bool) -> String {
+    semester_to_ics_in(semester, filter, &TimeWindow::default())
+}
+
+/// Like [`semester_to_ics`], leaving out appointments starting outside of `window`.
+pub fn semester_to_ics_in(
+    semester: &Semester,
+    filter: impl Fn(&str) -> bool,
+    window: &TimeWindow,
+) -> String {
+    // DTSTAMP has to be utc, created is the utc start of the scrape
+    let stamp = local_time(&semester.created)
+        .map(|created| format!("{}Z", created))
+        .unwrap_or_else(|| "19700101T000000Z".to_string());
+    let mut ics = String::new();
+    ics.push_str("BEGIN:VCALENDAR\r\n");
+    ics.push_str("VERSION:2.0\r\n");
+    ics.push_str("PRODID:-//paul-scrape-rs//EN\r\n");
+    ics.push_str(&fold(&format!("X-WR-CALNAME:{}", escape(&semester.name))));
+    ics.push_str(VTIMEZONE);
+    for course in semester.courses.iter().filter(|course| filter(&course.cid)) {
+        for (i, appointment) in course.appointments.iter().enumerate() {
+            // uids keep their index, an event doesn't change its uid with the window
+            if !window.contains(appointment) {
+                continue;
+            }
+            let uid = format!("{}-{}@paul-scrape-rs", course.cid, i);
+            ics.push_str(&event(&uid, &course.name, appointment, &stamp));
+        }
+        for small_group in &course.small_groups {
+            for (i, appointment) in small_group.appointments.iter().enumerate() {
+                if !window.contains(appointment) {
+                    continue;
+                }
+                let uid = format!("{}-{}-{}@paul-scrape-rs", course.cid, small_group.name, i);
+                let summary = format!("{} ({})", course.name, small_group.name);
+                ics.push_str(&event(&uid, &summary, appointment, &stamp));
+            }
+        }
+    }
+    ics.push_str("END:VCALENDAR\r\n");
+    ics
+}
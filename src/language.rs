@@ -0,0 +1,79 @@
+use scraper::{Html, Selector};
+
+// PAUL can be switched to english per session, which changes every caption
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    German,
+    English,
+}
+
+pub struct Captions {
+    pub appointments: &'static str,
+    pub small_groups: &'static str,
+    // the link to the page with every small group and its dates
+    pub all_small_groups: &'static str,
+    pub prerequisites: &'static str,
+    pub recommended_knowledge: &'static str,
+    pub credits: &'static str,
+    pub sws: &'static str,
+    pub related_courses: &'static str,
+    pub course_type: &'static str,
+    pub module_responsible: &'static str,
+    pub last_updated: &'static str,
+}
+
+const GERMAN: Captions = Captions {
+    appointments: "Termine",
+    small_groups: "Kleingruppe(n)",
+    all_small_groups: "Alle Kleingruppen",
+    prerequisites: "Voraussetzungen",
+    recommended_knowledge: "Empfohlene Vorkenntnisse",
+    credits: "Leistungspunkte",
+    sws: "SWS",
+    related_courses: "Zugehörige Veranstaltungen",
+    course_type: "Veranstaltungsart",
+    module_responsible: "Modulverantwortliche",
+    last_updated: "Zuletzt geändert",
+};
+
+const ENGLISH: Captions = Captions {
+    appointments: "Dates",
+    small_groups: "Small group(s)",
+    all_small_groups: "All small groups",
+    prerequisites: "Prerequisites",
+    recommended_knowledge: "Recommended knowledge",
+    credits: "Credits",
+    sws: "SWS",
+    related_courses: "Related courses",
+    course_type: "Course type",
+    module_responsible: "Module coordinator",
+    last_updated: "Last modified",
+};
+
+impl Language {
+    pub fn captions(self) -> &'static Captions {
+        match self {
+            Language::German => &GERMAN,
+            Language::English => &ENGLISH,
+        }
+    }
+}
+
+/// Detects the interface language from the `lang` attribute of the page, defaulting to german.
+pub fn detect_language(document: &Html) -> Language {
+    let lang = document
+        .select(&Selector::parse("html").unwrap())
+        .next()
+        .and_then(|html| {
+            html.value()
+                .attr("lang")
+                .or_else(|| html.value().attr("xml:lang"))
+        })
+        .unwrap_or_default();
+    if lang.to_lowercase().starts_with("en") {
+        Language::English
+    } else {
+        Language::German
+    }
+}
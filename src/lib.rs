@@ -1,16 +1,99 @@
-use reqwest::{Client, Url};
-use scraper::{Html, Selector};
+#[cfg(feature = "scrape")]
+use reqwest::Client;
+use scraper::{ElementRef, Html, Node, Selector};
 use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    language::{detect_language, Captions, Language},
+    links::{CourseUrl, Link, LinkClassifier, LinkKind, SmallGroupUrl, TreeUrl},
+    numbers::{parse_amount, Amount},
+    table::{find_tables_by_caption, Table},
+};
+
+pub mod analysis;
+pub mod cache;
+pub mod canary;
+pub mod charset;
+pub mod cid;
+#[cfg(feature = "scrape")]
+pub mod config;
+#[cfg(feature = "scrape")]
+pub mod cookies;
+#[cfg(feature = "scrape")]
+pub mod crawl;
+pub mod datetime;
+pub mod diff;
+pub mod error;
+pub mod excerpt;
+#[cfg(feature = "scrape")]
+pub mod fetch;
+pub mod graph;
+pub mod ics;
+// caption lookup for the parsers, not part of the api
+pub(crate) mod language;
+pub mod links;
+pub mod modules;
+pub mod numbers;
+pub mod pacing;
+#[cfg(feature = "scrape")]
+pub mod pack;
+pub mod partition;
+pub mod pauline;
+pub mod postprocess;
+pub mod prelude;
+#[cfg(feature = "scrape")]
+pub mod progress;
+pub mod query;
+pub mod rooms;
+pub mod search;
+pub mod sink;
+pub mod snapshot;
+#[cfg(feature = "scrape")]
+pub mod spill;
+pub mod status;
+pub mod table;
+pub mod tags;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathSegmentKind {
+    // the semester picked on the main page
+    Semester,
+    // a category page (faculty, study program, module level, ...)
+    Category,
+    // the link text of a course in a category listing
+    CourseListing,
+    // the title on the course page itself
+    Course,
+    // the title on a small group page
+    SmallGroup,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PathSegment {
+    pub kind: PathSegmentKind,
+    pub title: String,
+    pub url: Option<Url>,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Path {
     pub fragments: Vec<String>,
+    // same entries as fragments, but typed; empty for paths
+    // built with push or read from older state files
+    #[serde(default)]
+    pub segments: Vec<PathSegment>,
 }
 
 impl Path {
     pub fn new() -> Self {
         Self {
             fragments: Vec::new(),
+            segments: Vec::new(),
         }
     }
 
@@ -22,6 +105,28 @@ impl Path {
         // return the new path
         path
     }
+
+    pub fn push_segment(&self, kind: PathSegmentKind, title: String, url: Option<&Url>) -> Self {
+        let mut path = self.push(title.clone());
+        path.segments.push(PathSegment {
+            kind,
+            title,
+            url: url.cloned(),
+        });
+        path
+    }
+
+    /// The top level category below the semester, usually a faculty.
+    pub fn faculty(&self) -> Option<&str> {
+        // untyped paths are semester > faculty > ...
+        if self.segments.is_empty() {
+            return self.fragments.get(1).map(String::as_str);
+        }
+        self.segments
+            .iter()
+            .find(|segment| segment.kind == PathSegmentKind::Category)
+            .map(|segment| segment.title.as_str())
+    }
 }
 
 impl Default for Path {
@@ -30,80 +135,154 @@ impl Default for Path {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CoursePage {
-    #[serde(serialize_with = "url_to_string")]
-    pub url: Url,
+    pub url: CourseUrl,
     pub path: Path,
+    // when the listing row shows it next to the link
+    #[serde(default)]
+    pub course_type: Option<String>,
 }
 
-fn url_to_string<S>(url: &Url, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    serializer.serialize_str(url.as_ref())
+// a course as far as the tree listing knows it, for --shallow catalogs
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CatalogEntry {
+    pub number: String,
+    pub title: String,
+    pub path: Path,
+    pub url: String,
+    #[serde(default)]
+    pub course_type: Option<String>,
 }
 
-pub async fn get_semesters(client: Client, base_url: &Url) -> Vec<(String, Url)> {
-    let response = client
-        .get(base_url.clone())
-        .send()
-        .await
-        .unwrap()
-        .text()
-        .await
-        .unwrap();
-    let redirect = get_redirect1(response, base_url);
-    // make request to redirect url
-    let response = client
-        .get(redirect)
-        .send()
-        .await
-        .unwrap()
-        .text()
-        .await
-        .unwrap();
-    // store 2nd href as redirect url
-    let redirect = get_redirect2(response, base_url);
-    // make request to redirect url
-    let response = client
-        .get(redirect.as_ref())
-        .send()
-        .await
-        .unwrap()
-        .text()
-        .await
-        .unwrap();
+impl From<CoursePage> for CatalogEntry {
+    fn from(page: CoursePage) -> Self {
+        // the link text is "L.079.05401 Grundlagen der Programmierung 1"
+        let text = page.path.fragments.last().cloned().unwrap_or_default();
+        let (number, title) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+        Self {
+            number: number.to_string(),
+            title: title.trim().to_string(),
+            url: page.url.to_string(),
+            path: page.path,
+            course_type: page.course_type,
+        }
+    }
+}
+
+// what PAUL calls the kinds of courses, used to spot the type in listing rows
+pub const COURSE_TYPES: &[&str] = &[
+    "Vorlesung",
+    "Übung",
+    "Vorlesung/Übung",
+    "Seminar",
+    "Proseminar",
+    "Hauptseminar",
+    "Projektgruppe",
+    "Praktikum",
+    "Tutorium",
+    "Kolloquium",
+    "Exkursion",
+    "Lecture",
+    "Exercise",
+    "Lecture/Exercise",
+    "Project group",
+    "Lab",
+    "Tutorial",
+    "Colloquium",
+];
+
+/// Whether `course_type` is one of `types`, ignoring case. No filter matches everything.
+pub fn matches_course_type(course_type: Option<&str>, types: &[String]) -> bool {
+    types.is_empty()
+        || course_type.is_some_and(|course_type| {
+            types
+                .iter()
+                .any(|wanted| wanted.trim().eq_ignore_ascii_case(course_type.trim()))
+        })
+}
+
+#[cfg(feature = "scrape")]
+pub async fn get_semesters(
+    client: Client,
+    base_url: &Url,
+    max_redirects: usize,
+) -> Result<Vec<(String, Url)>, error::Error> {
+    // the base url leads to the main page through a chain of meta refresh and link pages
+    let response = follow_redirects(&client, base_url, max_redirects).await?;
     // parse and return
-    get_semesters_from_main(&response, base_url)
+    Ok(get_semesters_from_main(&response, base_url))
 }
 
-fn get_redirect1(response: String, base_url: &Url) -> Url {
-    let document = Html::parse_document(&response);
-    // we want <meta http-equiv="refresh" content="0; URL=[WE WANT THIS]">
-    let redirect = document
-        .select(&Selector::parse("meta[http-equiv=refresh]").unwrap())
+#[cfg(feature = "scrape")]
+pub async fn follow_redirects(
+    client: &Client,
+    url: &Url,
+    max_redirects: usize,
+) -> Result<String, error::Error> {
+    let mut url = url.clone();
+    let mut visited = std::collections::HashSet::new();
+    loop {
+        if !visited.insert(url.clone()) {
+            return Err(error::Error::RedirectLoop(url.to_string()));
+        }
+        let response = fetch::fetch_page(client, &url, &fetch::ResponseLimits::default()).await?;
+        if is_main_page(&response) {
+            return Ok(response);
+        }
+        if visited.len() > max_redirects {
+            return Err(error::Error::TooManyRedirects(max_redirects));
+        }
+        url = find_redirect(&response, &url)
+            .ok_or_else(|| error::Error::NoRedirect(url.to_string()))?;
+    }
+}
+
+#[cfg(feature = "scrape")]
+fn is_main_page(response: &str) -> bool {
+    let document = Html::parse_document(response);
+    document
+        .select(&Selector::parse("li.intern.depth_2.linkItem").unwrap())
         .next()
-        .unwrap()
-        .value()
-        .attr("content")
-        .unwrap();
-    // result is "[seconds]; url=[url]"
-    let redirect = redirect.split(';').nth(1).unwrap();
-    let redirect = redirect.split_once('=').unwrap().1;
-    base_url.join(redirect).unwrap()
-}
-
-fn get_redirect2(response: String, base_url: &Url) -> Url {
-    let document = Html::parse_document(&response);
-    let redirect = document
-        .select(&Selector::parse("a").unwrap())
-        .nth(1)
-        .unwrap()
-        .value()
-        .attr("href")
-        .unwrap();
-    base_url.clone().join(redirect).unwrap()
+        .is_some()
+}
+
+/// Finds where a redirect page leads, either by meta refresh or by its link.
+#[cfg(feature = "scrape")]
+pub(crate) fn find_redirect(response: &str, page_url: &Url) -> Option<Url> {
+    let document = Html::parse_document(response);
+    // we want <meta http-equiv="refresh" content="0; URL=[WE WANT THIS]">
+    let refresh = document
+        .select(&Selector::parse("meta[http-equiv]").unwrap())
+        .filter(|meta| {
+            meta.value()
+                .attr("http-equiv")
+                .is_some_and(|equiv| equiv.eq_ignore_ascii_case("refresh"))
+        })
+        .find_map(|meta| meta.value().attr("content"));
+    if let Some(refresh) = refresh {
+        // result is "[seconds]; url=[url]", possibly quoted
+        let target = refresh
+            .split_once(';')
+            .map(|(_, target)| target.trim())
+            .and_then(|target| target.split_once('='))
+            .map(|(_, target)| target.trim().trim_matches(|c| c == '\'' || c == '"'));
+        if let Some(target) = target {
+            return page_url.join(target).ok();
+        }
+    }
+    // otherwise follow the link into the application, these pages have next to no links
+    let links = document
+        .select(&Selector::parse("a[href]").unwrap())
+        .filter_map(|a| a.value().attr("href"))
+        .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"))
+        .collect::<Vec<_>>();
+    links
+        .iter()
+        .find(|href| href.contains("PRGNAME"))
+        .or_else(|| links.get(1))
+        .or_else(|| links.first())
+        .and_then(|href| page_url.join(href).ok())
 }
 
 pub fn get_semesters_from_main(main_page: &str, base_url: &Url) -> Vec<(String, Url)> {
@@ -113,9 +292,9 @@ pub fn get_semesters_from_main(main_page: &str, base_url: &Url) -> Vec<(String,
     let li_nodes = main_page.select(&li_selector);
     // filter li_nodes
     let li_nodes = li_nodes.filter(|li_node| {
-        // their title attr has to start with Sommer or Winter
+        // their title attr has to start with Sommer (Summer in english) or Winter
         let title = li_node.value().attr("title").unwrap();
-        title.starts_with("Sommer") || title.starts_with("Winter")
+        title.starts_with("Sommer") || title.starts_with("Summer") || title.starts_with("Winter")
     });
     // map li_nodes to (title, url) tuples
     li_nodes
@@ -133,13 +312,139 @@ pub fn get_semesters_from_main(main_page: &str, base_url: &Url) -> Vec<(String,
         .collect()
 }
 
+// summer terms run from april to september, winter terms from october to march
+fn semester_prefixes(year: i32, summer: bool) -> Vec<String> {
+    if summer {
+        vec![format!("Sommer {}", year), format!("Summer {}", year)]
+    } else {
+        vec![format!("Winter {}", year)]
+    }
+}
+
+// (summer, first year) of "Sommer 2024", "SoSe 24", "WS 23/24", "Winter 2023/24", ...
+// the season is none for a bare year
+fn parse_term(spec: &str) -> Option<(Option<bool>, i32)> {
+    let spec = spec.trim().to_lowercase();
+    let (season, year) = match spec.rsplit_once(char::is_whitespace) {
+        Some((season, year)) => (Some(season.trim()), year),
+        None => (None, spec.as_str()),
+    };
+    let summer = match season {
+        None => None,
+        Some("sommer" | "summer" | "sose" | "ss" | "sommersemester") => Some(true),
+        Some("winter" | "wise" | "ws" | "wintersemester") => Some(false),
+        Some(_) => return None,
+    };
+    let first = year.split('/').next()?;
+    let year = first.parse::<i32>().ok()?;
+    match first.len() {
+        2 => Some((summer, 2000 + year)),
+        4 => Some((summer, year)),
+        _ => None,
+    }
+}
+
+/// Resolves a semester spec to the matching title returned by [`get_semesters_from_main`].
+///
+/// "current" and "next" go by `today`, abbreviations like "SoSe 2024" or
+/// "WS 23/24" and bare years by the listed terms. Returns `None` if no term or
+/// more than one matches. A listed title and a spec that doesn't name a term
+/// are returned unchanged.
+pub fn resolve_semester(
+    spec: &str,
+    today: chrono::NaiveDate,
+    semesters: &[(String, Url)],
+) -> Option<String> {
+    use chrono::Datelike;
+    let (year, summer) = match today.month() {
+        1..=3 => (today.year() - 1, false),
+        4..=9 => (today.year(), true),
+        _ => (today.year(), false),
+    };
+    let prefixes = match spec {
+        "current" => semester_prefixes(year, summer),
+        "next" if summer => semester_prefixes(year, false),
+        "next" => semester_prefixes(year + 1, true),
+        _ if semesters.iter().any(|(title, _)| title == spec) => return Some(spec.to_string()),
+        _ => {
+            let Some((summer, year)) = parse_term(spec) else {
+                return Some(spec.to_string());
+            };
+            let matching = semesters
+                .iter()
+                .map(|(title, _)| title)
+                .filter(|title| match parse_term(title) {
+                    Some((Some(listed), listed_year)) => {
+                        listed_year == year && summer.is_none_or(|summer| summer == listed)
+                    }
+                    _ => false,
+                })
+                .collect::<Vec<_>>();
+            return match matching[..] {
+                [title] => Some(title.clone()),
+                _ => None,
+            };
+        }
+    };
+    // winter terms are listed as "Winter 2023/24"
+    semesters
+        .iter()
+        .map(|(title, _)| title)
+        .find(|title| {
+            prefixes
+                .iter()
+                .any(|prefix| *title == prefix || title.starts_with(&format!("{}/", prefix)))
+        })
+        .cloned()
+}
+
 pub fn parse_courses_and_branches(
     response: String,
-    url: &Url,
+    url: &TreeUrl,
     path: &Path,
-) -> (Vec<CoursePage>, Vec<(Url, Path)>) {
+) -> (Vec<CoursePage>, Vec<(TreeUrl, Path)>) {
+    let (course_list, branch_list, _) = parse_tree_page(response, url, path);
+    (course_list, branch_list)
+}
+
+/// Like [`parse_courses_and_branches`], also returning the next page of a paginated listing.
+pub fn parse_tree_page(
+    response: String,
+    url: &TreeUrl,
+    path: &Path,
+) -> (Vec<CoursePage>, Vec<(TreeUrl, Path)>, Option<TreeUrl>) {
+    let page = parse_tree_page_with(response, url, path, &LinkClassifier::default());
+    (page.courses, page.branches, page.next_page)
+}
+
+pub struct TreePage {
+    pub courses: Vec<CoursePage>,
+    pub branches: Vec<(TreeUrl, Path)>,
+    pub next_page: Option<TreeUrl>,
+    // links to pages that are neither courses nor categories
+    pub other_links: Vec<(LinkKind, Url, Path)>,
+}
+
+/// Like [`parse_tree_page`], classifying the listing's links with `classifier`.
+pub fn parse_tree_page_with(
+    response: String,
+    url: &TreeUrl,
+    path: &Path,
+    classifier: &LinkClassifier,
+) -> TreePage {
+    parse_tree_page_document(&Html::parse_document(&response), url, path, classifier)
+}
+
+/// Like [`parse_tree_page_with`] on an already parsed page.
+pub fn parse_tree_page_document(
+    document: &Html,
+    url: &TreeUrl,
+    path: &Path,
+    classifier: &LinkClassifier,
+) -> TreePage {
     let mut course_list = Vec::new();
     let mut branch_list = Vec::new();
+    let mut other_links = Vec::new();
     // soup = BeautifulSoup(html, 'html.parser')
 
     // registration_links = soup.find(id="auditRegistration_list")
@@ -157,27 +462,15 @@ pub fn parse_courses_and_branches(
     //             course_links.extend(await __find_courses([link.attrs['href']], depth + 1))
     //         elif 'COURSEDETAILS' in link.attrs['href']:
     //             course_links.append(link.attrs['href'])
-    let document = Html::parse_document(&response);
 
-    let registration_links = document
-        .select(&Selector::parse("#auditRegistration_list").unwrap())
-        .next();
-    if let Some(registration_links) = registration_links {
-        let found_registration_links = registration_links
-            .select(&Selector::parse("a").unwrap())
-            .map(|a_node| {
-                let href = a_node.value().attr("href").unwrap().to_string();
-                let text = a_node
-                    .text()
-                    .collect::<Vec<_>>()
-                    .join(" ")
-                    .trim()
-                    .to_string();
-                (url.join(&href).unwrap(), path.push(text))
-            })
-            .collect::<Vec<_>>();
-        branch_list.extend(found_registration_links);
-    }
+    branch_list.extend(
+        extract_registration_links(document, url)
+            .into_iter()
+            .map(|(url, text)| {
+                let path = path.push_segment(PathSegmentKind::Category, text, Some(&url));
+                (TreeUrl::new(url), path)
+            }),
+    );
 
     let mut table = document
         .select(&Selector::parse("table.nb.eventTable").unwrap())
@@ -188,9 +481,21 @@ pub fn parse_courses_and_branches(
             .next();
     }
 
+    // large categories page through the eventTable, the pager links
+    // point to the same cluster and must not become branches
+    let next_page = table
+        .and_then(|table| {
+            table.parent().and_then(ElementRef::wrap).map_or_else(
+                || next_page_link(table, url),
+                |parent| next_page_link(parent, url),
+            )
+        })
+        .map(TreeUrl::new);
+
     if let Some(table) = table {
         table
             .select(&Selector::parse("a").unwrap())
+            .filter(|a_node| !is_pagination_link(a_node))
             .for_each(|a_node| {
                 let href = a_node.value().attr("href").unwrap();
                 let text = a_node
@@ -199,39 +504,441 @@ pub fn parse_courses_and_branches(
                     .join(" ")
                     .trim()
                     .to_string();
-                let url = url.join(href).unwrap();
-                if href.contains("COURSEOFFERINGCLUSTER") {
-                    branch_list.push((url, path.push(text)));
-                } else if href.contains("COURSEDETAILS") {
-                    course_list.push(CoursePage {
-                        url,
-                        path: path.push(text),
-                    });
+                match classifier.classify(url, href) {
+                    Some(Link::Tree(url)) => {
+                        let path = path.push_segment(PathSegmentKind::Category, text, Some(&url));
+                        branch_list.push((url, path));
+                    }
+                    Some(Link::Course(url)) => {
+                        let path =
+                            path.push_segment(PathSegmentKind::CourseListing, text, Some(&url));
+                        let course_type = listing_course_type(a_node);
+                        course_list.push(CoursePage {
+                            url,
+                            path,
+                            course_type,
+                        });
+                    }
+                    Some(Link::Other(kind, url)) => {
+                        let path = path.push(text);
+                        other_links.push((kind, url, path));
+                    }
+                    None => {}
                 }
             });
     }
-    (course_list, branch_list)
+    TreePage {
+        courses: course_list,
+        branches: branch_list,
+        next_page,
+        other_links,
+    }
+}
+
+fn listing_course_type(link: ElementRef) -> Option<String> {
+    // the text of the row around the link, without the title itself
+    // since a title like "Übung zur Vorlesung ..." names other types
+    let row = link
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|element| matches!(element.value().name(), "tr" | "li"))?;
+    row.descendants()
+        .filter(|node| !node.ancestors().any(|ancestor| ancestor.id() == link.id()))
+        .filter_map(|node| node.value().as_text())
+        .map(|text| text.trim())
+        .find_map(|text| {
+            COURSE_TYPES
+                .iter()
+                .find(|course_type| course_type.eq_ignore_ascii_case(text))
+                .map(|course_type| course_type.to_string())
+        })
+}
+
+/// Extracts the links of the `#auditRegistration_list` element found in `html`.
+///
+/// Returns the links resolved against `base_url` together with their trimmed
+/// link text. Returns an empty list if the snippet has no registration list.
+pub fn parse_registration_links(html: &str, base_url: &Url) -> Vec<(Url, String)> {
+    let document = Html::parse_document(html);
+    extract_registration_links(&document, base_url)
+}
+
+fn extract_registration_links(document: &Html, base_url: &Url) -> Vec<(Url, String)> {
+    let registration_links = document
+        .select(&Selector::parse("#auditRegistration_list").unwrap())
+        .next();
+    match registration_links {
+        Some(registration_links) => registration_links
+            .select(&Selector::parse("a").unwrap())
+            // anchors without a usable href are skipped
+            .filter_map(|a_node| {
+                let href = a_node.value().attr("href")?;
+                let url = base_url.join(href).ok()?;
+                let text = a_node
+                    .text()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .trim()
+                    .to_string();
+                Some((url, text))
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// problems that didn't stop a page from being parsed
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Warning {
+    MissingOu,
+    EmptyInstructors,
+    UnparsableAppointmentRow {
+        columns: Vec<String>,
+    },
+    InvalidAppointmentDate {
+        date: String,
+        time: String,
+    },
+    UnparsableAmount {
+        field: String,
+        text: String,
+    },
+    FrequencyMismatch {
+        frequency: Frequency,
+        from: String,
+        to: String,
+    },
+    // an appointments table whose header doesn't name the columns we need,
+    // read by the old positions if it has the old number of columns
+    UnknownAppointmentColumns {
+        header: Vec<String>,
+    },
+    // added by repair for a page that could not be fetched, it has no data
+    Placeholder,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::MissingOu => write!(f, "no organisational unit"),
+            Warning::EmptyInstructors => write!(f, "no instructors"),
+            Warning::UnparsableAppointmentRow { columns } => {
+                write!(f, "unparsable appointment row {:?}", columns)
+            }
+            Warning::InvalidAppointmentDate { date, time } => {
+                write!(f, "invalid appointment date {} {}", date, time)
+            }
+            Warning::UnparsableAmount { field, text } => {
+                write!(f, "unparsable {} {:?}", field, text)
+            }
+            Warning::FrequencyMismatch {
+                frequency,
+                from,
+                to,
+            } => {
+                write!(f, "{:?} appointments on {} and {}", frequency, from, to)
+            }
+            Warning::UnknownAppointmentColumns { header } => {
+                write!(f, "unknown appointment columns {:?}", header)
+            }
+            Warning::Placeholder => write!(f, "placeholder for a page that failed"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    // the page is fine, PAUL just has no data (courses without instructors exist)
+    Data,
+    // the page doesn't look like we expect, likely a layout change
+    Markup,
+}
+
+impl Warning {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Warning::MissingOu | Warning::EmptyInstructors | Warning::Placeholder => Severity::Data,
+            Warning::UnparsableAppointmentRow { .. }
+            | Warning::InvalidAppointmentDate { .. }
+            | Warning::UnparsableAmount { .. }
+            | Warning::FrequencyMismatch { .. }
+            | Warning::UnknownAppointmentColumns { .. } => Severity::Markup,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Warning::MissingOu => "missing_ou",
+            Warning::EmptyInstructors => "empty_instructors",
+            Warning::UnparsableAppointmentRow { .. } => "unparsable_appointment_row",
+            Warning::InvalidAppointmentDate { .. } => "invalid_appointment_date",
+            Warning::UnparsableAmount { .. } => "unparsable_amount",
+            Warning::FrequencyMismatch { .. } => "frequency_mismatch",
+            Warning::UnknownAppointmentColumns { .. } => "unknown_appointment_columns",
+            Warning::Placeholder => "placeholder",
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Course {
+    // empty for state files written before the url was recorded
+    #[serde(default)]
+    pub url: String,
     pub path: Path,
+    // the heading's lines with normalized whitespace: number, name and
+    // whatever PAUL adds below. empty for state files written before
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub title_lines: Vec<String>,
     pub instructors: String,
     pub ou: Option<String>,
     pub appointments: Vec<Appointment>,
+    // why appointments may be empty, none for state files written before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub appointments_table: Option<AppointmentsTable>,
     pub small_groups: Vec<String>,
+    #[serde(default)]
+    pub prerequisites: Option<String>,
+    #[serde(default)]
+    pub recommended_knowledge: Option<String>,
+    // ects, a range if the course allows different amounts
+    #[serde(default)]
+    pub credits: Option<Amount>,
+    #[serde(default)]
+    pub sws: Option<Amount>,
+    // only shown to a logged in student
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub my_status: Option<RegistrationStatus>,
+    // "zugehörige Veranstaltungen", e.g. the exercise of a lecture
+    #[serde(default)]
+    pub related_courses: Vec<CourseRef>,
+    // "Veranstaltungsart", e.g. Vorlesung or Seminar
+    #[serde(default)]
+    pub course_type: Option<String>,
+    // module pages linking the course, see modules::parse_module_page
+    #[serde(default)]
+    pub modules: Vec<String>,
+    // "Zuletzt geändert" as shown by PAUL, Paderborn wall clock time. a diff
+    // with the same value on both sides is a scraping artifact, not an edit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_updated_at: Option<chrono::NaiveDateTime>,
+    // gzip compressed, base64 encoded page, only with --embed-html all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+impl Course {
+    /// The heading's lines, split from the path for state files written before they were kept.
+    pub fn heading(&self) -> Vec<String> {
+        if !self.title_lines.is_empty() {
+            return self.title_lines.clone();
+        }
+        split_title(self.path.fragments.last().map_or("", String::as_str))
+    }
+
+    /// The heading on one line, e.g. "L.079.05401 Grundlagen der Programmierung 1".
+    pub fn display_title(&self) -> String {
+        self.heading().join(" ")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CourseRef {
+    pub url: String,
+    pub title: String,
+}
+
+// what the "Termine" table of a page looked like
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppointmentsTable {
+    // at least one appointment was parsed
+    Listed,
+    // the table is there but has no dates, e.g. asynchronous or online courses
+    Empty,
+    // the page has no appointments table at all
+    Absent,
+    // the table has rows but none of them could be parsed, see the warnings
+    Unparsable,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Appointment {
     pub start_time: (String, String),
     pub end_time: (String, String),
     pub room: String,
     pub instructors: String,
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(default)]
+    pub frequency: Frequency,
+    // what the room column says about where it takes place,
+    // unknown for state files written before
+    #[serde(default)]
+    pub location: Location,
 }
 
-pub fn parse_course_page(response: String, url: &Url, path: &Path) -> (Course, Vec<(Url, Path)>) {
-    let mut small_group_list = Vec::new();
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Location {
+    // a room on campus, named by the room column
+    Room,
+    // "Online-Veranstaltung", maybe with the meeting link
+    Online {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    // a room and a meeting link, or marked as hybrid
+    Hybrid {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    // empty, "N.N." and the like
+    #[default]
+    Unknown,
+}
+
+const ONLINE_MARKERS: &[&str] = &[
+    "online",
+    "virtuell",
+    "virtual",
+    "zoom",
+    "webex",
+    "bigbluebutton",
+    "big blue button",
+];
+
+// too short to look for inside words, "Steamsaal" is no Teams meeting
+const ONLINE_WORDS: &[&str] = &["digital", "teams"];
+
+// PAUL has no field for meeting links, they end up in the room column
+static MEETING_URL: std::sync::LazyLock<regex::Regex> =
+    std::sync::LazyLock::new(|| regex::Regex::new(r"https?://[^\s<>\)]+").unwrap());
+
+// "O2.267 / Zoom", a building letter, an optional building number and a room
+static ROOM_NUMBER: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+    regex::Regex::new(r"\b[A-Za-z][0-9]?\s?[.\s]\s?[0-9]{3}\b").unwrap()
+});
+
+impl Location {
+    pub fn parse(room: &str) -> Self {
+        let url = MEETING_URL
+            .find(room)
+            .map(|url| url.as_str().trim_end_matches(['.', ',', ';']).to_string());
+        let rest = match &url {
+            Some(url) => room.replace(url.as_str(), " "),
+            None => room.to_string(),
+        };
+        let lowercase = rest.to_lowercase();
+        let online = url.is_some()
+            || ONLINE_MARKERS
+                .iter()
+                .any(|marker| lowercase.contains(marker))
+            || lowercase
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| ONLINE_WORDS.contains(&word));
+        let has_room = ROOM_NUMBER.is_match(&rest);
+        let text = lowercase.trim().trim_matches(['-', '.', ' ']);
+        if lowercase.contains("hybrid") || (online && has_room) {
+            Location::Hybrid { url }
+        } else if online {
+            Location::Online { url }
+        } else if text.is_empty() || text == "n.n" || text == "nn" || text == "n.v" {
+            Location::Unknown
+        } else {
+            Location::Room
+        }
+    }
+
+    /// The meeting link, if the room column has one.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Location::Online { url } | Location::Hybrid { url } => url.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        *self == Location::Unknown
+    }
+}
+
+// first column of the Termine table
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Weekly,
+    Biweekly,
+    Single,
+    Block,
+    // missing or something we don't know (older pages only number the rows)
+    #[default]
+    Unknown,
+}
+
+impl Frequency {
+    pub fn parse(text: &str) -> Self {
+        let text = text.to_lowercase();
+        if text.contains("14-tägl") || text.contains("2-wöch") || text.contains("biweekly") {
+            Frequency::Biweekly
+        } else if text.contains("wöchentlich") || text.contains("wöch") || text.contains("weekly")
+        {
+            Frequency::Weekly
+        } else if text.contains("einzel") || text.contains("single") {
+            Frequency::Single
+        } else if text.contains("block") {
+            Frequency::Block
+        } else {
+            Frequency::Unknown
+        }
+    }
+
+    fn period_days(self) -> Option<i64> {
+        match self {
+            Frequency::Weekly => Some(7),
+            Frequency::Biweekly => Some(14),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_course_page(
+    response: String,
+    url: &CourseUrl,
+    path: &Path,
+) -> (Course, Vec<(SmallGroupUrl, Path)>) {
+    parse_course_page_with(response, url, path, &AppointmentColumns::default())
+}
+
+/// Like [`parse_course_page`], finding the appointment columns by `columns`.
+pub fn parse_course_page_with(
+    response: String,
+    url: &CourseUrl,
+    path: &Path,
+    columns: &AppointmentColumns,
+) -> (Course, Vec<(SmallGroupUrl, Path)>) {
+    parse_course_document(
+        &Html::parse_document(&response),
+        url,
+        path,
+        columns,
+        &LinkClassifier::default(),
+    )
+}
+
+/// Like [`parse_course_page_with`] on an already parsed page, for callers that
+/// also look for the small group listing or the combined small groups link.
+/// The module links are found with `classifier`.
+pub fn parse_course_document(
+    document: &Html,
+    url: &CourseUrl,
+    path: &Path,
+    columns: &AppointmentColumns,
+    classifier: &LinkClassifier,
+) -> (Course, Vec<(SmallGroupUrl, Path)>) {
     // soup = BeautifulSoup(html, 'html.parser')
     // title = soup.find('form', attrs={'name': 'courseform'}).find('h1').text.strip()
     // split_title = title.splitlines()
@@ -242,20 +949,15 @@ pub fn parse_course_page(response: String, url: &Url, path: &Path) -> (Course, V
     //     instructors_entry = instructors_element.text.strip()
 
     // ou_entry = soup.find('span', attrs={'name': 'courseOrgUnit'}).text.strip()
-    let document = Html::parse_document(&response);
-
-    let title = document
-        .select(&Selector::parse("form[name=courseform]").unwrap())
-        .next()
-        .unwrap()
-        .select(&Selector::parse("h1").unwrap())
-        .next()
-        .unwrap()
-        .text()
-        .collect::<Vec<_>>()
-        .join(" ")
-        .trim()
-        .to_string();
+    let title_lines = heading_lines(
+        document
+            .select(&Selector::parse("form[name=courseform]").unwrap())
+            .next()
+            .unwrap()
+            .select(&Selector::parse("h1").unwrap())
+            .next()
+            .unwrap(),
+    );
 
     let instructors = document
         .select(&Selector::parse("span#dozenten").unwrap())
@@ -274,7 +976,32 @@ pub fn parse_course_page(response: String, url: &Url, path: &Path) -> (Course, V
         // .to_string();
         .map(|span| span.text().collect::<Vec<_>>().join(" ").trim().to_string());
 
-    let appointments_list = extract_appointments(&document);
+    let (appointments_list, appointments_table, mut warnings) =
+        extract_appointments(document, columns);
+    if ou.is_none() {
+        warnings.push(Warning::MissingOu);
+    }
+    if instructors.is_empty() {
+        warnings.push(Warning::EmptyInstructors);
+    }
+
+    let language = detect_language(document);
+    let captions = language.captions();
+    let prerequisites = extract_section(document, captions.prerequisites);
+    let recommended_knowledge = extract_section(document, captions.recommended_knowledge);
+    let mut amount = |field: &str, caption: &str| {
+        let text = extract_section(document, caption)?;
+        let amount = parse_amount(&text);
+        if amount.is_none() {
+            warnings.push(Warning::UnparsableAmount {
+                field: field.to_string(),
+                text,
+            });
+        }
+        amount
+    };
+    let credits = amount("credits", captions.credits);
+    let sws = amount("sws", captions.sws);
 
     // tables: List[bs4.element.Tag] = soup.find_all('div', attrs={'class': 'tb'})
     // for table in tables:
@@ -282,43 +1009,548 @@ pub fn parse_course_page(response: String, url: &Url, path: &Path) -> (Course, V
     //     if caption and caption.text == 'Kleingruppe(n)':
     //         urls: List[str] = ['https://paul.uni-paderborn.de' + link.attrs['href'] for link in table.find_all('a')]
 
+    let (small_group_list, _) = parse_small_group_listing_document(document, url, path);
+
+    (
+        Course {
+            url: url.as_str().to_string(),
+            path: path.push_segment(PathSegmentKind::Course, title_lines.join("\n"), Some(url)),
+            title_lines,
+            instructors,
+            ou,
+            appointments: appointments_list,
+            appointments_table: Some(appointments_table),
+            small_groups: small_group_list
+                .iter()
+                .map(|(url, _)| url.as_str().to_string())
+                .collect(),
+            prerequisites,
+            recommended_knowledge,
+            credits,
+            sws,
+            my_status: extract_my_status(document),
+            related_courses: extract_related_courses(document, url, captions.related_courses),
+            course_type: extract_labeled_value(document, captions.course_type)
+                .or_else(|| extract_section(document, captions.course_type)),
+            modules: extract_links(document, url, LinkKind::Module, classifier)
+                .into_iter()
+                .map(|(url, _)| url.to_string())
+                .collect(),
+            source_updated_at: extract_last_updated(document, language),
+            html: None,
+            warnings,
+        },
+        small_group_list,
+    )
+}
+
+/// Splits a course heading into its lines, number first and name second,
+/// normalizing the whitespace of each line.
+pub fn split_title(title: &str) -> Vec<String> {
+    let mut lines = title
+        .lines()
+        .map(|line| normalize_text(std::iter::once(line)))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    // some pages put the number and the name on one line
+    if let [line] = lines.as_slice() {
+        if let Some((number, name)) = line.split_once(' ') {
+            if number.contains('.') && number.chars().any(|c| c.is_ascii_digit()) {
+                lines = vec![number.to_string(), name.to_string()];
+            }
+        }
+    }
+    lines
+}
+
+fn heading_lines(heading: ElementRef) -> Vec<String> {
+    // like .text, but a <br> breaks the line too
+    let mut text = String::new();
+    for node in heading.descendants() {
+        match node.value() {
+            Node::Text(part) => text.push_str(part),
+            Node::Element(element) if element.name() == "br" => text.push('\n'),
+            _ => {}
+        }
+    }
+    split_title(&text)
+}
+
+fn extract_related_courses(document: &Html, url: &Url, title: &str) -> Vec<CourseRef> {
+    // a box like the small groups, or a table captioned like the appointments
+    let boxes_selector = Selector::parse("div.tb").unwrap();
+    let boxes = document.select(&boxes_selector).filter(|container| {
+        container
+            .select(&Selector::parse("div.tbhead").unwrap())
+            .next()
+            .is_some_and(|caption| {
+                normalize_text(caption.text())
+                    .trim_end_matches(':')
+                    .eq_ignore_ascii_case(title)
+            })
+    });
+    let tables = find_tables_by_caption(document.root_element(), title)
+        .into_iter()
+        .map(|table| table.element());
+    let mut related = Vec::new();
+    for container in boxes.chain(tables) {
+        for a in container.select(&Selector::parse("a[href]").unwrap()) {
+            let href = a.value().attr("href").unwrap();
+            if !href.contains("COURSEDETAILS") {
+                continue;
+            }
+            let Ok(course_url) = url.join(href) else {
+                continue;
+            };
+            let course_ref = CourseRef {
+                url: course_url.to_string(),
+                title: normalize_text(a.text()),
+            };
+            if !related.contains(&course_ref) {
+                related.push(course_ref);
+            }
+        }
+    }
+    related
+}
+
+/// Extracts the small group links of a course page or of one of the
+/// continuation pages of its small group listing, plus the next page of the listing.
+pub fn parse_small_group_listing(
+    response: &str,
+    url: &Url,
+    path: &Path,
+) -> (Vec<(SmallGroupUrl, Path)>, Option<Url>) {
+    parse_small_group_listing_document(&Html::parse_document(response), url, path)
+}
+
+/// Like [`parse_small_group_listing`] on an already parsed page.
+pub fn parse_small_group_listing_document(
+    document: &Html,
+    url: &Url,
+    path: &Path,
+) -> (Vec<(SmallGroupUrl, Path)>, Option<Url>) {
+    let captions = detect_language(document).captions();
+    let mut small_group_list = Vec::new();
+    let mut next_page = None;
     let tables_selector = Selector::parse("div.tb").unwrap();
     let tables = document.select(&tables_selector);
 
     for table in tables {
         let caption = table.select(&Selector::parse("div.tbhead").unwrap()).next();
         if let Some(caption) = caption {
-            if caption.text().collect::<Vec<_>>().join(" ").trim() == "Kleingruppe(n)" {
+            if caption
+                .text()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .trim()
+                .eq_ignore_ascii_case(captions.small_groups)
+            {
+                // courses with many small groups page through them
                 let urls = table
                     .select(&Selector::parse("a").unwrap())
+                    .filter(|a| !is_pagination_link(a) && !is_all_small_groups_link(a, captions))
                     .map(|a| {
                         let href = a.value().attr("href").unwrap();
                         url.join(href).unwrap()
                     })
                     .collect::<Vec<_>>();
                 for url in urls {
-                    small_group_list.push((url, path.clone()));
+                    small_group_list.push((SmallGroupUrl::new(url), path.clone()));
                 }
+                next_page = next_page.or_else(|| next_page_link(table, url));
             }
         }
     }
+    (small_group_list, next_page)
+}
 
-    (
-        Course {
-            path: path.push(title),
-            instructors,
-            ou,
-            appointments: appointments_list,
-            small_groups: small_group_list
-                .iter()
-                .map(|(url, _)| url.as_str().to_string())
-                .collect(),
-        },
-        small_group_list,
-    )
+fn is_all_small_groups_link(a: &ElementRef, captions: &Captions) -> bool {
+    // "Alle Kleingruppen anzeigen"
+    normalize_text(a.text())
+        .to_lowercase()
+        .starts_with(&captions.all_small_groups.to_lowercase())
+}
+
+/// The link to the page listing every small group of a course with its dates,
+/// if the course page offers one.
+pub fn combined_small_groups_link(response: &str, url: &Url) -> Option<Url> {
+    combined_small_groups_link_document(&Html::parse_document(response), url)
+}
+
+/// Like [`combined_small_groups_link`] on an already parsed page.
+pub fn combined_small_groups_link_document(document: &Html, url: &Url) -> Option<Url> {
+    let captions = detect_language(document).captions();
+    let link = document
+        .select(&Selector::parse("div.tb a[href]").unwrap())
+        .find(|a| is_all_small_groups_link(a, captions))?;
+    url.join(link.value().attr("href")?).ok()
+}
+
+/// Parses the page listing every small group of a course, one request
+/// instead of one per group.
+///
+/// Each group is a box captioned with a link to the group's own page, the
+/// url the group is known by. Boxes without that link are left out, the
+/// caller has to fetch groups missing here one by one.
+pub fn parse_combined_small_groups(response: &str, url: &Url, path: &Path) -> Vec<SmallGroup> {
+    parse_combined_small_groups_with(response, url, path, &AppointmentColumns::default())
+}
+
+/// Like [`parse_combined_small_groups`], finding the appointment columns by `columns`.
+pub fn parse_combined_small_groups_with(
+    response: &str,
+    url: &Url,
+    path: &Path,
+    columns: &AppointmentColumns,
+) -> Vec<SmallGroup> {
+    let document = Html::parse_document(response);
+    let captions = detect_language(&document).captions();
+    let mut small_groups = Vec::new();
+    for group in document.select(&Selector::parse("div.tb").unwrap()) {
+        let Some(caption) = group.select(&Selector::parse("div.tbhead").unwrap()).next() else {
+            continue;
+        };
+        let Some(group_url) = caption
+            .select(&Selector::parse("a[href]").unwrap())
+            .next()
+            .and_then(|a| url.join(a.value().attr("href")?).ok())
+        else {
+            continue;
+        };
+        let group_url = SmallGroupUrl::new(group_url);
+        let title = normalize_text(caption.text());
+        let (appointments, appointments_table, warnings) =
+            extract_appointments_in(group, captions, columns);
+        let (name, group_number) = parse_small_group_title(&title);
+        small_groups.push(SmallGroup {
+            url: group_url.as_str().to_string(),
+            name,
+            group_number,
+            path: path.push_segment(PathSegmentKind::SmallGroup, title, Some(&group_url)),
+            appointments,
+            appointments_table: Some(appointments_table),
+            my_status: extract_my_status_in(group),
+            html: None,
+            warnings,
+        });
+    }
+    small_groups
+}
+
+const NEXT_PAGE_LABELS: &[&str] = &[
+    ">",
+    "»",
+    ">>",
+    "weiter",
+    "nächste seite",
+    "next",
+    "next page",
+];
+const PREVIOUS_PAGE_LABELS: &[&str] = &["<", "«", "<<", "zurück", "vorherige seite", "previous"];
+
+fn is_next_page_link(a: &ElementRef) -> bool {
+    let text = normalize_text(a.text()).to_lowercase();
+    let title = a.value().attr("title").unwrap_or_default().to_lowercase();
+    NEXT_PAGE_LABELS.contains(&text.as_str())
+        || title.contains("nächste")
+        || title.contains("next")
+        || a.value()
+            .attr("class")
+            .is_some_and(|class| class.to_lowercase().contains("next"))
+}
+
+fn is_pagination_link(a: &ElementRef) -> bool {
+    // page numbers, previous/next arrows and anything styled as a pager
+    let text = normalize_text(a.text()).to_lowercase();
+    is_next_page_link(a)
+        || (!text.is_empty() && text.chars().all(|c| c.is_ascii_digit()))
+        || PREVIOUS_PAGE_LABELS.contains(&text.as_str())
+        || a.value()
+            .attr("class")
+            .is_some_and(|class| class.to_lowercase().contains("page"))
+}
+
+fn next_page_link(container: ElementRef, url: &Url) -> Option<Url> {
+    container
+        .select(&Selector::parse("a[href]").unwrap())
+        .find(is_next_page_link)
+        .and_then(|a| url.join(a.value().attr("href").unwrap()).ok())
+        // the last page sometimes links to itself
+        .filter(|next| next != url)
+}
+
+// every link of the page to pages of `kind` with its text, each url once
+fn extract_links(
+    document: &Html,
+    url: &Url,
+    kind: LinkKind,
+    classifier: &LinkClassifier,
+) -> Vec<(Url, String)> {
+    let mut links: Vec<(Url, String)> = Vec::new();
+    for a in document.select(&Selector::parse("a[href]").unwrap()) {
+        let href = a.value().attr("href").unwrap();
+        if classifier.kind(href) != Some(kind) {
+            continue;
+        }
+        let Ok(link_url) = url.join(href) else {
+            continue;
+        };
+        if !links.iter().any(|(known, _)| *known == link_url) {
+            links.push((link_url, normalize_text(a.text())));
+        }
+    }
+    links
+}
+
+// "Zuletzt geändert: 12.03.2024 14:35", sometimes without the time
+fn last_updated_regex(language: Language) -> regex::Regex {
+    regex::Regex::new(&format!(
+        r"(?i){}:?\s*(\d{{1,2}})\.(\d{{1,2}})\.(\d{{4}})(?:,?\s*(\d{{1,2}}):(\d{{2}}))?",
+        regex::escape(language.captions().last_updated)
+    ))
+    .unwrap()
 }
 
-fn extract_appointments(document: &Html) -> Vec<Appointment> {
+static LAST_UPDATED_GERMAN: std::sync::LazyLock<regex::Regex> =
+    std::sync::LazyLock::new(|| last_updated_regex(Language::German));
+static LAST_UPDATED_ENGLISH: std::sync::LazyLock<regex::Regex> =
+    std::sync::LazyLock::new(|| last_updated_regex(Language::English));
+
+fn extract_last_updated(document: &Html, language: Language) -> Option<chrono::NaiveDateTime> {
+    let text = normalize_text(document.root_element().text());
+    let regex = match language {
+        Language::German => &LAST_UPDATED_GERMAN,
+        Language::English => &LAST_UPDATED_ENGLISH,
+    };
+    let captures = regex.captures(&text)?;
+    let number = |i: usize| captures.get(i).and_then(|m| m.as_str().parse::<u32>().ok());
+    let date = chrono::NaiveDate::from_ymd_opt(number(3)? as i32, number(2)?, number(1)?)?;
+    date.and_hms_opt(number(4).unwrap_or(0), number(5).unwrap_or(0), 0)
+}
+
+fn normalize_text<'a>(text: impl Iterator<Item = &'a str>) -> String {
+    text.flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_heading(element: &ElementRef) -> bool {
+    // either a real heading or a paragraph that only consists of bold text
+    match element.value().name() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "dt" | "th" | "strong" | "b" => true,
+        "p" | "div" => {
+            let bold = element
+                .select(&Selector::parse("strong, b").unwrap())
+                .map(|bold| normalize_text(bold.text()))
+                .collect::<Vec<_>>()
+                .join(" ");
+            !bold.is_empty() && bold == normalize_text(element.text())
+        }
+        _ => false,
+    }
+}
+
+fn extract_labeled_value(document: &Html, label: &str) -> Option<String> {
+    // <tr><td>[label]:</td><td>value</td></tr> like the instructors and the ou
+    let cell_selector = Selector::parse("td, th").unwrap();
+    document
+        .select(&Selector::parse("tr").unwrap())
+        .find_map(|row| {
+            let mut cells = row.select(&cell_selector);
+            let key = normalize_text(cells.next()?.text());
+            if !key.trim_end_matches(':').eq_ignore_ascii_case(label) {
+                return None;
+            }
+            Some(normalize_text(cells.next()?.text()))
+        })
+        .filter(|value| !value.is_empty())
+}
+
+fn extract_section(document: &Html, title: &str) -> Option<String> {
+    // PAUL renders free text sections either as their own box
+    // <div class="tb"><div class="tbhead">[title]</div>...</div>
+    // or as a bold heading inside the description followed by the text
+    for table in document.select(&Selector::parse("div.tb").unwrap()) {
+        let caption = table.select(&Selector::parse("div.tbhead").unwrap()).next();
+        if let Some(caption) = caption {
+            if normalize_text(caption.text())
+                .trim_end_matches(':')
+                .eq_ignore_ascii_case(title)
+            {
+                let text = table
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|child| *child != caption)
+                    .map(|child| normalize_text(child.text()))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                return Some(text).filter(|text| !text.is_empty());
+            }
+        }
+    }
+
+    let headings = Selector::parse("h1, h2, h3, h4, h5, h6, dt, th, strong, b, p").unwrap();
+    for heading in document.select(&headings) {
+        let heading_text = normalize_text(heading.text());
+        if !heading_text
+            .to_ascii_lowercase()
+            .starts_with(&title.to_ascii_lowercase())
+        {
+            continue;
+        }
+        let rest = &heading_text[title.len()..];
+        let rest = rest.trim_start_matches(':').trim();
+        if !rest.is_empty() {
+            // "Voraussetzungen: ..." in a single element
+            return Some(rest.to_string());
+        }
+        // a bold heading wrapped in its own paragraph, continue after the paragraph
+        let mut anchor = heading;
+        while let Some(parent) = anchor.parent().and_then(ElementRef::wrap) {
+            if !matches!(parent.value().name(), "p" | "div" | "span")
+                || normalize_text(parent.text()) != heading_text
+            {
+                break;
+            }
+            anchor = parent;
+        }
+        let mut text = Vec::new();
+        for sibling in anchor.next_siblings() {
+            match ElementRef::wrap(sibling) {
+                Some(element) if is_heading(&element) => break,
+                Some(element) => text.push(normalize_text(element.text())),
+                None => {
+                    if let Some(sibling_text) = sibling.value().as_text() {
+                        text.push(normalize_text(std::iter::once(&**sibling_text)));
+                    }
+                }
+            }
+        }
+        let text = text
+            .into_iter()
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        return Some(text).filter(|text| !text.is_empty());
+    }
+    None
+}
+
+/// Extracts the appointments of every table captioned "Termine" (or "Dates" on english
+/// pages) found in `html`.
+///
+/// Columns are found by their header, see [`AppointmentColumns`]. Rows missing a column
+/// and references to course appointments (dates marked with `*`) are skipped. Rows marked
+/// as cancelled (e.g. "fällt aus" or struck through) are kept with `cancelled` set. Dates
+/// and times are kept as displayed by PAUL, e.g. `("Di, 18. Apr. 2023", "11:00")`.
+pub fn parse_appointments_table(html: &str) -> Vec<Appointment> {
+    let document = Html::parse_document(html);
+    extract_appointments(&document, &AppointmentColumns::default()).0
+}
+
+/// Tells why [`parse_appointments_table`] found no appointments in `html`, see [`AppointmentsTable`].
+pub fn appointments_table_status(html: &str) -> AppointmentsTable {
+    let document = Html::parse_document(html);
+    extract_appointments(&document, &AppointmentColumns::default()).1
+}
+
+/// The header texts that identify the columns of an appointments table, any of
+/// them matches, ignoring case. A config file can override them when PAUL
+/// renames a column, adding or reordering columns needs no change.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppointmentColumns {
+    pub date: Vec<String>,
+    pub start: Vec<String>,
+    pub end: Vec<String>,
+    pub room: Vec<String>,
+    pub instructors: Vec<String>,
+}
+
+impl Default for AppointmentColumns {
+    fn default() -> Self {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            date: names(&["Datum", "Date"]),
+            start: names(&["von", "from"]),
+            end: names(&["bis", "to"]),
+            room: names(&["Raum", "Room"]),
+            instructors: names(&["Lehrende", "Lecturers", "Instructors"]),
+        }
+    }
+}
+
+// where the fields are in a row of one table
+struct ColumnIndices {
+    frequency: Option<usize>,
+    date: usize,
+    start: usize,
+    end: usize,
+    room: Option<usize>,
+    instructors: Option<usize>,
+    // rows of the old layout have exactly this many cells
+    cells: Option<usize>,
+}
+
+impl AppointmentColumns {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn find(&self, table: &Table) -> Option<ColumnIndices> {
+        let find = |names: &[String]| names.iter().find_map(|name| table.column(name));
+        Some(ColumnIndices {
+            // the unlabeled column holds the frequency, "wöchentlich" etc.
+            frequency: table.header.iter().position(String::is_empty),
+            date: find(&self.date)?,
+            start: find(&self.start)?,
+            end: find(&self.end)?,
+            room: find(&self.room),
+            instructors: find(&self.instructors),
+            cells: None,
+        })
+    }
+
+    // the positions before header matching if the header lacks date or times
+    fn resolve(&self, table: &Table) -> (ColumnIndices, Option<Warning>) {
+        if let Some(indices) = self.find(table) {
+            return (indices, None);
+        }
+        let legacy = ColumnIndices {
+            frequency: Some(0),
+            date: 1,
+            start: 2,
+            end: 3,
+            room: Some(4),
+            instructors: Some(5),
+            cells: Some(6),
+        };
+        // a header without labels says nothing about the layout
+        let warning = table
+            .header
+            .iter()
+            .any(|header| !header.is_empty())
+            .then(|| Warning::UnknownAppointmentColumns {
+                header: table.header.clone(),
+            });
+        (legacy, warning)
+    }
+}
+
+fn extract_appointments(
+    document: &Html,
+    columns: &AppointmentColumns,
+) -> (Vec<Appointment>, AppointmentsTable, Vec<Warning>) {
+    let captions = detect_language(document).captions();
+    extract_appointments_in(document.root_element(), captions, columns)
+}
+
+fn extract_appointments_in(
+    root: ElementRef,
+    captions: &Captions,
+    columns: &AppointmentColumns,
+) -> (Vec<Appointment>, AppointmentsTable, Vec<Warning>) {
     // appointments: List[schemas.Appointment] = []
 
     // tables: List[bs4.element.Tag] = soup.find_all('table')
@@ -345,51 +1577,213 @@ fn extract_appointments(document: &Html) -> Vec<Appointment> {
     //                 instructors=columns[5]
     //             ))
     let mut appointments_list = Vec::new();
-    let tables_selector = Selector::parse("table").unwrap();
-
-    let tables = document.select(&tables_selector);
-
-    for table in tables {
-        let caption = table.select(&Selector::parse("caption").unwrap()).next();
-        if let Some(caption) = caption {
-            if caption.text().collect::<Vec<_>>().join(" ").trim() == "Termine" {
-                let rows_selector = Selector::parse("tr").unwrap();
-                let rows = table.select(&rows_selector);
-                for row in rows.skip(1) {
-                    let columns = row
-                        .select(&Selector::parse("td").unwrap())
-                        .map(|td| td.text().collect::<Vec<_>>().join(" ").trim().to_string())
-                        .collect::<Vec<_>>();
-                    if columns.len() != 6 {
-                        continue;
-                    }
-                    if columns[1].contains('*') {
-                        continue;
-                    }
-                    appointments_list.push(Appointment {
-                        start_time: (columns[1].clone(), columns[2].clone()),
-                        end_time: (columns[1].clone(), columns[3].clone()),
-                        room: columns[4].split_whitespace().collect::<Vec<_>>().join(" "),
-                        instructors: columns[5].clone(),
+    let mut warnings = Vec::new();
+    let (mut found_table, mut unparsable_rows) = (false, false);
+    for table in find_tables_by_caption(root, captions.appointments) {
+        found_table = true;
+        let (indices, warning) = columns.resolve(&table);
+        // tables with only notes don't need their columns
+        warnings.extend(warning.filter(|_| table.rows().iter().any(|row| row.len() > 1)));
+        for (row, columns) in table.row_elements() {
+            // single cell rows are notes like "no further dates"
+            if columns.len() <= 1 {
+                continue;
+            }
+            let cell = |index: Option<usize>| {
+                index
+                    .and_then(|index| columns.get(index))
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let (Some(date), Some(start), Some(end), true) = (
+                columns.get(indices.date),
+                columns.get(indices.start),
+                columns.get(indices.end),
+                indices.cells.is_none_or(|cells| cells == columns.len()),
+            ) else {
+                unparsable_rows = true;
+                warnings.push(Warning::UnparsableAppointmentRow { columns });
+                continue;
+            };
+            if date.contains('*') {
+                continue;
+            }
+            for time in [start, end] {
+                if datetime::parse_datetime(date, time).is_none() {
+                    warnings.push(Warning::InvalidAppointmentDate {
+                        date: date.clone(),
+                        time: time.clone(),
                     });
                 }
             }
+            let room = cell(indices.room)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            appointments_list.push(Appointment {
+                start_time: (date.clone(), start.clone()),
+                end_time: (date.clone(), end.clone()),
+                location: Location::parse(&room),
+                room,
+                instructors: cell(indices.instructors),
+                cancelled: is_cancelled(&row),
+                frequency: Frequency::parse(&cell(indices.frequency)),
+            });
+        }
+    }
+    warnings.extend(check_frequencies(&appointments_list));
+    let status = if !appointments_list.is_empty() {
+        AppointmentsTable::Listed
+    } else if !found_table {
+        AppointmentsTable::Absent
+    } else if unparsable_rows {
+        AppointmentsTable::Unparsable
+    } else {
+        AppointmentsTable::Empty
+    };
+    (appointments_list, status, warnings)
+}
+
+fn check_frequencies(appointments: &[Appointment]) -> Vec<Warning> {
+    // consecutive dates of a weekly series have to be whole weeks apart,
+    // holidays only ever skip weeks
+    let mut warnings = Vec::new();
+    for pair in appointments.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        let Some(period) = a.frequency.period_days() else {
+            continue;
+        };
+        if a.frequency != b.frequency || a.start_time.1 != b.start_time.1 {
+            continue;
+        }
+        let (Some(from), Some(to)) = (
+            datetime::parse_date(&a.start_time.0),
+            datetime::parse_date(&b.start_time.0),
+        ) else {
+            continue;
+        };
+        if (to - from).num_days() % period != 0 {
+            warnings.push(Warning::FrequencyMismatch {
+                frequency: a.frequency,
+                from: a.start_time.0.clone(),
+                to: b.start_time.0.clone(),
+            });
         }
     }
-    appointments_list
+    warnings
+}
+
+const CANCELLED_MARKERS: &[&str] = &[
+    "fällt aus",
+    "entfällt",
+    "abgesagt",
+    "ausgefallen",
+    "cancelled",
+    "canceled",
+];
+
+fn is_cancelled(row: &ElementRef) -> bool {
+    // cancelled dates are either annotated or struck through
+    let text = row.text().collect::<String>().to_lowercase();
+    if CANCELLED_MARKERS.iter().any(|marker| text.contains(marker)) {
+        return true;
+    }
+    if row
+        .select(&Selector::parse("s, del, strike").unwrap())
+        .next()
+        .is_some()
+    {
+        return true;
+    }
+    std::iter::once(*row)
+        .chain(row.select(&Selector::parse("td").unwrap()))
+        .any(|element| {
+            let class = element.value().attr("class").unwrap_or_default();
+            let style = element.value().attr("style").unwrap_or_default();
+            class.contains("cancel") || style.contains("line-through")
+        })
+}
+
+/// Checks whether a fetched page still shows a course or small group.
+///
+/// PAUL answers unknown course ids with a regular page that lacks the course form.
+pub fn is_course_page(response: &str) -> bool {
+    let document = Html::parse_document(response);
+    document
+        .select(&Selector::parse("form[name=courseform] h1, form[name=courseform] h2").unwrap())
+        .next()
+        .is_some()
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SmallGroup {
     pub url: String,
     pub path: Path,
+    // the title without the "Kleingruppe:" prefix, see parse_small_group_title.
+    // empty in state files written before it was recorded
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_number: Option<u32>,
     pub appointments: Vec<Appointment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub appointments_table: Option<AppointmentsTable>,
+    // only shown to a logged in student
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub my_status: Option<RegistrationStatus>,
+    // gzip compressed, base64 encoded page, only with --embed-html all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
 }
 
-pub fn parse_small_group(response: String, url: &Url, path: &Path) -> SmallGroup {
+/// Splits a small group title like "Kleingruppe: Übung 03, Di 14-16" into
+/// the display name "Übung 03, Di 14-16" and the group number 3.
+///
+/// The number is the first one before a comma, the rest is usually a time hint.
+pub fn parse_small_group_title(title: &str) -> (String, Option<u32>) {
+    // PAUL separates the prefix with a non-breaking space
+    let name = normalize_text(title.split_whitespace());
+    let name = ["Kleingruppe:", "Small group:"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(&name)
+        .trim()
+        .to_string();
+    let group_number = name
+        .split(',')
+        .next()
+        .unwrap_or_default()
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|digits| !digits.is_empty())
+        .and_then(|digits| digits.parse().ok());
+    (name, group_number)
+}
+
+pub fn parse_small_group(response: String, url: &SmallGroupUrl, path: &Path) -> SmallGroup {
+    parse_small_group_with(response, url, path, &AppointmentColumns::default())
+}
+
+/// Like [`parse_small_group`], finding the appointment columns by `columns`.
+pub fn parse_small_group_with(
+    response: String,
+    url: &SmallGroupUrl,
+    path: &Path,
+    columns: &AppointmentColumns,
+) -> SmallGroup {
+    parse_small_group_document(&Html::parse_document(&response), url, path, columns)
+}
+
+/// Like [`parse_small_group_with`] on an already parsed page.
+pub fn parse_small_group_document(
+    document: &Html,
+    url: &SmallGroupUrl,
+    path: &Path,
+    columns: &AppointmentColumns,
+) -> SmallGroup {
     // soup = BeautifulSoup(html, 'html.parser')
     // title = soup.find('form', attrs={'name': 'courseform'}).find('h2').text.strip()
-    let document = Html::parse_document(&response);
 
     let title = document
         .select(&Selector::parse("form[name=courseform]").unwrap())
@@ -404,19 +1798,124 @@ pub fn parse_small_group(response: String, url: &Url, path: &Path) -> SmallGroup
         .trim()
         .to_string();
 
-    let appointments_list = extract_appointments(&document);
+    let (appointments_list, appointments_table, warnings) = extract_appointments(document, columns);
+    let (name, group_number) = parse_small_group_title(&title);
 
     SmallGroup {
         url: url.as_str().to_string(),
-        path: path.push(title),
+        name,
+        group_number,
+        path: path.push_segment(PathSegmentKind::SmallGroup, title, Some(url)),
         appointments: appointments_list,
+        appointments_table: Some(appointments_table),
+        my_status: extract_my_status(document),
+        html: None,
+        warnings,
     }
 }
 
-#[derive(Serialize, Deserialize)]
+// the logged in student's registration for a course or small group
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RegistrationStatus {
+    // registered, not decided yet
+    Registered,
+    Accepted,
+    Waitlisted { position: Option<u32> },
+    Rejected,
+}
+
+impl RegistrationStatus {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        if text.starts_with("warteliste") || text.starts_with("waiting list") {
+            // "Warteliste Platz 3"
+            let position = text
+                .split(|c: char| !c.is_ascii_digit())
+                .find(|part| !part.is_empty())
+                .and_then(|part| part.parse().ok());
+            Some(RegistrationStatus::Waitlisted { position })
+        } else if text.starts_with("zugelassen") || text.starts_with("accepted") {
+            Some(RegistrationStatus::Accepted)
+        } else if text.starts_with("angemeldet") || text.starts_with("registered") {
+            Some(RegistrationStatus::Registered)
+        } else if text.starts_with("abgelehnt") || text.starts_with("rejected") {
+            Some(RegistrationStatus::Rejected)
+        } else {
+            None
+        }
+    }
+}
+
+fn extract_my_status(document: &Html) -> Option<RegistrationStatus> {
+    // the status is a short label inside the course form, long texts
+    // are descriptions that happen to start with one of the words
+    let form = document
+        .select(&Selector::parse("form[name=courseform]").unwrap())
+        .next()?;
+    extract_my_status_in(form)
+}
+
+fn extract_my_status_in(root: ElementRef) -> Option<RegistrationStatus> {
+    root.select(&Selector::parse("span, td, div, p, strong, b").unwrap())
+        .filter(|element| {
+            !element
+                .children()
+                .any(|child| ElementRef::wrap(child).is_some_and(|child| !is_inline(&child)))
+        })
+        .map(|element| normalize_text(element.text()))
+        .filter(|text| text.len() <= 40)
+        .find_map(|text| RegistrationStatus::parse(&text))
+}
+
+fn is_inline(element: &ElementRef) -> bool {
+    matches!(
+        element.value().name(),
+        "span" | "strong" | "b" | "em" | "i" | "a" | "br"
+    )
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StateSerializable {
     pub semester: String,
     pub start_time: chrono::DateTime<chrono::Utc>,
     pub courses: Vec<Course>,
     pub small_groups: Vec<SmallGroup>,
+    #[serde(default)]
+    pub failed_pages: Vec<FailedPage>,
+}
+
+// a page that was fetched but couldn't be parsed
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FailedPage {
+    pub url: String,
+    pub path: Path,
+    pub error: String,
+    // none for state files written before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<error::ErrorKind>,
+    // gzip compressed, base64 encoded page, unless --embed-html none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    // for parse failures, the sanitized start of where the parser looked,
+    // see excerpt::page_excerpt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+}
+
+// what to paste into a bug report
+impl std::fmt::Display for FailedPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\n  at {}\n  in {}",
+            self.error,
+            self.url,
+            self.path.fragments.join(" > ").replace('\n', " ")
+        )?;
+        if let Some(excerpt) = &self.excerpt {
+            write!(f, "\n  page: {}", excerpt)?;
+        }
+        Ok(())
+    }
 }
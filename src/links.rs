@@ -0,0 +1,146 @@
+use std::{fmt, ops::Deref};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+// urls typed by the kind of page behind them, so a course page can't be handed
+// to the tree parser by accident. they serialize as the plain url, state and
+// checkpoint files look the same as before
+
+macro_rules! page_url {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(Url);
+
+        impl $name {
+            /// Wraps a url the caller knows to point to this kind of page.
+            pub fn new(url: Url) -> Self {
+                Self(url)
+            }
+
+            pub fn as_url(&self) -> &Url {
+                &self.0
+            }
+
+            pub fn into_url(self) -> Url {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = Url;
+
+            fn deref(&self) -> &Url {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+page_url!(
+    /// A category page listing courses and further categories, or a semester.
+    TreeUrl
+);
+page_url!(
+    /// A course page.
+    CourseUrl
+);
+page_url!(
+    /// A small group page, only known from the listing of its course.
+    SmallGroupUrl
+);
+page_url!(
+    /// A module page of the module handbook.
+    ModuleUrl
+);
+
+/// What a link points to, by the PRGNAME in its href.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Tree,
+    Course,
+    Exam,
+    Module,
+    Room,
+}
+
+impl std::str::FromStr for LinkKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(kind.to_string()))
+            .map_err(|_| format!("unknown link kind {}", kind))
+    }
+}
+
+/// A link of a category listing, typed by what it points to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Link {
+    Tree(TreeUrl),
+    Course(CourseUrl),
+    // pages the crawler has no parser for, reported instead of followed
+    Other(LinkKind, Url),
+}
+
+/// Maps markers in hrefs to the kind of page they link to, first match wins.
+#[derive(Clone, Debug)]
+pub struct LinkClassifier {
+    markers: Vec<(String, LinkKind)>,
+}
+
+impl Default for LinkClassifier {
+    fn default() -> Self {
+        Self {
+            markers: vec![
+                ("COURSEOFFERINGCLUSTER".to_string(), LinkKind::Tree),
+                ("COURSEDETAILS".to_string(), LinkKind::Course),
+                ("EXAMDETAILS".to_string(), LinkKind::Exam),
+                ("MODULEDETAILS".to_string(), LinkKind::Module),
+                ("ROOMDETAILS".to_string(), LinkKind::Room),
+            ],
+        }
+    }
+}
+
+impl LinkClassifier {
+    /// Adds a marker, checked before the ones registered earlier.
+    pub fn register(&mut self, marker: impl Into<String>, kind: LinkKind) {
+        self.markers.insert(0, (marker.into(), kind));
+    }
+
+    pub fn kind(&self, href: &str) -> Option<LinkKind> {
+        self.markers
+            .iter()
+            .find(|(marker, _)| href.contains(marker.as_str()))
+            .map(|(_, kind)| *kind)
+    }
+
+    /// Classifies a link of a category listing by its href, resolved against `base`.
+    ///
+    /// Returns `None` for links without a known marker.
+    pub fn classify(&self, base: &Url, href: &str) -> Option<Link> {
+        // small groups are linked as COURSEDETAILS too, they're told apart by the
+        // table they're listed in, not by their href
+        let kind = self.kind(href)?;
+        let url = base.join(href).ok()?;
+        Some(match kind {
+            LinkKind::Tree => Link::Tree(TreeUrl::new(url)),
+            LinkKind::Course => Link::Course(CourseUrl::new(url)),
+            kind => Link::Other(kind, url),
+        })
+    }
+}
+
+/// [`LinkClassifier::classify`] with the default markers.
+pub fn classify_link(base: &Url, href: &str) -> Option<Link> {
+    LinkClassifier::default().classify(base, href)
+}
@@ -1,71 +1,446 @@
-use clap::Parser;
-use indicatif::{MultiProgress, ProgressBar};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use clap::{ArgEnum, CommandFactory, Parser, Subcommand};
+use flate2::{write::GzEncoder, Compression};
 use paul_scrape_rs::{
-    get_semesters, parse_course_page, parse_courses_and_branches, parse_small_group, Course,
-    CoursePage, Path, SmallGroup, StateSerializable,
+    analysis::find_free_slots,
+    cache::HtmlCache,
+    combined_small_groups_link_document,
+    config::{
+        client_builder, config_path, load_dotenv, user_cache_dir, Config, Politeness, CONFIG_FILE,
+    },
+    cookies::CookieJar,
+    error::ErrorKind,
+    excerpt::{page_excerpt, COURSE_PAGE, MODULE_PAGE, SMALL_GROUP_LISTING, TREE_PAGE},
+    fetch::{fetch_page_retrying, percentile, IpFamily, ResponseLimits, TimedResolver, Timings},
+    get_semesters,
+    graph::{CrawlGraph, NodeKind},
+    is_course_page,
+    links::{CourseUrl, LinkClassifier, LinkKind, ModuleUrl, SmallGroupUrl, TreeUrl},
+    matches_course_type,
+    modules::{parse_module_page_with, Module},
+    numbers::Amount,
+    pacing::Pacer,
+    pack, parse_combined_small_groups_with, parse_course_document, parse_course_page,
+    parse_small_group, parse_small_group_listing, parse_small_group_listing_document,
+    parse_small_group_title, parse_small_group_with, parse_tree_page_with,
+    partition::{merge_states, partition_by_faculty},
+    postprocess::{apply_post_processors, post_processors_by_name, CoursePostProcessor},
+    progress::{
+        BarProgress, FacultyCounts, FacultyEvent, FacultyTally, LogProgress, NoProgress,
+        ProgressKind, ProgressSink,
+    },
+    resolve_semester,
+    search::find_courses,
+    sink::{open_sink, Sink},
+    snapshot::read_snapshot,
+    spill::Spill,
+    status::{run_status, Outcome},
+    Appointment, AppointmentColumns, CatalogEntry, Course, CoursePage, FailedPage, Path,
+    PathSegmentKind, Severity, SmallGroup, StateSerializable, TreePage, Warning,
 };
 use rand::Rng;
 use reqwest::Url;
-use std::{collections::VecDeque, env, fs::File, sync::Arc};
-use tokio::sync::Mutex;
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    env,
+    fs::File,
+    io::{BufRead, LineWriter, Write},
+    sync::Arc,
+};
+use tokio::{
+    sync::{Mutex, Notify},
+    time::Instant,
+};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true)]
 struct Args {
-    // base url
-    #[clap(default_value_t = Url::parse(&env::var("BASE_URL").unwrap_or("https://paul.uni-paderborn.de".to_string())).unwrap())]
-    base_url: Url,
-    // semester
-    #[clap(default_value_t = env::var("SEMESTER").unwrap_or("Sommer 2023".to_string()))]
-    semester: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// print a man page and exit
+    #[clap(long)]
+    man: bool,
+    /// base url, falls back to $BASE_URL, then paul-scrape.toml
+    base_url: Option<Url>,
+    /// comma separated semesters, "current"/"next" pick them based on today's date,
+    /// "SoSe 2024" or "WS 23/24" the listed term. falls back to $SEMESTER, then
+    /// paul-scrape.toml
+    semester: Option<String>,
+    /// requests per second, falls back to paul-scrape.toml, then 20
+    #[clap(long)]
+    requests_per_second: Option<u64>,
+    /// constant: requests_per_second throughout. ramp: start at a tenth of it, speed up while
+    /// requests succeed, back off on errors and slow down once only leaves are left
+    #[clap(long, arg_enum, default_value = "constant")]
+    rate_policy: RatePolicy,
+    /// write the outputs into this directory, falls back to paul-scrape.toml
+    #[clap(long)]
+    output_dir: Option<String>,
+    /// where to write the result, repeatable: state.json, msgpack:<file>, ndjson:<file>,
+    /// sqlite:<file>. defaults to state.json
+    #[clap(long = "output", multiple_occurrences = true)]
+    outputs: Vec<String>,
+    /// how outputs without a kind are written, msgpack is zstd compressed and
+    /// needs the msgpack feature. the default output becomes state.msgpack
+    #[clap(long, arg_enum, default_value = "json")]
+    format: StateFormat,
+    /// stop crawling after this long (e.g. "90m") and write a resume file
+    #[clap(long, parse(try_from_str = humantime::parse_duration))]
+    deadline: Option<std::time::Duration>,
+    /// where to write the resume file when the deadline is hit
+    #[clap(long, default_value = "resume.json")]
+    resume_file: String,
+    /// continue a crawl from a resume file
+    #[clap(long)]
+    resume: Option<String>,
+    /// post process every course with these builtin plugins, in order
+    #[clap(long = "plugin", multiple_occurrences = true)]
+    plugins: Vec<String>,
+    /// embed the raw html of failed or all pages into the output
+    #[clap(long, arg_enum, default_value = "none")]
+    embed_html: EmbedHtml,
+    /// only fetch courses whose number starts with one of these (e.g. L.079)
+    #[clap(long = "course-number-prefix", multiple_occurrences = true)]
+    course_number_prefixes: Vec<String>,
+    /// extra href marker and the kind of page it links to, e.g. MYDETAILS=course,
+    /// checked before the built in ones. kinds: tree, course, exam, module, room
+    #[clap(long = "link-marker", multiple_occurrences = true, parse(try_from_str = parse_link_marker))]
+    link_markers: Vec<(String, LinkKind)>,
+    /// only walk the tree and write catalog.json with the listed courses instead of
+    /// the outputs, no course or small group pages are fetched
+    #[clap(long)]
+    shallow: bool,
+    /// also fetch the module pages linked from courses and write them to modules.json
+    #[clap(long)]
+    modules: bool,
+    /// fetch every small group page on its own, even when the course links a
+    /// page listing all of them with their dates
+    #[clap(long)]
+    individual_small_groups: bool,
+    /// only keep courses of these types (e.g. Vorlesung,Seminar), listing rows that show
+    /// another type are not fetched at all
+    #[clap(long, use_value_delimiter = true)]
+    only_types: Vec<String>,
+    /// skip pages larger than this many bytes
+    #[clap(long, default_value_t = ResponseLimits::default().max_body_size)]
+    max_body_size: usize,
+    /// fetch pages failing with timeouts or 5xx again this often
+    #[clap(long, default_value_t = 3)]
+    retries: u32,
+    /// don't follow tree branches nested deeper than this (the semester is depth 1),
+    /// guards against category cycles with changing urls
+    #[clap(long, default_value_t = 20)]
+    max_depth: usize,
+    /// give up on the entry redirect chain after this many hops
+    #[clap(long, default_value_t = 5)]
+    max_redirects: usize,
+    /// load cookies from this file and save them back after the crawl
+    #[clap(long)]
+    cookie_jar: Option<String>,
+    /// write the graph of crawled pages and links to this file
+    #[clap(long)]
+    graph: Option<String>,
+    /// how to write the --graph file
+    #[clap(long, arg_enum, default_value = "dot")]
+    graph_format: GraphFormat,
+    /// only connect over ipv4, for networks with broken ipv6
+    #[clap(long, conflicts_with = "ipv6")]
+    ipv4: bool,
+    /// only connect over ipv6
+    #[clap(long)]
+    ipv6: bool,
+    /// HOST:PORT:IP like curl, connect to IP instead of looking up HOST, e.g. to
+    /// pin a specific frontend. reqwest uses the port of the url, not this one
+    #[clap(long, multiple_occurrences = true, parse(try_from_str = parse_resolve))]
+    resolve: Vec<(String, std::net::SocketAddr)>,
+    /// pem file with extra root certificates, e.g. of a tls intercepting proxy.
+    /// overrides ca_cert in [tls]
+    #[clap(long)]
+    ca_cert: Option<String>,
+    /// accept any certificate. anyone on the way can then read and change
+    /// the pages, only for debugging. like insecure in [tls]
+    #[clap(long)]
+    insecure: bool,
+    /// ask PAUL for uncompressed pages, for debugging. overrides compression in [http]
+    #[clap(long)]
+    no_compression: bool,
+    /// abort on unexpected markup instead of warning and carrying on (for CI)
+    #[clap(long)]
+    strict: bool,
+    /// KIND=PERCENT, e.g. parse=5: more failed pages of that kind mark the run
+    /// as degraded in status.json and exit with code 2
+    #[clap(long = "alert-threshold", multiple_occurrences = true, parse(try_from_str = parse_threshold))]
+    alert_thresholds: Vec<(ErrorKind, f64)>,
+    /// progress bars, a status line every 1000 pages for logs, or nothing
+    #[clap(long, arg_enum, default_value = "bar")]
+    progress: Progress,
+    /// keep at most this many queue entries in memory, the rest waits in a temporary file
+    #[clap(long)]
+    queue_capacity: Option<usize>,
+    /// log every queue push/pop and its outcome as ndjson to this file
+    #[clap(long)]
+    trace_queue: Option<String>,
+    /// keep fetched pages in this directory and reuse them on the next run
+    #[clap(long)]
+    cache_dir: Option<String>,
+    /// like --cache-dir with ~/.cache/paul-scrape
+    #[clap(long)]
+    cache: bool,
+    /// refetch cached pages older than this (e.g. "24h"), cached pages never expire without it
+    #[clap(long, parse(try_from_str = humantime::parse_duration))]
+    max_cache_age: Option<std::time::Duration>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Ask for the base url, semesters, rate limit and output paths and write paul-scrape.toml
+    Init,
+    /// Print a completion script for a shell
+    Completions {
+        #[clap(arg_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Search the courses of the latest scrape by name, instructor or path
+    Find {
+        query: String,
+        /// state files to search, defaults to the states listed in the output dir's manifest.json
+        #[clap(long = "data", multiple_occurrences = true)]
+        data: Vec<String>,
+        /// also match names with typos
+        #[clap(long)]
+        fuzzy: bool,
+        #[clap(long, default_value = "20")]
+        limit: usize,
+    },
+    /// List courses and small groups that fit around already chosen courses
+    FreeSlots {
+        /// course numbers, course urls or small group urls
+        #[clap(required = true)]
+        chosen: Vec<String>,
+        /// state files to search, defaults to the states listed in the output dir's manifest.json
+        #[clap(long = "data", multiple_occurrences = true)]
+        data: Vec<String>,
+        #[clap(long, default_value = "50")]
+        limit: usize,
+    },
+    /// Print one course with its appointments and small groups, fetched or from the latest scrape
+    Show {
+        /// a course url is fetched from PAUL, a course number or cid is looked up in the state files
+        course: String,
+        /// state files to search, defaults to the states listed in the output dir's manifest.json
+        #[clap(long = "data", multiple_occurrences = true)]
+        data: Vec<String>,
+    },
+    /// Bundle a run's outputs, manifest and converted semester.json into one tar.zst
+    Pack {
+        /// the run's output dir, defaults to the configured one
+        dir: Option<String>,
+        #[clap(long, short, default_value = "paul-scrape.tar.zst")]
+        output: String,
+        /// also pack the html cache, so the receiver can re-parse without fetching
+        #[clap(long)]
+        with_cache: bool,
+    },
+    /// Split the catalog.json of a --shallow crawl into resume files for several workers
+    Partition {
+        #[clap(default_value = "catalog.json")]
+        catalog: String,
+        #[clap(long, default_value_t = 2)]
+        shards: usize,
+        /// requests per second of all workers together, split evenly between
+        /// them. defaults to the configured requests_per_second
+        #[clap(long)]
+        total_rate: Option<u64>,
+        /// where to write shard-<n>.json and partition.json
+        #[clap(long, default_value = "shards")]
+        dir: String,
+    },
+    /// Combine the state files of the workers of a partitioned crawl
+    Merge {
+        #[clap(required = true)]
+        states: Vec<String>,
+        #[clap(long, short, default_value = "state.json")]
+        output: String,
+    },
+    /// Extract a bundle written by pack
+    Unpack {
+        archive: String,
+        #[clap(long, default_value = ".")]
+        into: String,
+    },
+    /// List a bundle's files and the run it holds
+    Inspect { archive: String },
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RatePolicy {
+    Constant,
+    Ramp,
 }
 
-#[derive(Debug)]
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum EmbedHtml {
+    None,
+    Failed,
+    All,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Progress {
+    Bar,
+    Log,
+    None,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum StateFormat {
+    Json,
+    Msgpack,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum GraphFormat {
+    Dot,
+    Graphml,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 enum QueueEntry {
     Main,
-    Tree(Url, Path),
-    CourseLeaf(Url, Path),
-    SmallGroupLeaf(Url, Path),
+    Tree(TreeUrl, Path),
+    CourseLeaf(CourseUrl, Path),
+    SmallGroupLeaf(SmallGroupUrl, Path),
+    // a continuation page of a course's small group listing: course url, page url
+    SmallGroupListing(CourseUrl, Url, Path),
+    // a module handbook page linked from a course, only with --modules
+    ModuleLeaf(ModuleUrl, Path),
+    // a page with every small group of a course: page url, the links it
+    // replaces, fetched one by one if they are missing on the page
+    SmallGroupsCombined(Url, Vec<(SmallGroupUrl, Path)>, Path),
+}
+
+impl QueueEntry {
+    fn url(&self) -> Option<&Url> {
+        match self {
+            QueueEntry::Main => None,
+            QueueEntry::Tree(url, _) => Some(url),
+            QueueEntry::CourseLeaf(url, _) => Some(url),
+            QueueEntry::SmallGroupLeaf(url, _) => Some(url),
+            QueueEntry::SmallGroupListing(_, url, _) => Some(url),
+            QueueEntry::ModuleLeaf(url, _) => Some(url),
+            QueueEntry::SmallGroupsCombined(url, _, _) => Some(url),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            QueueEntry::Main => "main",
+            QueueEntry::Tree(_, _) => "tree",
+            QueueEntry::CourseLeaf(_, _) => "course",
+            QueueEntry::SmallGroupLeaf(_, _) => "small_group",
+            QueueEntry::SmallGroupListing(_, _, _) => "small_group_listing",
+            QueueEntry::ModuleLeaf(_, _) => "module",
+            QueueEntry::SmallGroupsCombined(_, _, _) => "small_groups_combined",
+        }
+    }
+
+    fn path(&self) -> Option<&Path> {
+        match self {
+            QueueEntry::Main => None,
+            QueueEntry::Tree(_, path)
+            | QueueEntry::CourseLeaf(_, path)
+            | QueueEntry::SmallGroupLeaf(_, path)
+            | QueueEntry::SmallGroupListing(_, _, path)
+            | QueueEntry::ModuleLeaf(_, path)
+            | QueueEntry::SmallGroupsCombined(_, _, path) => Some(path),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        // listings hang off a course, so they are finished like leaves
+        matches!(
+            self,
+            QueueEntry::CourseLeaf(_, _)
+                | QueueEntry::SmallGroupLeaf(_, _)
+                | QueueEntry::SmallGroupListing(_, _, _)
+                | QueueEntry::ModuleLeaf(_, _)
+                | QueueEntry::SmallGroupsCombined(_, _, _)
+        )
+    }
+}
+
+// one line of --trace-queue
+#[derive(Serialize)]
+struct TraceRecord<'a> {
+    time: chrono::DateTime<chrono::Utc>,
+    event: &'a str,
+    kind: Option<&'a str>,
+    url: Option<&'a Url>,
+    path: Option<&'a Vec<String>>,
+    outcome: Option<&'a str>,
 }
 
 struct Queue {
     queue: VecDeque<QueueEntry>,
-    _bars: MultiProgress,
-    tree_bar: ProgressBar,
-    leaf_bar: ProgressBar,
+    // every url ever pushed, queued, in flight or done
+    seen: HashSet<Url>,
+    duplicates: usize,
+    // entries queued or in flight, an entry counts until its task has pushed its links
+    outstanding: usize,
+    // how far every tree page got, kept in the checkpoint so a resume can
+    // pick up unfinished subtrees without redoing the expanded ones
+    branches: BTreeMap<Url, Branch>,
+    trace: Option<LineWriter<File>>,
+    // why entries in flight failed, for the outcome of their done trace record
+    failures: HashMap<Url, ErrorKind>,
+    progress: Box<dyn ProgressSink>,
+    // pages and courses per top level category, for the live table and the report
+    faculties: FacultyTally,
+    // entries beyond the capacity wait on disk until the queue drains
+    capacity: Option<usize>,
+    spill: Option<Spill<QueueEntry>>,
+    spilled_trees: usize,
 }
 
 impl Queue {
-    pub fn new() -> Self {
-        let bars = MultiProgress::new();
-        let tree_bar = bars.add(ProgressBar::new(0));
-        tree_bar.set_style(
-            indicatif::ProgressStyle::default_bar()
-                .template("{prefix:.bold.dim} {bar} {pos:>7}/{len:7} ({elapsed}:{eta}) {wide_msg}")
-                .unwrap(),
-        );
-        tree_bar.set_prefix("Tree: ");
-        let leaf_bar = bars.add(ProgressBar::new(0));
-        leaf_bar.set_style(
-            indicatif::ProgressStyle::default_bar()
-                .template("{prefix:.bold.dim} {bar} {pos:>7}/{len:7} ({elapsed}:{eta}) {wide_msg}")
-                .unwrap(),
-        );
-        leaf_bar.set_prefix("Leaf: ");
+    pub fn new(
+        trace: Option<LineWriter<File>>,
+        progress: Box<dyn ProgressSink>,
+        capacity: Option<usize>,
+    ) -> Self {
+        let spill = capacity.map(|_| Spill::new().expect("Failed to create queue spill file"));
         Self {
+            capacity,
+            spill,
+            spilled_trees: 0,
             queue: VecDeque::new(),
-            _bars: bars,
-            tree_bar,
-            leaf_bar,
+            seen: HashSet::new(),
+            duplicates: 0,
+            outstanding: 0,
+            branches: BTreeMap::new(),
+            trace,
+            failures: HashMap::new(),
+            progress,
+            faculties: FacultyTally::default(),
         }
     }
 
     pub fn push_back(&mut self, entry: QueueEntry) {
         // println!("Pushing to queue: {:?}", entry);
-        let is_leaf = matches!(
-            &entry,
-            QueueEntry::CourseLeaf(_, _) | QueueEntry::SmallGroupLeaf(_, _)
-        );
+        // two branches can link the same page, only fetch it once
+        if let Some(url) = entry.url() {
+            if !self.seen.insert(url.clone()) {
+                self.duplicates += 1;
+                self.trace_entry("duplicate", &entry);
+                return;
+            }
+        }
+        self.trace_entry("push", &entry);
+        let is_leaf = entry.is_leaf();
         let message = match &entry {
             QueueEntry::Main => "pushing main page".to_string(),
             QueueEntry::Tree(_, path) => format!("pushing tree {}", path.fragments.last().unwrap()),
@@ -78,20 +453,83 @@ impl Queue {
                     path.fragments.last().unwrap()
                 )
             }
+            QueueEntry::SmallGroupListing(_, _, path) => {
+                format!(
+                    "pushing small_group listing {}",
+                    path.fragments.last().unwrap()
+                )
+            }
+            QueueEntry::ModuleLeaf(url, _) => format!("pushing module {}", url),
+            QueueEntry::SmallGroupsCombined(_, links, path) => format!(
+                "pushing {} combined small groups {}",
+                links.len(),
+                path.fragments.last().unwrap()
+            ),
         };
-        if is_leaf {
-            self.leaf_bar.inc_length(1);
-            self.leaf_bar.set_message(message);
-            self.leaf_bar.tick();
-        } else {
-            self.tree_bar.inc_length(1);
-            self.tree_bar.set_message(message);
-            self.tree_bar.tick();
+        self.progress.queued(progress_kind(is_leaf), &message);
+        if let Some(path) = entry.path() {
+            self.count_faculty(path, FacultyEvent::Queued);
+        }
+        if let QueueEntry::Tree(url, path) = &entry {
+            self.set_branch(url, path, BranchStatus::Queued);
+        }
+        self.outstanding += 1;
+        match (&mut self.spill, self.capacity) {
+            (Some(spill), Some(capacity)) if self.queue.len() >= capacity => {
+                if !is_leaf {
+                    self.spilled_trees += 1;
+                }
+                spill.push(&entry).expect("Failed to spill queue entry");
+            }
+            _ => self.queue.push_back(entry),
+        }
+    }
+
+    // entries in memory and on disk
+    fn queued(&self) -> usize {
+        self.queue.len() + self.spill.as_ref().map_or(0, Spill::len)
+    }
+
+    fn unspill(&mut self, count: usize) {
+        let Some(spill) = &mut self.spill else {
+            return;
+        };
+        let entries = spill
+            .pop_many(count)
+            .expect("Failed to read spilled queue entries");
+        self.spilled_trees -= entries.iter().filter(|entry| !entry.is_leaf()).count();
+        self.queue.extend(entries);
+    }
+
+    // reload spilled entries once the queue is half empty
+    fn refill(&mut self) {
+        if let Some(capacity) = self.capacity.map(|capacity| capacity.max(1)) {
+            if self.queue.len() < capacity.div_ceil(2) {
+                self.unspill(capacity - self.queue.len());
+            }
         }
-        self.queue.push_back(entry)
+    }
+
+    pub fn set_branch(&mut self, url: &Url, path: &Path, status: BranchStatus) {
+        self.branches.insert(
+            url.clone(),
+            Branch {
+                path: path.clone(),
+                status,
+            },
+        );
+    }
+
+    pub fn running(&self) -> usize {
+        self.outstanding - self.queued()
+    }
+
+    pub fn done(&mut self) {
+        self.outstanding -= 1;
     }
 
     pub fn pop(&mut self) -> Option<QueueEntry> {
+        self.refill();
         // choose random element and put at the front
         let len = self.queue.len();
         if len == 0 {
@@ -100,23 +538,121 @@ impl Queue {
         let idx = rand::thread_rng().gen_range(0..len);
         // swap
         let front = self.queue.swap_remove_front(idx).unwrap();
+        self.trace_entry("pop", &front);
         // let front = self.queue.pop_front()?;
-        let is_leaf = matches!(
-            front,
-            QueueEntry::CourseLeaf(_, _) | QueueEntry::SmallGroupLeaf(_, _)
-        );
-        if is_leaf {
-            self.leaf_bar.inc(1);
-        } else {
-            self.tree_bar.inc(1);
+        self.progress.started(progress_kind(front.is_leaf()));
+        if let Some(path) = front.path() {
+            self.count_faculty(path, FacultyEvent::Started);
         }
         // println!("Popping from queue: {:?}", front);
         Some(front)
     }
 
+    // no tree pages are queued, what's left are mostly leaves
+    pub fn only_leaves(&self) -> bool {
+        self.spilled_trees == 0 && self.queue.iter().all(QueueEntry::is_leaf)
+    }
+
+    pub fn pop_leaf(&mut self) -> Option<QueueEntry> {
+        // like pop, but leaves tree pages in the queue
+        self.refill();
+        // past the tree cutoff the spill can hold leaves behind a queue full of
+        // tree pages, load it even if that goes over the capacity
+        while !self.queue.iter().any(QueueEntry::is_leaf)
+            && self.spill.as_ref().is_some_and(|spill| !spill.is_empty())
+        {
+            self.unspill(self.capacity.unwrap_or(1).max(1));
+        }
+        let leaves = self
+            .queue
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.is_leaf())
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        if leaves.is_empty() {
+            return None;
+        }
+        let idx = leaves[rand::thread_rng().gen_range(0..leaves.len())];
+        let front = self.queue.remove(idx).unwrap();
+        self.trace_entry("pop", &front);
+        self.progress.started(ProgressKind::Leaf);
+        if let Some(path) = front.path() {
+            self.count_faculty(path, FacultyEvent::Started);
+        }
+        Some(front)
+    }
+
+    pub fn count_faculty(&mut self, path: &Path, event: FacultyEvent) {
+        self.faculties.record(path, event, &*self.progress);
+    }
+
+    fn trace_entry(&mut self, event: &str, entry: &QueueEntry) {
+        self.trace(event, Some(entry.kind()), entry.url(), entry.path(), None);
+    }
+
+    pub fn trace(
+        &mut self,
+        event: &str,
+        kind: Option<&str>,
+        url: Option<&Url>,
+        path: Option<&Path>,
+        outcome: Option<&str>,
+    ) {
+        let Some(trace) = &mut self.trace else {
+            return;
+        };
+        let record = TraceRecord {
+            time: chrono::Utc::now(),
+            event,
+            kind,
+            url,
+            path: path.map(|path| &path.fragments),
+            outcome,
+        };
+        serde_json::to_writer(&mut *trace, &record).unwrap();
+        trace.write_all(b"\n").unwrap();
+    }
+
+    pub fn record_failure(&mut self, url: &Url, kind: ErrorKind) {
+        if self.trace.is_some() {
+            self.failures.insert(url.clone(), kind);
+        }
+    }
+
+    // "ok", or the kind of error the entry's page failed with
+    pub fn trace_done(&mut self, kind: &str, url: Option<&Url>, path: Option<&Path>) {
+        let outcome = match url.and_then(|url| self.failures.remove(url)) {
+            Some(failure) => serde_json::to_value(failure)
+                .unwrap()
+                .as_str()
+                .unwrap()
+                .to_string(),
+            None => "ok".to_string(),
+        };
+        self.trace("done", Some(kind), url, path, Some(&outcome));
+    }
+
+    // whether the url was new, like HashSet::insert
+    pub fn mark_seen(&mut self, url: &str) -> bool {
+        Url::parse(url).is_ok_and(|url| self.seen.insert(url))
+    }
+
+    pub fn drain(&mut self) -> Vec<QueueEntry> {
+        self.unspill(usize::MAX);
+        self.queue.drain(..).collect()
+    }
+
     pub fn finish(&mut self) {
-        self.tree_bar.finish();
-        self.leaf_bar.finish();
+        self.progress.finish();
+    }
+}
+
+fn progress_kind(is_leaf: bool) -> ProgressKind {
+    if is_leaf {
+        ProgressKind::Leaf
+    } else {
+        ProgressKind::Tree
     }
 }
 
@@ -125,64 +661,548 @@ struct State {
     queue: Arc<Mutex<Queue>>,
     client: reqwest::Client,
     base_url: Url,
-    semester: String,
+    semesters: Vec<String>,
     start_time: chrono::DateTime<chrono::Utc>,
     courses: Arc<Mutex<Vec<Course>>>,
     small_groups: Arc<Mutex<Vec<SmallGroup>>>,
-    running_tasks: Arc<Mutex<u64>>,
+    failed_pages: Arc<Mutex<Vec<FailedPage>>>,
+    // woken whenever an entry is done
+    entry_done: Arc<Notify>,
+    post_processors: Arc<Vec<Box<dyn CoursePostProcessor>>>,
+    embed_html: EmbedHtml,
+    course_number_prefixes: Arc<Vec<String>>,
+    only_types: Arc<Vec<String>>,
+    shallow: bool,
+    catalog: Arc<Mutex<Vec<CatalogEntry>>>,
+    // only with --modules
+    modules: Option<Arc<Mutex<Vec<Module>>>>,
+    // fetch the combined small group page instead of each group's page
+    combine_small_groups: bool,
+    response_limits: ResponseLimits,
+    max_redirects: usize,
+    max_depth: usize,
+    // branches that were not followed because of max_depth
+    depth_limited: Arc<Mutex<Vec<Path>>>,
+    classifier: Arc<LinkClassifier>,
+    appointment_columns: Arc<AppointmentColumns>,
+    // listing links to pages without a parser, by kind
+    unfollowed: Arc<Mutex<BTreeMap<LinkKind, usize>>>,
+    retries: u32,
+    timings: Timings,
+    graph: Arc<Mutex<CrawlGraph>>,
+    strict: bool,
+    cache: Option<HtmlCache>,
+    pacer: Arc<Mutex<Pacer>>,
+}
+
+// manifest.json, what a run was configured with and what it wrote
+#[derive(Serialize)]
+struct Manifest {
+    version: &'static str,
+    // missing when built outside of a git checkout
+    git_hash: Option<&'static str>,
+    started: chrono::DateTime<chrono::Utc>,
+    finished: chrono::DateTime<chrono::Utc>,
+    config: RunConfig,
+    semesters: Vec<String>,
+    counts: Counts,
+    files: Vec<ManifestFile>,
+    // the json and msgpack state outputs among files, what find, free-slots and show read
+    states: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RunConfig {
+    base_url: String,
+    semester: String,
+    requests_per_second: u64,
+    retries: u32,
+    max_depth: usize,
+    max_redirects: usize,
+    max_body_size: usize,
+    deadline: Option<String>,
+    course_number_prefixes: Vec<String>,
+    only_types: Vec<String>,
+    plugins: Vec<String>,
+    outputs: Vec<String>,
+    cache_dir: Option<String>,
+    resume: Option<String>,
+    ca_cert: Option<String>,
+    // the outputs of an insecure run may have been tampered with
+    insecure: bool,
+    // whether gzip, deflate and brotli were accepted
+    compression: bool,
+    // how the crawler identified itself, see [politeness] in the config
+    user_agent: String,
+    from: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Counts {
+    courses: usize,
+    small_groups: usize,
+    failed_pages: usize,
+    // left in the queue when the deadline hit
+    remaining: usize,
 }
 
+#[derive(Serialize)]
+struct ManifestFile {
+    path: String,
+    bytes: u64,
+    sha256: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BranchStatus {
+    Queued,
+    // fetched and parsed, its courses and sub branches are queued
+    Expanded,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Branch {
+    path: Path,
+    status: BranchStatus,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    semesters: Vec<String>,
+    start_time: chrono::DateTime<chrono::Utc>,
+    courses: Vec<Course>,
+    small_groups: Vec<SmallGroup>,
+    #[serde(default)]
+    failed_pages: Vec<FailedPage>,
+    queue: Vec<QueueEntry>,
+    #[serde(default)]
+    branches: BTreeMap<Url, Branch>,
+    #[serde(default)]
+    catalog: Vec<CatalogEntry>,
+    #[serde(default)]
+    modules: Vec<Module>,
+    #[serde(default)]
+    graph: CrawlGraph,
+}
+
+const DEFAULT_BASE_URL: &str = "https://paul.uni-paderborn.de";
+const DEFAULT_SEMESTER: &str = "Sommer 2023";
 const REQUESTS_PER_SECOND: u64 = 20;
+// fraction of the deadline after which no more tree pages are fetched
+const TREE_CUTOFF: f64 = 0.9;
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 8)]
 async fn main() {
+    // before parsing, BASE_URL and SEMESTER may come from .env
+    load_dotenv();
     let args = Args::parse();
-    let base_url = args.base_url;
-    let semester = args.semester;
-
-    let queue = Arc::new(Mutex::new(Queue::new()));
-
-    let state = State {
-        queue: queue.clone(),
-        client: reqwest::Client::new(),
-        base_url,
-        semester,
-        start_time: chrono::Utc::now(),
-        courses: Arc::new(Mutex::new(Vec::new())),
-        small_groups: Arc::new(Mutex::new(Vec::new())),
-        running_tasks: Arc::new(Mutex::new(0)),
+    match args.command {
+        Some(Command::Init) => {
+            init().await;
+            return;
+        }
+        Some(Command::Completions { shell }) => {
+            let mut command = Args::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+            return;
+        }
+        Some(Command::Find {
+            query,
+            data,
+            fuzzy,
+            limit,
+        }) => {
+            find(&query, data, fuzzy, limit);
+            return;
+        }
+        Some(Command::FreeSlots {
+            chosen,
+            data,
+            limit,
+        }) => {
+            free_slots(&chosen, data, limit);
+            return;
+        }
+        Some(Command::Show { course, data }) => {
+            show(&course, data).await;
+            return;
+        }
+        Some(Command::Pack {
+            dir,
+            output,
+            with_cache,
+        }) => {
+            pack_run(dir, &output, with_cache);
+            return;
+        }
+        Some(Command::Unpack { archive, into }) => {
+            let unpacked = pack::unpack(archive.as_ref(), into.as_ref()).unwrap_or_else(|e| {
+                eprintln!("Failed to unpack {}: {}", archive, e);
+                std::process::exit(1)
+            });
+            eprintln!("Unpacked {} files into {}", unpacked.len(), into);
+            return;
+        }
+        Some(Command::Partition {
+            catalog,
+            shards,
+            total_rate,
+            dir,
+        }) => {
+            partition(&catalog, shards, total_rate, &dir);
+            return;
+        }
+        Some(Command::Merge { states, output }) => {
+            merge(&states, &output);
+            return;
+        }
+        Some(Command::Inspect { archive }) => {
+            inspect_pack(&archive);
+            return;
+        }
+        None => {}
+    }
+    if args.man {
+        print!("{}", man_page(&Args::command()));
+        return;
+    }
+    let mut config = Config::load(config_path()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    if args.no_compression {
+        config.http.compression = Some(false);
+    }
+    if let Some(path) = &args.ca_cert {
+        config.tls.ca_cert = Some(path.clone());
+    }
+    config.tls.insecure |= args.insecure;
+    let base_url = args
+        .base_url
+        .or_else(|| {
+            env::var("BASE_URL")
+                .ok()
+                .and_then(|url| Url::parse(&url).ok())
+        })
+        .or(config.base_url)
+        .unwrap_or_else(|| Url::parse(DEFAULT_BASE_URL).unwrap());
+    let semester = args
+        .semester
+        .or_else(|| env::var("SEMESTER").ok())
+        .or(config.semester)
+        .unwrap_or_else(|| DEFAULT_SEMESTER.to_string());
+    let requests_per_second = args
+        .requests_per_second
+        .or(config.requests_per_second)
+        .unwrap_or(REQUESTS_PER_SECOND)
+        .max(1);
+    let output_dir = args.output_dir.or(config.output_dir);
+    let cache_dir = args.cache_dir.or(config.cache_dir).or_else(|| {
+        args.cache
+            .then(user_cache_dir)
+            .flatten()
+            .map(|dir| dir.to_string_lossy().into_owned())
+    });
+    // opened up front so a bad output fails before the crawl
+    let outputs = match (args.outputs.is_empty(), args.format) {
+        (true, StateFormat::Json) => vec!["state.json".to_string()],
+        (true, StateFormat::Msgpack) => vec!["msgpack:state.msgpack".to_string()],
+        (false, StateFormat::Json) => args.outputs,
+        (false, StateFormat::Msgpack) => args
+            .outputs
+            .into_iter()
+            .map(|output| match output.split_once(':') {
+                Some(("json" | "msgpack" | "ndjson" | "sqlite", _)) => output,
+                _ => format!("msgpack:{}", output),
+            })
+            .collect(),
+    };
+    let mut sinks = outputs
+        .iter()
+        .map(|output| open_sink(output, output_dir.as_deref()))
+        .collect::<Result<Vec<Box<dyn Sink>>, _>>()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+
+    // recorded in manifest.json
+    let run_config = RunConfig {
+        base_url: base_url.to_string(),
+        semester: semester.clone(),
+        requests_per_second,
+        retries: args.retries,
+        max_depth: args.max_depth,
+        max_redirects: args.max_redirects,
+        max_body_size: args.max_body_size,
+        deadline: args
+            .deadline
+            .map(|deadline| humantime::format_duration(deadline).to_string()),
+        course_number_prefixes: args.course_number_prefixes.clone(),
+        only_types: args.only_types.clone(),
+        plugins: args.plugins.clone(),
+        outputs: outputs.clone(),
+        cache_dir: cache_dir.clone(),
+        resume: args.resume.clone(),
+        ca_cert: config.tls.ca_cert.clone(),
+        insecure: config.tls.insecure,
+        compression: config.http.compression != Some(false),
+        user_agent: config.politeness.user_agent(),
+        from: config.politeness.from().map(str::to_string),
+    };
+
+    let checkpoint = args.resume.map(|resume| {
+        let file = File::open(&resume).expect("Failed to open resume file");
+        serde_json::from_reader::<_, Checkpoint>(file).expect("Failed to parse resume file")
+    });
+
+    let post_processors = post_processors_by_name(&args.plugins).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    let post_processors = Arc::new(post_processors);
+
+    let course_number_prefixes = Arc::new(args.course_number_prefixes);
+    let only_types = Arc::new(args.only_types);
+    let mut classifier = LinkClassifier::default();
+    for (marker, kind) in args.link_markers {
+        classifier.register(marker, kind);
+    }
+    let classifier = Arc::new(classifier);
+    let appointment_columns = Arc::new(config.appointment_columns.clone());
+
+    let cookie_jar = Arc::new(match &args.cookie_jar {
+        Some(path) => CookieJar::load(path).expect("Failed to load cookie jar"),
+        None => CookieJar::default(),
+    });
+    let timings = Timings::default();
+    let family = match (args.ipv4, args.ipv6) {
+        (true, _) => Some(IpFamily::V4),
+        (_, true) => Some(IpFamily::V6),
+        _ => None,
+    };
+    if config.tls.insecure {
+        eprintln!("WARNING: insecure TLS accepts any certificate, the connection to PAUL");
+        eprintln!("WARNING: can be read and changed by anyone in between. Don't publish the");
+        eprintln!("WARNING: outputs of this run, use --ca-cert for an intercepting proxy instead.");
+    }
+    let mut client = client_builder(&config.http, &config.politeness, &config.tls)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        })
+        .cookie_provider(cookie_jar.clone())
+        .dns_resolver(Arc::new(TimedResolver {
+            timings: timings.clone(),
+            family,
+        }));
+    for (host, addr) in &args.resolve {
+        client = client.resolve(host, *addr);
+    }
+    let client = client.build().unwrap();
+
+    let cache = cache_dir.as_ref().map(|dir| {
+        HtmlCache::new(dir, args.max_cache_age).expect("Failed to create cache directory")
+    });
+
+    let trace = args.trace_queue.as_ref().map(|path| {
+        LineWriter::new(File::create(path).expect("Failed to create queue trace file"))
+    });
+    let progress: Box<dyn ProgressSink> = match args.progress {
+        Progress::Bar => Box::new(BarProgress::new()),
+        Progress::Log => Box::new(LogProgress::new(1000)),
+        Progress::None => Box::new(NoProgress),
     };
+    let queue = Arc::new(Mutex::new(Queue::new(trace, progress, args.queue_capacity)));
+    let pacer = Arc::new(Mutex::new(match args.rate_policy {
+        RatePolicy::Constant => Pacer::constant(requests_per_second as f64),
+        RatePolicy::Ramp => Pacer::ramp(requests_per_second as f64),
+    }));
+
+    let state = match &checkpoint {
+        Some(checkpoint) => State {
+            queue: queue.clone(),
+            client: client.clone(),
+            base_url,
+            semesters: checkpoint.semesters.clone(),
+            start_time: checkpoint.start_time,
+            courses: Arc::new(Mutex::new(checkpoint.courses.clone())),
+            small_groups: Arc::new(Mutex::new(checkpoint.small_groups.clone())),
+            failed_pages: Arc::new(Mutex::new(checkpoint.failed_pages.clone())),
+            entry_done: Arc::new(Notify::new()),
+            post_processors: post_processors.clone(),
+            embed_html: args.embed_html,
+            course_number_prefixes: course_number_prefixes.clone(),
+            only_types: only_types.clone(),
+            shallow: args.shallow,
+            catalog: Arc::new(Mutex::new(checkpoint.catalog.clone())),
+            modules: args
+                .modules
+                .then(|| Arc::new(Mutex::new(checkpoint.modules.clone()))),
+            combine_small_groups: !args.individual_small_groups,
+            response_limits: ResponseLimits {
+                max_body_size: args.max_body_size,
+            },
+            max_redirects: args.max_redirects,
+            max_depth: args.max_depth,
+            depth_limited: Arc::new(Mutex::new(Vec::new())),
+            classifier: classifier.clone(),
+            appointment_columns: appointment_columns.clone(),
+            unfollowed: Arc::new(Mutex::new(BTreeMap::new())),
+            retries: args.retries,
+            timings: timings.clone(),
+            graph: Arc::new(Mutex::new(checkpoint.graph.clone())),
+            strict: args.strict,
+            cache: cache.clone(),
+            pacer: pacer.clone(),
+        },
+        None => State {
+            queue: queue.clone(),
+            client: client.clone(),
+            semesters: resolve_semesters(&client, &semester, &base_url, args.max_redirects).await,
+            base_url,
+            start_time: chrono::Utc::now(),
+            courses: Arc::new(Mutex::new(Vec::new())),
+            small_groups: Arc::new(Mutex::new(Vec::new())),
+            failed_pages: Arc::new(Mutex::new(Vec::new())),
+            entry_done: Arc::new(Notify::new()),
+            post_processors: post_processors.clone(),
+            embed_html: args.embed_html,
+            course_number_prefixes: course_number_prefixes.clone(),
+            only_types: only_types.clone(),
+            shallow: args.shallow,
+            catalog: Arc::new(Mutex::new(Vec::new())),
+            modules: args.modules.then(|| Arc::new(Mutex::new(Vec::new()))),
+            combine_small_groups: !args.individual_small_groups,
+            response_limits: ResponseLimits {
+                max_body_size: args.max_body_size,
+            },
+            max_redirects: args.max_redirects,
+            max_depth: args.max_depth,
+            depth_limited: Arc::new(Mutex::new(Vec::new())),
+            classifier: classifier.clone(),
+            appointment_columns: appointment_columns.clone(),
+            unfollowed: Arc::new(Mutex::new(BTreeMap::new())),
+            retries: args.retries,
+            timings: timings.clone(),
+            graph: Arc::new(Mutex::new(CrawlGraph::default())),
+            strict: args.strict,
+            cache: cache.clone(),
+            pacer: pacer.clone(),
+        },
+    };
+
+    let started = Instant::now();
+    let deadline = args.deadline.map(|deadline| started + deadline);
+    let tree_cutoff = args
+        .deadline
+        .map(|deadline| started + deadline.mul_f64(TREE_CUTOFF));
+
+    // add the main page or the resumed entries to the queue
+    {
+        let mut queue = queue.lock().await;
+        match checkpoint {
+            Some(checkpoint) => {
+                // branches that failed or were in flight are the roots of unfinished
+                // subtrees, fetch them again. queued ones are still in the queue
+                let queued = checkpoint
+                    .queue
+                    .iter()
+                    .filter_map(QueueEntry::url)
+                    .collect::<HashSet<_>>();
+                let retry = checkpoint
+                    .branches
+                    .iter()
+                    .filter(|(url, branch)| {
+                        branch.status != BranchStatus::Expanded && !queued.contains(url)
+                    })
+                    .map(|(url, branch)| (url.clone(), branch.path.clone()))
+                    .collect::<Vec<_>>();
+                if !retry.is_empty() {
+                    eprintln!("Requeueing {} unfinished tree branches", retry.len());
+                    let retry_urls = retry
+                        .iter()
+                        .map(|(url, _)| url.to_string())
+                        .collect::<HashSet<_>>();
+                    state
+                        .failed_pages
+                        .lock()
+                        .await
+                        .retain(|page| !retry_urls.contains(&page.url));
+                }
+                queue.branches = checkpoint.branches.clone();
+                // branches are only recorded for tree pages
+                for (url, path) in retry {
+                    queue.push_back(QueueEntry::Tree(TreeUrl::new(url), path));
+                }
+                // expanded branches would only queue pages that are done or queued already
+                for url in checkpoint
+                    .branches
+                    .iter()
+                    .filter(|(_, branch)| branch.status == BranchStatus::Expanded)
+                    .map(|(url, _)| url)
+                {
+                    queue.mark_seen(url.as_str());
+                }
+                // pages finished before the checkpoint are not fetched again
+                for url in checkpoint
+                    .courses
+                    .iter()
+                    .map(|course| &course.url)
+                    .chain(checkpoint.small_groups.iter().map(|sg| &sg.url))
+                    .chain(checkpoint.failed_pages.iter().map(|page| &page.url))
+                    .chain(checkpoint.modules.iter().map(|module| &module.url))
+                {
+                    queue.mark_seen(url);
+                }
+                for course in &checkpoint.courses {
+                    queue.count_faculty(&course.path, FacultyEvent::Course);
+                }
+                for entry in checkpoint.queue {
+                    queue.push_back(entry);
+                }
+            }
+            None => queue.push_back(QueueEntry::Main),
+        }
+    }
 
     let event_loop = tokio::spawn({
         let state = state.clone();
         async move {
             loop {
-                // wait 1 / REQUESTS_PER_SECOND seconds
-                tokio::time::sleep(tokio::time::Duration::from_secs_f64(
-                    1.0 / REQUESTS_PER_SECOND as f64,
-                ))
-                .await;
+                // wait 1 / requests_per_second seconds, as paced
+                let leaves_only = state.queue.lock().await.only_leaves();
+                let delay = state.pacer.lock().await.delay(leaves_only);
+                tokio::time::sleep(delay).await;
+                let now = Instant::now();
                 // get the queue
                 let entry = {
                     let mut queue = state.queue.lock().await;
-                    queue.pop()
+                    if deadline.is_some_and(|deadline| now >= deadline) {
+                        // past the deadline, only wait for running tasks
+                        None
+                    } else if tree_cutoff.is_some_and(|tree_cutoff| now >= tree_cutoff) {
+                        // deadline approaching, only finish leaves
+                        queue.pop_leaf()
+                    } else {
+                        queue.pop()
+                    }
                 };
                 // if there is an entry, process it, else wait
                 let entry = match entry {
                     Some(entry) => entry,
                     None => {
-                        // check if there are any running tasks
-                        let running_tasks = {
-                            let running_tasks = state.running_tasks.lock().await;
-                            *running_tasks
-                        };
-                        if running_tasks == 0 {
-                            // if there are no running tasks, we are done
+                        // a popped entry counts as running until its links are pushed,
+                        // so nothing running means nothing new can be queued
+                        if state.queue.lock().await.running() == 0 {
                             break;
-                        } else {
-                            // if there are running tasks, continue
-                            continue;
                         }
+                        // the permit of a notify between the check and here is kept
+                        state.entry_done.notified().await;
+                        continue;
                     }
                 };
                 // process the entry
@@ -196,145 +1216,1635 @@ async fn main() {
         }
     });
 
-    // add the main page to the queue
-    {
+    // wait for the event loop to finish
+    event_loop.await.unwrap();
+
+    // anything left in the queue was cut off by the deadline
+    let (remaining, duplicates, branches, faculties) = {
         let mut queue = queue.lock().await;
-        queue.push_back(QueueEntry::Main);
+        (
+            queue.drain(),
+            queue.duplicates,
+            queue.branches.clone(),
+            queue.faculties.counts(),
+        )
+    };
+    let graph = state.graph.lock().await.clone();
+
+    // we're done, hand every semester to the outputs
+    let courses = state.courses.lock().await.clone();
+    let small_groups = state.small_groups.lock().await.clone();
+    let failed_pages = state.failed_pages.lock().await.clone();
+    for semester in &state.semesters {
+        // the first path fragment is always the semester
+        let in_semester = |path: &Path| path.fragments.first() == Some(semester);
+        let semester_state = StateSerializable {
+            semester: semester.clone(),
+            start_time: state.start_time,
+            courses: courses
+                .iter()
+                .filter(|course| in_semester(&course.path))
+                .cloned()
+                .collect(),
+            small_groups: small_groups
+                .iter()
+                .filter(|small_group| in_semester(&small_group.path))
+                .cloned()
+                .collect(),
+            failed_pages: failed_pages
+                .iter()
+                .filter(|failed_page| in_semester(&failed_page.path))
+                .cloned()
+                .collect(),
+        };
+        // a shallow crawl has no courses, don't overwrite a full state.json with it
+        if state.shallow {
+            continue;
+        }
+        for sink in &mut sinks {
+            sink.write(&semester_state)
+                .unwrap_or_else(|e| eprintln!("{}", e));
+        }
+    }
+    let mut produced = Vec::new();
+    let mut states = Vec::new();
+    if state.shallow {
+        let mut catalog = state.catalog.lock().await.clone();
+        catalog.sort_by(|a, b| a.number.cmp(&b.number));
+        let path = match &output_dir {
+            Some(dir) => std::path::Path::new(dir).join("catalog.json"),
+            None => "catalog.json".into(),
+        };
+        let file = File::create(&path).expect("Failed to create catalog");
+        serde_json::to_writer_pretty(file, &catalog).expect("Failed to write catalog");
+        eprintln!("Listed {} courses in {}", catalog.len(), path.display());
+        produced.push(path);
+    } else {
+        for sink in &mut sinks {
+            sink.finish().unwrap_or_else(|e| eprintln!("{}", e));
+            states.extend(
+                sink.files()
+                    .iter()
+                    .filter(|path| {
+                        matches!(
+                            path.extension().and_then(|extension| extension.to_str()),
+                            Some("json" | "msgpack")
+                        )
+                    })
+                    .map(|path| path.display().to_string()),
+            );
+            produced.extend(sink.files());
+        }
+    }
+    if let Some(modules) = &state.modules {
+        let mut modules = modules.lock().await.clone();
+        modules.sort_by(|a, b| a.number.cmp(&b.number));
+        let path = match &output_dir {
+            Some(dir) => std::path::Path::new(dir).join("modules.json"),
+            None => "modules.json".into(),
+        };
+        let file = File::create(&path).expect("Failed to create modules.json");
+        serde_json::to_writer_pretty(file, &modules).expect("Failed to write modules.json");
+        eprintln!("Wrote {} modules to {}", modules.len(), path.display());
+        produced.push(path);
     }
 
-    // wait for the event loop to finish
-    event_loop.await.unwrap();
+    report(
+        &courses,
+        &small_groups,
+        &failed_pages,
+        &timings,
+        !args.resolve.is_empty(),
+    );
+    report_faculties(&faculties);
+    if duplicates > 0 {
+        eprintln!("Skipped {} links to already queued pages", duplicates);
+    }
+    let depth_limited = state.depth_limited.lock().await;
+    if !depth_limited.is_empty() {
+        eprintln!(
+            "Skipped {} branches deeper than --max-depth {}:",
+            depth_limited.len(),
+            args.max_depth
+        );
+        for path in depth_limited.iter() {
+            eprintln!("  {}", path.fragments.join(" > "));
+        }
+    }
+    for (kind, count) in state.unfollowed.lock().await.iter() {
+        eprintln!("Not following {} {:?} links", count, kind);
+    }
+
+    if let Some(path) = &args.graph {
+        let graph = match args.graph_format {
+            GraphFormat::Dot => graph.to_dot(),
+            GraphFormat::Graphml => graph.to_graphml(),
+        };
+        std::fs::write(path, graph).expect("Failed to write graph file");
+        produced.push(path.into());
+    }
+
+    if let Some(path) = &args.cookie_jar {
+        cookie_jar.save(path).expect("Failed to save cookie jar");
+    }
+
+    let counts = Counts {
+        courses: courses.len(),
+        small_groups: small_groups.len(),
+        failed_pages: failed_pages.len(),
+        remaining: remaining.len(),
+    };
+    let semesters = state.semesters.clone();
+    let status = run_status(
+        counts.courses + counts.small_groups + counts.failed_pages,
+        &failed_pages,
+        &args.alert_thresholds,
+    );
+
+    if !remaining.is_empty() {
+        eprintln!(
+            "Deadline reached with {} entries left, writing {}",
+            remaining.len(),
+            args.resume_file
+        );
+        let file = File::create(&args.resume_file).expect("Failed to create resume file");
+        let checkpoint = Checkpoint {
+            semesters: state.semesters,
+            start_time: state.start_time,
+            courses,
+            small_groups,
+            failed_pages,
+            queue: remaining,
+            branches,
+            catalog: state.catalog.lock().await.clone(),
+            modules: match &state.modules {
+                Some(modules) => modules.lock().await.clone(),
+                None => Vec::new(),
+            },
+            graph,
+        };
+        serde_json::to_writer_pretty(file, &checkpoint).expect("Failed to write resume file");
+        produced.push(args.resume_file.clone().into());
+    }
+
+    if config.tls.insecure {
+        eprintln!("WARNING: fetched with insecure TLS, the outputs may have been tampered with");
+    }
+    let manifest = Manifest {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: option_env!("PAUL_SCRAPE_GIT_HASH"),
+        started: state.start_time,
+        finished: chrono::Utc::now(),
+        config: run_config,
+        semesters,
+        counts,
+        files: produced
+            .iter()
+            .map(|path| manifest_file(path).expect("Failed to checksum output"))
+            .collect(),
+        states,
+    };
+    let manifest_path = match &output_dir {
+        Some(dir) => std::path::Path::new(dir).join("manifest.json"),
+        None => "manifest.json".into(),
+    };
+    let file = File::create(manifest_path).expect("Failed to create manifest");
+    serde_json::to_writer_pretty(file, &manifest).expect("Failed to write manifest");
+
+    let status_path = match &output_dir {
+        Some(dir) => std::path::Path::new(dir).join("status.json"),
+        None => "status.json".into(),
+    };
+    let file = File::create(status_path).expect("Failed to create status.json");
+    serde_json::to_writer_pretty(file, &status).expect("Failed to write status.json");
+    if status.status == Outcome::Degraded {
+        for exceeded in &status.exceeded {
+            eprintln!(
+                "Degraded: {:.1}% {:?} failures, more than {}%",
+                exceeded.rate, exceeded.kind, exceeded.threshold
+            );
+        }
+        // 1 is a crash, 2 a finished run that needs a look
+        std::process::exit(2)
+    }
+}
+
+fn manifest_file(path: &std::path::Path) -> std::io::Result<ManifestFile> {
+    let mut hasher = Sha256::new();
+    let bytes = std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(ManifestFile {
+        path: path.display().to_string(),
+        bytes,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
 
-    // we're done, dump state to state.json
-    let file = File::create("state.json").expect("Failed to create state.json");
-    let state = StateSerializable {
-        semester: state.semester,
-        start_time: state.start_time,
-        courses: state.courses.lock().await.clone(),
-        small_groups: state.small_groups.lock().await.clone(),
+fn report(
+    courses: &[Course],
+    small_groups: &[SmallGroup],
+    failed_pages: &[FailedPage],
+    timings: &Timings,
+    resolve_overrides: bool,
+) {
+    // summary of everything that didn't go smoothly
+    let mut warnings: BTreeMap<&str, usize> = BTreeMap::new();
+    for warning in courses
+        .iter()
+        .flat_map(|course| &course.warnings)
+        .chain(small_groups.iter().flat_map(|sg| &sg.warnings))
+    {
+        *warnings.entry(warning.kind()).or_default() += 1;
+    }
+    eprintln!(
+        "Scraped {} courses and {} small groups, {} pages failed",
+        courses.len(),
+        small_groups.len(),
+        failed_pages.len()
+    );
+    for (kind, count) in warnings {
+        eprintln!("  {}: {}", kind, count);
+    }
+    // slow ttfb/body means PAUL is slow, slow parse means we are
+    for (phase, durations) in timings.phases() {
+        eprintln!(
+            "  {} ({} samples): p50 {:?} p90 {:?} p99 {:?} max {:?}",
+            phase,
+            durations.len(),
+            percentile(&durations, 50.0),
+            percentile(&durations, 90.0),
+            percentile(&durations, 99.0),
+            percentile(&durations, 100.0),
+        );
+    }
+    // every new connection resolves the host first, so dns lookups count connections.
+    // hosts pinned with --resolve connect without a lookup, then the count is unknown
+    let phases = timings.phases();
+    let samples = |phase: &str| {
+        phases
+            .iter()
+            .find(|(name, _)| *name == phase)
+            .map_or(0, |(_, durations)| durations.len())
     };
-    serde_json::to_writer_pretty(file, &state).expect("Failed to write state.json");
+    let (requests, connections) = (samples("ttfb"), samples("dns"));
+    if requests > 0 {
+        let protocols = timings
+            .protocols()
+            .iter()
+            .map(|(version, count)| format!("{} {}", version, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if resolve_overrides {
+            eprintln!("  {} requests, {}", requests, protocols);
+        } else {
+            eprintln!(
+                "  {} requests over {} connections ({:.0}% reused), {}",
+                requests,
+                connections,
+                100.0 * requests.saturating_sub(connections) as f64 / requests as f64,
+                protocols
+            );
+        }
+        let transfer = timings.transfer();
+        eprintln!(
+            "  {:.1} MB transferred for {:.1} MB of pages ({:.0}% saved), {} of {} responses compressed",
+            transfer.transferred as f64 / 1e6,
+            transfer.decoded as f64 / 1e6,
+            100.0 * transfer.decoded.saturating_sub(transfer.transferred) as f64
+                / transfer.decoded.max(1) as f64,
+            transfer.compressed_responses,
+            requests
+        );
+    }
 }
 
-async fn handle_entry(entry: QueueEntry, state: State) {
+fn report_faculties(faculties: &BTreeMap<String, FacultyCounts>) {
+    if faculties.is_empty() {
+        return;
+    }
+    let width = faculties
+        .keys()
+        .map(|faculty| faculty.chars().count())
+        .max()
+        .unwrap_or(0);
+    eprintln!(
+        "  {:width$}  {:>7}  {:>7}  {:>6}  {:>7}",
+        "faculty",
+        "fetched",
+        "queued",
+        "failed",
+        "courses",
+        width = width
+    );
+    for (faculty, counts) in faculties {
+        // a faculty without courses most likely lost its subtree
+        let marker = if counts.courses == 0 || counts.failed > 0 {
+            " !"
+        } else {
+            ""
+        };
+        eprintln!(
+            "  {:width$}  {:>7}  {:>7}  {:>6}  {:>7}{}",
+            faculty,
+            counts.started,
+            counts.queued,
+            counts.failed,
+            counts.courses,
+            marker,
+            width = width
+        );
+    }
+}
+
+async fn resolve_semesters(
+    client: &reqwest::Client,
+    spec: &str,
+    base_url: &Url,
+    max_redirects: usize,
+) -> Vec<String> {
+    let specs = spec.split(',').map(str::trim).collect::<Vec<_>>();
+    let semesters = get_semesters(client.clone(), base_url, max_redirects)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to get semesters: {}", e);
+            std::process::exit(1)
+        });
+    let today = chrono::Local::now().date_naive();
+    specs
+        .into_iter()
+        .map(|spec| {
+            let semester = resolve_semester(spec, today, &semesters).unwrap_or_else(|| {
+                eprintln!("{} matches no single semester listed in PAUL", spec);
+                std::process::exit(1)
+            });
+            if semester != spec {
+                eprintln!("Resolved {} semester to {}", spec, semester);
+            }
+            semester
+        })
+        .collect()
+}
+
+async fn init() {
+    // first run wizard, answers default to the existing paul-scrape.toml
+    let existing = Config::load(CONFIG_FILE).unwrap_or_default();
+    if std::path::Path::new(CONFIG_FILE).exists()
+        && !prompt(&format!("{} exists, overwrite it?", CONFIG_FILE), "n")
+            .to_lowercase()
+            .starts_with('y')
     {
-        let mut running_tasks = state.running_tasks.lock().await;
-        *running_tasks += 1;
+        return;
     }
+
+    let default_base_url = existing
+        .base_url
+        .map(|url| url.to_string())
+        .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+    let base_url = loop {
+        match Url::parse(&prompt("PAUL base url", &default_base_url)) {
+            Ok(url) => break url,
+            Err(e) => println!("Not a url: {}", e),
+        }
+    };
+
+    println!("Fetching the semesters listed in PAUL...");
+    let client = client_builder(&existing.http, &existing.politeness, &existing.tls)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        })
+        .build()
+        .unwrap();
+    let semesters = match get_semesters(client, &base_url, 5).await {
+        Ok(semesters) => semesters,
+        Err(e) => {
+            println!("Failed to get semesters: {}", e);
+            Vec::new()
+        }
+    };
+    for (i, (name, _)) in semesters.iter().enumerate() {
+        println!("  {}) {}", i + 1, name);
+    }
+    println!("Pick semesters by number or name, separated by commas.");
+    println!("\"current\" and \"next\" are resolved on every run.");
+    let default_semester = existing.semester.unwrap_or_else(|| "current".to_string());
+    let semester = prompt("Semesters", &default_semester)
+        .split(',')
+        .map(str::trim)
+        .filter(|answer| !answer.is_empty())
+        .map(|answer| match answer.parse::<usize>() {
+            Ok(i) if (1..=semesters.len()).contains(&i) => semesters[i - 1].0.clone(),
+            _ => answer.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let default_rate = existing
+        .requests_per_second
+        .unwrap_or(REQUESTS_PER_SECOND)
+        .to_string();
+    let requests_per_second = loop {
+        match prompt("Requests per second", &default_rate).parse::<u64>() {
+            Ok(rate) if rate > 0 => break rate,
+            _ => println!("Enter a whole number above 0"),
+        }
+    };
+
+    let output_dir = prompt(
+        "Directory for the state files",
+        existing.output_dir.as_deref().unwrap_or("."),
+    );
+    let cache_dir = prompt(
+        "Directory for the page cache, \"none\" to disable",
+        existing.cache_dir.as_deref().unwrap_or("none"),
+    );
+    // sent with every request, some university IT policies require it
+    let contact = prompt(
+        "Contact for PAUL's operators (email or url), \"none\" to skip",
+        existing.politeness.contact.as_deref().unwrap_or("none"),
+    );
+
+    let config = Config {
+        base_url: Some(base_url),
+        semester: Some(semester),
+        requests_per_second: Some(requests_per_second),
+        output_dir: Some(output_dir).filter(|dir| dir != "."),
+        cache_dir: Some(cache_dir).filter(|dir| dir != "none"),
+        http: existing.http,
+        tls: existing.tls,
+        politeness: Politeness {
+            contact: Some(contact).filter(|contact| contact != "none"),
+            ..existing.politeness
+        },
+        appointment_columns: existing.appointment_columns,
+        webhooks: existing.webhooks,
+    };
+    config.save(CONFIG_FILE).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    println!(
+        "Wrote {}, run paul-scrape-rs to start scraping",
+        CONFIG_FILE
+    );
+}
+
+// the state files of the last run, as listed in its manifest
+fn latest_state_files() -> Vec<String> {
+    let config = Config::load(config_path()).unwrap_or_default();
+    let dir = std::path::PathBuf::from(config.output_dir.unwrap_or_else(|| ".".to_string()));
+    let manifest: serde_json::Value = match std::fs::read_to_string(dir.join("manifest.json")) {
+        Ok(manifest) => serde_json::from_str(&manifest).expect("Failed to parse manifest.json"),
+        // written before there were manifests
+        Err(_) => return vec![dir.join("state.json").display().to_string()],
+    };
+    match manifest["states"].as_array() {
+        Some(states) => states
+            .iter()
+            .filter_map(|path| path.as_str())
+            .map(str::to_string)
+            .collect(),
+        // written before the manifest listed them
+        None => vec![dir.join("state.json").display().to_string()],
+    }
+}
+
+// the given state files, or the ones of the latest scrape
+fn read_states(data: Vec<String>) -> (Vec<String>, Vec<StateSerializable>) {
+    let files = if data.is_empty() {
+        latest_state_files()
+    } else {
+        data
+    };
+    let states = files
+        .iter()
+        .map(|path| {
+            read_snapshot::<StateSerializable>(path).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1)
+            })
+        })
+        .collect::<Vec<_>>();
+    (files, states)
+}
+
+// "Di 11:00-13:00" once per weekly slot, and the rooms
+fn weekly_times(appointments: &[Appointment]) -> (String, String) {
+    let mut times = Vec::new();
+    let mut rooms = Vec::new();
+    for appointment in appointments {
+        let day = appointment
+            .start_time
+            .0
+            .split(',')
+            .next()
+            .unwrap_or_default();
+        let time = format!(
+            "{} {}-{}",
+            day, appointment.start_time.1, appointment.end_time.1
+        );
+        if !times.contains(&time) {
+            times.push(time);
+        }
+        if !rooms.contains(&appointment.room) {
+            rooms.push(appointment.room.clone());
+        }
+    }
+    (times.join(", "), rooms.join(", "))
+}
+
+// a course row of find and free-slots
+fn course_row(semester: &str, course: &Course) -> [String; 5] {
+    let heading = course.heading();
+    let (times, rooms) = weekly_times(&course.appointments);
+    [
+        semester.to_string(),
+        heading.first().cloned().unwrap_or_default(),
+        heading.get(1).cloned().unwrap_or_default(),
+        times,
+        rooms,
+    ]
+}
+
+fn find(query: &str, data: Vec<String>, fuzzy: bool, limit: usize) {
+    let (files, states) = read_states(data);
+
+    let hits = find_courses(&states, query, fuzzy);
+    let rows = hits
+        .iter()
+        .take(limit)
+        .map(|hit| course_row(hit.semester, hit.course))
+        .collect::<Vec<_>>();
+
+    print_table(&rows);
+    eprintln!(
+        "{} of {} matching courses in {}",
+        rows.len(),
+        hits.len(),
+        files.join(", ")
+    );
+}
+
+fn free_slots(chosen: &[String], data: Vec<String>, limit: usize) {
+    let (files, states) = read_states(data);
+
+    let mut rows = Vec::new();
+    let mut fitting = 0;
+    for state in &states {
+        for fit in find_free_slots(state, chosen) {
+            fitting += 1;
+            if rows.len() >= limit {
+                continue;
+            }
+            rows.push(course_row(&state.semester, fit.course));
+            // the small groups that fit, below their course
+            for small_group in fit.small_groups {
+                let name = small_group
+                    .path
+                    .fragments
+                    .last()
+                    .cloned()
+                    .unwrap_or_default();
+                let (times, rooms) = weekly_times(&small_group.appointments);
+                rows.push([
+                    String::new(),
+                    String::new(),
+                    format!("  {}", name),
+                    times,
+                    rooms,
+                ]);
+            }
+        }
+    }
+
+    print_table(&rows);
+    eprintln!(
+        "{} courses fit around {} in {}",
+        fitting,
+        chosen.join(", "),
+        files.join(", ")
+    );
+}
+
+fn print_table(rows: &[[String; 5]]) {
+    let header = ["semester", "number", "name", "times", "rooms"].map(str::to_string);
+    let rows = std::iter::once(&header)
+        .chain(rows)
+        .map(|row| row.to_vec())
+        .collect::<Vec<_>>();
+    print_columns(&rows, "");
+}
+
+// rows padded to aligned columns
+fn print_columns(rows: &[Vec<String>], indent: &str) {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    let widths = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|field| field.chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(field, width)| format!("{:width$}", field, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}{}", indent, line.trim_end());
+    }
+}
+
+// a worker's share of a partitioned crawl, written to partition.json
+#[derive(Serialize)]
+struct Shard {
+    file: String,
+    faculties: Vec<String>,
+    courses: usize,
+    requests_per_second: u64,
+}
+
+fn partition(catalog: &str, shards: usize, total_rate: Option<u64>, dir: &str) {
+    let entries: Vec<CatalogEntry> = std::fs::read_to_string(catalog)
+        .map_err(|e| e.to_string())
+        .and_then(|catalog| serde_json::from_str(&catalog).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", catalog, e);
+            std::process::exit(1)
+        });
+    let total_rate = total_rate
+        .or(Config::load(config_path())
+            .unwrap_or_default()
+            .requests_per_second)
+        .unwrap_or(REQUESTS_PER_SECOND);
+    let shards = shards.max(1);
+    if total_rate < shards as u64 {
+        eprintln!(
+            "A total rate of {} is less than one request per second per worker, using 1",
+            total_rate
+        );
+    }
+    let requests_per_second = (total_rate / shards as u64).max(1);
+    std::fs::create_dir_all(dir).expect("Failed to create shard directory");
+
+    let mut plan = Vec::new();
+    for (i, entries) in partition_by_faculty(&entries, shards)
+        .into_iter()
+        .enumerate()
+    {
+        if entries.is_empty() {
+            eprintln!(
+                "Shard {} is empty, there are fewer faculties than shards",
+                i + 1
+            );
+            continue;
+        }
+        let file = std::path::Path::new(dir).join(format!("shard-{}.json", i + 1));
+        let checkpoint = Checkpoint {
+            semesters: entries
+                .iter()
+                .filter_map(|entry| entry.path.fragments.first().cloned())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            start_time: chrono::Utc::now(),
+            courses: Vec::new(),
+            small_groups: Vec::new(),
+            failed_pages: Vec::new(),
+            queue: entries
+                .iter()
+                .filter_map(|entry| {
+                    let url = Url::parse(&entry.url).ok()?;
+                    Some(QueueEntry::CourseLeaf(
+                        CourseUrl::new(url),
+                        entry.path.clone(),
+                    ))
+                })
+                .collect(),
+            branches: BTreeMap::new(),
+            catalog: Vec::new(),
+            modules: Vec::new(),
+            graph: CrawlGraph::default(),
+        };
+        let writer = File::create(&file).expect("Failed to create shard file");
+        serde_json::to_writer(writer, &checkpoint).expect("Failed to write shard file");
+        plan.push(Shard {
+            file: file.display().to_string(),
+            faculties: entries
+                .iter()
+                .map(|entry| entry.path.faculty().unwrap_or_default().to_string())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            courses: checkpoint.queue.len(),
+            requests_per_second,
+        });
+    }
+    let plan_file = std::path::Path::new(dir).join("partition.json");
+    let writer = File::create(&plan_file).expect("Failed to create partition.json");
+    serde_json::to_writer_pretty(writer, &plan).expect("Failed to write partition.json");
+    for shard in &plan {
+        eprintln!(
+            "{}: {} courses of {}",
+            shard.file,
+            shard.courses,
+            shard.faculties.join(", ")
+        );
+    }
+    // the output dirs keep the workers' state.json files apart for merge
+    eprintln!("Run on the workers, then merge their state.json files:");
+    for (i, shard) in plan.iter().enumerate() {
+        eprintln!(
+            "  paul-scrape-rs --resume {} --requests-per-second {} --output-dir worker-{}",
+            shard.file,
+            shard.requests_per_second,
+            i + 1
+        );
+    }
+}
+
+fn merge(files: &[String], output: &str) {
+    let states = files
+        .iter()
+        .map(|path| {
+            read_snapshot::<StateSerializable>(path).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1)
+            })
+        })
+        .collect();
+    let merged = merge_states(states).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    let writer = File::create(output).expect("Failed to create merged state");
+    serde_json::to_writer(writer, &merged).expect("Failed to write merged state");
+    eprintln!(
+        "Merged {} courses, {} small groups and {} failed pages into {}",
+        merged.courses.len(),
+        merged.small_groups.len(),
+        merged.failed_pages.len(),
+        output
+    );
+}
+
+fn pack_run(dir: Option<String>, output: &str, with_cache: bool) {
+    let config = Config::load(config_path()).unwrap_or_default();
+    let dir = dir.or(config.output_dir).unwrap_or_else(|| ".".to_string());
+    let cache_dir = with_cache.then(|| {
+        config.cache_dir.unwrap_or_else(|| {
+            eprintln!("--with-cache: no cache_dir configured");
+            std::process::exit(1)
+        })
+    });
+    let packed = pack::pack(
+        dir.as_ref(),
+        output.as_ref(),
+        cache_dir.as_deref().map(AsRef::as_ref),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Failed to pack {}: {}", dir, e);
+        std::process::exit(1)
+    });
+    let pages = packed
+        .iter()
+        .filter(|entry| entry.path.starts_with("cache/"))
+        .count();
+    for entry in packed
+        .iter()
+        .filter(|entry| !entry.path.starts_with("cache/"))
+    {
+        eprintln!("  {} ({} bytes)", entry.path, entry.bytes);
+    }
+    if pages > 0 {
+        eprintln!("  {} cached pages", pages);
+    }
+    eprintln!("Wrote {}", output);
+}
+
+fn inspect_pack(archive: &str) {
+    let (entries, manifest) = pack::inspect(archive.as_ref()).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", archive, e);
+        std::process::exit(1)
+    });
+    if let Some(manifest) = manifest {
+        println!(
+            "paul-scrape {} run of {}, finished {}",
+            manifest["version"].as_str().unwrap_or("?"),
+            manifest["semesters"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|semester| semester.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            manifest["finished"].as_str().unwrap_or("?"),
+        );
+        let counts = &manifest["counts"];
+        println!(
+            "{} courses, {} small groups, {} failed pages",
+            counts["courses"], counts["small_groups"], counts["failed_pages"]
+        );
+    }
+    let pages = entries
+        .iter()
+        .filter(|entry| entry.path.starts_with("cache/"))
+        .count();
+    let rows = entries
+        .iter()
+        .filter(|entry| !entry.path.starts_with("cache/"))
+        .map(|entry| vec![entry.bytes.to_string(), entry.path.clone()])
+        .collect::<Vec<_>>();
+    print_columns(&rows, "  ");
+    if pages > 0 {
+        println!("  {} cached pages", pages);
+    }
+}
+
+async fn show(course: &str, data: Vec<String>) {
+    let (course, small_groups) = match Url::parse(course) {
+        Ok(url) => fetch_course(url).await,
+        Err(_) => {
+            let (files, states) = read_states(data);
+            // a cid of semester.json is the course number and a hash, "L.079.05401|3f"
+            let number = course.split('|').next().unwrap_or(course);
+            let found = states.into_iter().find_map(|state| {
+                let course = state.courses.iter().find(|candidate| {
+                    candidate.url == course
+                        || candidate.heading().first().map(String::as_str) == Some(number)
+                })?;
+                let small_groups = state
+                    .small_groups
+                    .iter()
+                    .filter(|small_group| course.small_groups.contains(&small_group.url))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Some((course.clone(), small_groups))
+            });
+            found.unwrap_or_else(|| {
+                eprintln!("No course {} in {}", course, files.join(", "));
+                std::process::exit(1)
+            })
+        }
+    };
+    print_course(&course, &small_groups);
+}
+
+// the course page and its small groups, straight from PAUL
+async fn fetch_course(url: Url) -> (Course, Vec<SmallGroup>) {
+    let config = Config::load(config_path()).unwrap_or_default();
+    let client = config
+        .client_builder()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        })
+        .build()
+        .unwrap();
+    let limits = ResponseLimits::default();
+    let fetch = |url: Url| {
+        let client = client.clone();
+        async move {
+            fetch_page_retrying(&client, &url, &limits, &Timings::default(), 3)
+                .await
+                .unwrap_or_else(|e| {
+                    eprintln!("Failed to fetch {}: {}", url, e);
+                    std::process::exit(1)
+                })
+        }
+    };
+    let url = CourseUrl::new(url);
+    let html = fetch(url.as_url().clone()).await;
+    // the parser expects a course heading, anything else is reported instead
+    if !is_course_page(&html) {
+        eprintln!("{} is not a course page", url.as_url());
+        std::process::exit(1)
+    }
+    let (_, mut next_listing) = parse_small_group_listing(&html, &url, &Path::new());
+    let (mut course, mut links) = parse_course_page(html, &url, &Path::new());
+    while let Some(listing) = next_listing {
+        let html = fetch(listing.clone()).await;
+        let (more, next) = parse_small_group_listing(&html, &listing, &course.path);
+        course
+            .small_groups
+            .extend(more.iter().map(|(url, _)| url.to_string()));
+        links.extend(more);
+        next_listing = next;
+    }
+    let mut small_groups = Vec::new();
+    for (small_group_url, path) in links {
+        let html = fetch(small_group_url.as_url().clone()).await;
+        small_groups.push(parse_small_group(html, &small_group_url, &path));
+    }
+    (course, small_groups)
+}
+
+fn print_course(course: &Course, small_groups: &[SmallGroup]) {
+    let amount = |amount: &Option<Amount>| {
+        amount.as_ref().map(|amount| {
+            if amount.min == amount.max {
+                amount.min.to_string()
+            } else {
+                format!("{}-{}", amount.min, amount.max)
+            }
+        })
+    };
+    println!("{}", course.heading().join("\n"));
+    let fields = [
+        ("url", Some(course.url.clone())),
+        ("type", course.course_type.clone()),
+        ("instructors", Some(course.instructors.clone())),
+        ("unit", course.ou.clone()),
+        ("credits", amount(&course.credits)),
+        ("sws", amount(&course.sws)),
+        ("prerequisites", course.prerequisites.clone()),
+        (
+            "last modified",
+            course.source_updated_at.map(|updated| updated.to_string()),
+        ),
+    ];
+    let rows = fields
+        .into_iter()
+        .filter_map(|(name, value)| Some(vec![name.to_string(), value?]))
+        .filter(|row| !row[1].is_empty())
+        .collect::<Vec<_>>();
+    print_columns(&rows, "  ");
+    for warning in &course.warnings {
+        println!("  warning: {}", warning);
+    }
+
+    println!();
+    print_appointments("Appointments", &course.appointments);
+    println!();
+    println!(
+        "Small groups ({} of {} linked)",
+        small_groups.len(),
+        course.small_groups.len()
+    );
+    for small_group in small_groups {
+        // scrapes from before names were parsed only have the title in the path
+        let (name, group_number) = if small_group.name.is_empty() {
+            parse_small_group_title(small_group.path.fragments.last().map_or("", String::as_str))
+        } else {
+            (small_group.name.clone(), small_group.group_number)
+        };
+        let title = match group_number {
+            Some(number) => format!("{} (group {})", name, number),
+            None => name,
+        };
+        print_appointments(&format!("  {}", title), &small_group.appointments);
+        for warning in &small_group.warnings {
+            println!("    warning: {}", warning);
+        }
+    }
+}
+
+fn print_appointments(title: &str, appointments: &[Appointment]) {
+    println!("{} ({})", title, appointments.len());
+    let rows = appointments
+        .iter()
+        .map(|appointment| {
+            vec![
+                appointment.start_time.0.clone(),
+                format!("{}-{}", appointment.start_time.1, appointment.end_time.1),
+                appointment.room.clone(),
+                appointment.instructors.clone(),
+                if appointment.cancelled {
+                    "cancelled".to_string()
+                } else {
+                    String::new()
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+    let indent = format!("{}  ", " ".repeat(title.len() - title.trim_start().len()));
+    print_columns(&rows, &indent);
+}
+
+// clap_mangen needs clap 4, this is the same layout written by hand
+fn man_page(command: &clap::Command) -> String {
+    // backslashes start roff escapes and a plain "-" is a hyphen, not the minus of a
+    // flag. lines starting with a dot or quote are requests, \& in front keeps them text
+    let escape = |text: &str| {
+        text.lines()
+            .map(|line| {
+                let line = line.replace('\\', "\\e").replace('-', "\\-");
+                if line.starts_with(['.', '\'']) {
+                    format!("\\&{}", line)
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let name = command.get_name();
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{}\"\n.SH NAME\n{} \\- {}\n.SH SYNOPSIS\n.B {}\n[OPTIONS] [BASE_URL] [SEMESTER]\n",
+        name.to_uppercase(),
+        command.get_version().unwrap_or_default(),
+        escape(name),
+        escape(command.get_about().unwrap_or_default()),
+        escape(name),
+    );
+    page.push_str(".SH OPTIONS\n");
+    for arg in command.get_arguments() {
+        let flag = match (arg.get_short(), arg.get_long()) {
+            (_, Some(long)) if arg.is_takes_value_set() => {
+                format!("\\-\\-{} <{}>", escape(long), arg.get_id().to_uppercase())
+            }
+            (_, Some(long)) => format!("\\-\\-{}", escape(long)),
+            (Some(short), None) => format!("\\-{}", short),
+            (None, None) => format!("<{}>", arg.get_id().to_uppercase()),
+        };
+        page.push_str(&format!(".TP\n{}\n", flag));
+        // the doc comments of Args, arguments without one only get their flag
+        if let Some(help) = arg.get_help() {
+            page.push_str(&format!("{}\n", escape(help)));
+        }
+    }
+    page.push_str(".SH COMMANDS\n");
+    for subcommand in command.get_subcommands() {
+        page.push_str(&format!(
+            ".TP\n{}\n{}\n",
+            escape(subcommand.get_name()),
+            escape(subcommand.get_about().unwrap_or_default())
+        ));
+    }
+    page
+}
+
+fn prompt(question: &str, default: &str) -> String {
+    print!("{} [{}]: ", question, default);
+    std::io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer).unwrap();
+    match answer.trim() {
+        "" => default.to_string(),
+        answer => answer.to_string(),
+    }
+}
+
+async fn handle_entry(entry: QueueEntry, state: State) {
+    let (kind, url, path) = (entry.kind(), entry.url().cloned(), entry.path().cloned());
+    process_entry(entry, &state).await;
+    state
+        .queue
+        .lock()
+        .await
+        .trace_done(kind, url.as_ref(), path.as_ref());
+    finish_entry(&state).await;
+}
+
+async fn process_entry(entry: QueueEntry, state: &State) {
     match entry {
         QueueEntry::Main => {
             // get the main page
-            let semesters = get_semesters(state.client.clone(), &state.base_url).await;
+            let semesters =
+                get_semesters(state.client.clone(), &state.base_url, state.max_redirects)
+                    .await
+                    .unwrap_or_else(|e| {
+                        eprintln!("Failed to get semesters: {}", e);
+                        std::process::exit(1)
+                    });
             // add the tree pages to the queue
             {
                 let mut queue = state.queue.lock().await;
+                let mut graph = state.graph.lock().await;
+                graph.add_node(state.base_url.as_str(), NodeKind::Main);
                 for (semester, url) in semesters {
-                    if semester != state.semester {
+                    if !state.semesters.contains(&semester) {
                         continue;
                     }
-                    queue.push_back(QueueEntry::Tree(url, Path::new().push(semester)));
+                    graph.add_edge(
+                        state.base_url.as_str(),
+                        url.as_str(),
+                        NodeKind::Tree,
+                        &semester,
+                    );
+                    let path =
+                        Path::new().push_segment(PathSegmentKind::Semester, semester, Some(&url));
+                    queue.push_back(QueueEntry::Tree(TreeUrl::new(url), path));
                 }
             }
         }
         QueueEntry::Tree(url, path) => {
             // get the tree page
-            let tree_page = state.client.get(url.clone()).send().await.unwrap();
-            let (courses, branches) = parse_courses_and_branches(
-                tree_page
-                    .text()
+            let Some(tree_page) = fetch(state, &url, &path, "tree").await else {
+                state
+                    .queue
+                    .lock()
                     .await
-                    .expect("Failed to parse tree page. This is probably a bug in paul-scrape-rs."),
-                &url,
-                &path,
-            );
-            {
-                let mut queue = state.queue.lock().await;
-                // add the tree pages to the queue
-                // debug: only take the first two branches
-                for (url, path) in branches {
-                    // for (url, path) in branches.into_iter().take(2) {
-                    queue.push_back(QueueEntry::Tree(url, path));
+                    .set_branch(&url, &path, BranchStatus::Failed);
+                return;
+            };
+            match catch_parse(&state.timings, || {
+                parse_tree_page_with(tree_page.clone(), &url, &path, &state.classifier)
+            }) {
+                Ok(TreePage {
+                    courses,
+                    branches,
+                    next_page,
+                    other_links,
+                }) => {
+                    let mut queue = state.queue.lock().await;
+                    // no parser for these yet, count them so they don't go unnoticed
+                    let mut unfollowed = state.unfollowed.lock().await;
+                    for (kind, link_url, path) in other_links {
+                        queue.trace(
+                            "unfollowed",
+                            Some(&format!("{:?}", kind).to_lowercase()),
+                            Some(&link_url),
+                            Some(&path),
+                            None,
+                        );
+                        *unfollowed.entry(kind).or_default() += 1;
+                    }
+                    drop(unfollowed);
+                    queue.set_branch(&url, &path, BranchStatus::Expanded);
+                    let mut graph = state.graph.lock().await;
+                    // the rest of a paginated listing, same category
+                    if let Some(next_page) = next_page {
+                        graph.add_edge(
+                            url.as_str(),
+                            next_page.as_str(),
+                            NodeKind::Tree,
+                            "next page",
+                        );
+                        queue.push_back(QueueEntry::Tree(next_page, path.clone()));
+                    }
+                    // add the tree pages to the queue
+                    // debug: only take the first two branches
+                    for (branch_url, path) in branches {
+                        // for (url, path) in branches.into_iter().take(2) {
+                        if path.fragments.len() > state.max_depth {
+                            queue.trace(
+                                "depth_limit",
+                                Some("tree"),
+                                Some(&branch_url),
+                                Some(&path),
+                                None,
+                            );
+                            state.depth_limited.lock().await.push(path);
+                            continue;
+                        }
+                        graph.add_edge(
+                            url.as_str(),
+                            branch_url.as_str(),
+                            NodeKind::Tree,
+                            path.fragments.last().unwrap(),
+                        );
+                        queue.push_back(QueueEntry::Tree(branch_url, path));
+                    }
+                    // add the leaf pages to the queue
+                    for CoursePage {
+                        url: course_url,
+                        path,
+                        course_type,
+                    } in courses
+                    {
+                        if !has_course_number_prefix(&path, &state.course_number_prefixes) {
+                            continue;
+                        }
+                        // without a type in the listing the course page has to tell
+                        if course_type.is_some()
+                            && !matches_course_type(course_type.as_deref(), &state.only_types)
+                        {
+                            continue;
+                        }
+                        if state.shallow {
+                            state.catalog.lock().await.push(
+                                CoursePage {
+                                    url: course_url,
+                                    path,
+                                    course_type,
+                                }
+                                .into(),
+                            );
+                            continue;
+                        }
+                        graph.add_edge(
+                            url.as_str(),
+                            course_url.as_str(),
+                            NodeKind::Course,
+                            path.fragments.last().unwrap(),
+                        );
+                        queue.push_back(QueueEntry::CourseLeaf(course_url, path));
+                    }
                 }
-                // add the leaf pages to the queue
-                for CoursePage { url, path } in courses {
-                    queue.push_back(QueueEntry::CourseLeaf(url, path));
+                Err(error) => {
+                    state
+                        .queue
+                        .lock()
+                        .await
+                        .set_branch(&url, &path, BranchStatus::Failed);
+                    parse_failed(state, &url, path, error, &tree_page, TREE_PAGE).await
                 }
             }
         }
         QueueEntry::CourseLeaf(url, path) => {
             // get the leaf page
-            let course_page = state
-                .client
-                .get(url.clone())
-                .send()
-                .await
-                .unwrap_or_else(|e| {
-                    eprintln!(
-                        "[{}] Failed to get course page: {} ({:?}) with error: {}",
-                        chrono::Utc::now(),
-                        url,
-                        path,
-                        e
-                    );
-                    std::process::exit(1)
-                });
+            let Some(course_page) = fetch(state, &url, &path, "course").await else {
+                return;
+            };
             // parse the response
-            let (course, small_groups_links) = parse_course_page(
-                course_page.text().await.expect(
-                    "Failed to parse course page. This is probably a bug in paul-scrape-rs.",
-                ),
-                &url,
-                &path,
-            );
-
-            // add the small group pages to the queue
-            {
-                let mut queue = state.queue.lock().await;
-                for (url, path) in small_groups_links {
-                    queue.push_back(QueueEntry::SmallGroupLeaf(url, path));
+            match catch_parse(&state.timings, || {
+                // parsed once for the course, its listing and the combined link
+                let document = Html::parse_document(&course_page);
+                let (course, small_groups_links) = parse_course_document(
+                    &document,
+                    &url,
+                    &path,
+                    &state.appointment_columns,
+                    &state.classifier,
+                );
+                let (_, next_listing) = parse_small_group_listing_document(&document, &url, &path);
+                let combined = combined_small_groups_link_document(&document, &url);
+                (course, small_groups_links, next_listing, combined)
+            }) {
+                Ok((mut course, small_groups_links, next_listing, combined)) => {
+                    if course.course_type.is_some()
+                        && !matches_course_type(course.course_type.as_deref(), &state.only_types)
+                    {
+                        return;
+                    }
+                    check_strict(state, &url, &course.warnings);
+                    apply_post_processors(&state.post_processors, &mut course);
+                    if state.embed_html == EmbedHtml::All {
+                        course.html = Some(embed_html(&course_page));
+                    }
+                    // modules are shared by several courses, the queue only fetches each once
+                    let module_urls = match state.modules {
+                        Some(_) => course.modules.clone(),
+                        None => Vec::new(),
+                    };
+                    // add the course to the list of courses, before the listing
+                    // continuation pages can append to it
+                    {
+                        state
+                            .queue
+                            .lock()
+                            .await
+                            .count_faculty(&course.path, FacultyEvent::Course);
+                        let mut courses = state.courses.lock().await;
+                        courses.push(course);
+                    }
+                    // add the small group pages to the queue
+                    {
+                        let mut queue = state.queue.lock().await;
+                        let mut graph = state.graph.lock().await;
+                        for (small_group_url, path) in &small_groups_links {
+                            graph.add_edge(
+                                url.as_str(),
+                                small_group_url.as_str(),
+                                NodeKind::SmallGroup,
+                                path.fragments.last().unwrap(),
+                            );
+                        }
+                        // one request for all groups instead of one per group
+                        match combined
+                            .filter(|_| state.combine_small_groups && small_groups_links.len() > 1)
+                        {
+                            Some(combined) => queue.push_back(QueueEntry::SmallGroupsCombined(
+                                combined,
+                                small_groups_links,
+                                path.clone(),
+                            )),
+                            None => {
+                                for (small_group_url, path) in small_groups_links {
+                                    queue.push_back(QueueEntry::SmallGroupLeaf(
+                                        small_group_url,
+                                        path,
+                                    ));
+                                }
+                            }
+                        }
+                        if let Some(next_listing) = next_listing {
+                            queue.push_back(QueueEntry::SmallGroupListing(
+                                url.clone(),
+                                next_listing,
+                                path.clone(),
+                            ));
+                        }
+                        for module_url in module_urls.iter().filter_map(|url| Url::parse(url).ok())
+                        {
+                            queue.push_back(QueueEntry::ModuleLeaf(
+                                ModuleUrl::new(module_url),
+                                path.clone(),
+                            ));
+                        }
+                    }
+                }
+                Err(error) => {
+                    parse_failed(state, &url, path, error, &course_page, COURSE_PAGE).await
                 }
-            }
-            // add the course to the list of courses
-            {
-                let mut courses = state.courses.lock().await;
-                courses.push(course);
             }
         }
         QueueEntry::SmallGroupLeaf(url, path) => {
             // get the leaf page
-            let small_group_page = state
-                .client
-                .get(url.clone())
-                .send()
-                .await
-                .unwrap_or_else(|e| {
-                    eprintln!(
-                        "[{}] Failed to get small group page: {} ({:?}) with error: {}",
-                        chrono::Utc::now(),
-                        url,
-                        path,
-                        e
-                    );
-                    std::process::exit(1)
-                });
+            let Some(small_group_page) = fetch(state, &url, &path, "small group").await else {
+                return;
+            };
             // parse the response
-            let small_group = parse_small_group(
-                small_group_page.text().await.expect(
-                    "Failed to parse small group page. This is probably a bug in paul-scrape-rs.",
-                ),
-                &url,
-                &path,
-            );
+            match catch_parse(&state.timings, || {
+                parse_small_group_with(
+                    small_group_page.clone(),
+                    &url,
+                    &path,
+                    &state.appointment_columns,
+                )
+            }) {
+                Ok(mut small_group) => {
+                    check_strict(state, &url, &small_group.warnings);
+                    if state.embed_html == EmbedHtml::All {
+                        small_group.html = Some(embed_html(&small_group_page));
+                    }
+                    // add the small group to the list of small groups
+                    let mut small_groups = state.small_groups.lock().await;
+                    small_groups.push(small_group);
+                }
+                Err(error) => {
+                    parse_failed(state, &url, path, error, &small_group_page, COURSE_PAGE).await
+                }
+            }
+        }
+        QueueEntry::SmallGroupListing(course_url, url, path) => {
+            let Some(listing_page) = fetch(state, &url, &path, "small group listing").await else {
+                return;
+            };
+            match catch_parse(&state.timings, || {
+                parse_small_group_listing(&listing_page, &url, &path)
+            }) {
+                Ok((small_groups_links, next_listing)) => {
+                    // the course only knows the links of the first page
+                    {
+                        let mut courses = state.courses.lock().await;
+                        if let Some(course) = courses
+                            .iter_mut()
+                            .find(|course| course.url == course_url.as_str())
+                        {
+                            course.small_groups.extend(
+                                small_groups_links
+                                    .iter()
+                                    .map(|(url, _)| url.as_str().to_string()),
+                            );
+                        }
+                    }
+                    let mut queue = state.queue.lock().await;
+                    let mut graph = state.graph.lock().await;
+                    for (small_group_url, path) in small_groups_links {
+                        graph.add_edge(
+                            course_url.as_str(),
+                            small_group_url.as_str(),
+                            NodeKind::SmallGroup,
+                            path.fragments.last().unwrap(),
+                        );
+                        queue.push_back(QueueEntry::SmallGroupLeaf(small_group_url, path));
+                    }
+                    if let Some(next_listing) = next_listing {
+                        queue.push_back(QueueEntry::SmallGroupListing(
+                            course_url,
+                            next_listing,
+                            path,
+                        ));
+                    }
+                }
+                Err(error) => {
+                    parse_failed(state, &url, path, error, &listing_page, SMALL_GROUP_LISTING).await
+                }
+            }
+        }
+        QueueEntry::SmallGroupsCombined(url, links, path) => {
+            let small_groups = match fetch(state, &url, &path, "combined small groups").await {
+                Some(page) => match catch_parse(&state.timings, || {
+                    parse_combined_small_groups_with(&page, &url, &path, &state.appointment_columns)
+                }) {
+                    Ok(small_groups) => small_groups,
+                    Err(error) => {
+                        parse_failed(state, &url, path, error, &page, SMALL_GROUP_LISTING).await;
+                        Vec::new()
+                    }
+                },
+                None => Vec::new(),
+            };
+            // groups the page doesn't show are fetched one by one
+            let listed = links
+                .iter()
+                .map(|(url, _)| url.as_str())
+                .collect::<HashSet<_>>();
+            let small_groups = small_groups
+                .into_iter()
+                .filter(|small_group| listed.contains(small_group.url.as_str()))
+                .collect::<Vec<_>>();
+            let found = small_groups
+                .iter()
+                .map(|small_group| small_group.url.clone())
+                .collect::<HashSet<_>>();
+            let mut queue = state.queue.lock().await;
+            // a group already fetched through another link isn't added twice
+            let small_groups = small_groups
+                .into_iter()
+                .filter(|small_group| queue.mark_seen(&small_group.url))
+                .collect::<Vec<_>>();
+            for small_group in &small_groups {
+                check_strict(state, &url, &small_group.warnings);
+            }
+            state.small_groups.lock().await.extend(small_groups);
+            for (small_group_url, path) in links {
+                if !found.contains(small_group_url.as_str()) {
+                    queue.push_back(QueueEntry::SmallGroupLeaf(small_group_url, path));
+                }
+            }
+        }
+        QueueEntry::ModuleLeaf(url, path) => {
+            let Some(module_page) = fetch(state, &url, &path, "module").await else {
+                return;
+            };
+            match catch_parse(&state.timings, || {
+                parse_module_page_with(&module_page, &url, &state.classifier)
+            }) {
+                Ok(module) => {
+                    if let Some(modules) = &state.modules {
+                        modules.lock().await.push(module);
+                    }
+                }
+                Err(error) => {
+                    parse_failed(state, &url, path, error, &module_page, MODULE_PAGE).await
+                }
+            }
+        }
+    }
+}
 
-            // add the small group to the list of small groups
-            {
-                let mut small_groups = state.small_groups.lock().await;
-                small_groups.push(small_group);
+async fn finish_entry(state: &State) {
+    state.queue.lock().await.done();
+    state.entry_done.notify_one();
+}
+
+async fn fetch(state: &State, url: &Url, path: &Path, kind: &str) -> Option<String> {
+    if let Some(page) = state.cache.as_ref().and_then(|cache| cache.get(url)) {
+        return Some(page);
+    }
+    let page = fetch_page_retrying(
+        &state.client,
+        url,
+        &state.response_limits,
+        &state.timings,
+        state.retries,
+    )
+    .await;
+    state.pacer.lock().await.record(page.is_ok());
+    match page {
+        Ok(page) => {
+            if let Some(cache) = &state.cache {
+                if let Err(e) = cache.put(url, &page) {
+                    eprintln!("Failed to cache {}: {}", url, e);
+                }
             }
+            Some(page)
+        }
+        // unexpected responses and pages that kept failing are recorded, the crawl goes on
+        Err(e) => {
+            eprintln!(
+                "[{}] Failed to get {} page: {} ({:?}) with error: {}",
+                chrono::Utc::now(),
+                kind,
+                url,
+                path,
+                e
+            );
+            record_failure(state, url, path.clone(), e.kind(), e.to_string(), "", None).await;
+            None
         }
     }
+}
+
+fn parse_resolve(resolve: &str) -> Result<(String, std::net::SocketAddr), String> {
+    let mut parts = resolve.splitn(3, ':');
+    let (Some(host), Some(port), Some(ip)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("expected HOST:PORT:IP, got {}", resolve));
+    };
+    let port = port.parse().map_err(|e| format!("{}: {}", port, e))?;
+    // ipv6 addresses may come in brackets like in urls
+    let ip = ip
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .map_err(|e| format!("{}: {}", ip, e))?;
+    Ok((host.to_string(), std::net::SocketAddr::new(ip, port)))
+}
+
+fn parse_threshold(threshold: &str) -> Result<(ErrorKind, f64), String> {
+    let (kind, percent) = threshold
+        .split_once('=')
+        .ok_or_else(|| format!("expected KIND=PERCENT, got {}", threshold))?;
+    let percent = percent
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|e| format!("{}: {}", percent, e))?;
+    Ok((kind.parse()?, percent))
+}
+
+fn parse_link_marker(marker: &str) -> Result<(String, LinkKind), String> {
+    let (marker, kind) = marker
+        .split_once('=')
+        .ok_or_else(|| format!("expected MARKER=kind, got {}", marker))?;
+    Ok((marker.to_string(), kind.parse()?))
+}
+
+fn has_course_number_prefix(path: &Path, prefixes: &[String]) -> bool {
+    // the link text in the listing starts with the course number
+    let title = path.fragments.last().map(|title| title.trim_start());
+    prefixes.is_empty()
+        || title.is_some_and(|title| prefixes.iter().any(|prefix| title.starts_with(prefix)))
+}
+
+fn catch_parse<T>(timings: &Timings, parse: impl FnOnce() -> T) -> Result<T, String> {
+    // the parsers panic on unexpected markup, keep the crawl going instead
+    let started = std::time::Instant::now();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(parse));
+    timings.record("parse", started.elapsed());
+    result.map_err(|panic| {
+        panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "parser panicked".to_string())
+    })
+}
+
+fn embed_html(html: &str) -> String {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(html.as_bytes()).unwrap();
+    BASE64_STANDARD.encode(encoder.finish().unwrap())
+}
+
+async fn parse_failed(
+    state: &State,
+    url: &Url,
+    path: Path,
+    error: String,
+    html: &str,
+    expected: &[&str],
+) {
+    let excerpt = page_excerpt(html, expected);
+    if state.strict {
+        eprintln!("Failed to parse {}: {}\n  page: {}", url, error, excerpt);
+        std::process::exit(1)
+    }
+    record_failure(
+        state,
+        url,
+        path,
+        ErrorKind::Parse,
+        error,
+        html,
+        Some(excerpt),
+    )
+    .await;
+}
+
+fn check_strict(state: &State, url: &Url, warnings: &[Warning]) {
+    if !state.strict {
+        return;
+    }
+    if let Some(warning) = warnings
+        .iter()
+        .find(|warning| warning.severity() == Severity::Markup)
     {
-        let mut running_tasks = state.running_tasks.lock().await;
-        *running_tasks -= 1;
+        eprintln!("Unexpected markup on {}: {}", url, warning);
+        std::process::exit(1)
     }
 }
+
+async fn record_failure(
+    state: &State,
+    url: &Url,
+    path: Path,
+    kind: ErrorKind,
+    error: String,
+    html: &str,
+    excerpt: Option<String>,
+) {
+    {
+        let mut queue = state.queue.lock().await;
+        queue.trace("failed", None, Some(url), Some(&path), Some(&error));
+        queue.record_failure(url, kind);
+        queue.count_faculty(&path, FacultyEvent::Failed);
+    }
+    let failed_page = FailedPage {
+        url: url.to_string(),
+        path,
+        error,
+        kind: Some(kind),
+        html: match state.embed_html {
+            _ if html.is_empty() => None,
+            EmbedHtml::None => None,
+            EmbedHtml::Failed | EmbedHtml::All => Some(embed_html(html)),
+        },
+        excerpt,
+    };
+    state.failed_pages.lock().await.push(failed_page);
+}
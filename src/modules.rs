@@ -0,0 +1,80 @@
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    extract_labeled_value, extract_links, extract_section,
+    language::detect_language,
+    links::{LinkClassifier, LinkKind, ModuleUrl},
+    normalize_text,
+    numbers::{parse_amount, Amount},
+    CourseRef,
+};
+
+// module handbook pages (MODULEDETAILS), linked from the courses belonging to a module
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Module {
+    pub url: String,
+    // "M.079.4010"
+    pub number: String,
+    pub title: String,
+    pub credits: Option<Amount>,
+    pub responsible: Option<String>,
+    // the courses the module consists of
+    pub courses: Vec<CourseRef>,
+}
+
+pub fn parse_module_page(response: &str, url: &ModuleUrl) -> Module {
+    parse_module_page_with(response, url, &LinkClassifier::default())
+}
+
+/// Like [`parse_module_page`], finding the course links with `classifier`.
+pub fn parse_module_page_with(
+    response: &str,
+    url: &ModuleUrl,
+    classifier: &LinkClassifier,
+) -> Module {
+    let document = Html::parse_document(response);
+    let captions = detect_language(&document).captions();
+
+    // "M.079.4010 Softwaretechnik", sometimes split over two lines like course titles
+    let heading = document
+        .select(&Selector::parse("h1").unwrap())
+        .next()
+        .map(|h1| normalize_text(h1.text()))
+        .unwrap_or_default();
+    let (number, title) = heading.split_once(' ').unwrap_or((heading.as_str(), ""));
+
+    // "Modulverantwortliche(r):", the suffix differs between pages
+    let cell_selector = Selector::parse("td, th").unwrap();
+    let responsible = document
+        .select(&Selector::parse("tr").unwrap())
+        .find_map(|row| {
+            let mut cells = row.select(&cell_selector);
+            let key = normalize_text(cells.next()?.text()).to_lowercase();
+            if !key.starts_with(&captions.module_responsible.to_lowercase()) {
+                return None;
+            }
+            Some(normalize_text(cells.next()?.text()))
+        })
+        .filter(|responsible| !responsible.is_empty());
+
+    let credits = extract_labeled_value(&document, captions.credits)
+        .or_else(|| extract_section(&document, captions.credits))
+        .and_then(|text| parse_amount(&text));
+
+    Module {
+        url: url.to_string(),
+        number: number.to_string(),
+        title: title.to_string(),
+        credits,
+        responsible,
+        courses: extract_links(&document, url, LinkKind::Course, classifier)
+            .into_iter()
+            .map(|(url, title)| CourseRef {
+                url: url.to_string(),
+                title,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+// PAUL writes numbers the german way ("2,5") and sometimes as ranges ("5-10"),
+// usually followed by a unit ("5 ECTS", "4 SWS")
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Amount {
+    pub min: f64,
+    pub max: f64,
+}
+
+pub(crate) fn parse_number(number: &str) -> Option<f64> {
+    let number = number.trim();
+    // "1.000,5" => "1000.5", a lone dot is taken as a decimal point
+    let number = if number.contains(',') {
+        number.replace('.', "").replace(',', ".")
+    } else {
+        number.to_string()
+    };
+    number.parse().ok()
+}
+
+/// Parses "5", "2,5 SWS", "5-10 ECTS" or "5 bis 10", `None` if there is no number.
+pub fn parse_amount(text: &str) -> Option<Amount> {
+    let numbers = text
+        .split(|c: char| !(c.is_ascii_digit() || c == ',' || c == '.'))
+        .map(|part| part.trim_matches(|c| c == ',' || c == '.'))
+        .filter(|part| !part.is_empty())
+        .map(parse_number)
+        .collect::<Option<Vec<_>>>()?;
+    match numbers.as_slice() {
+        [value] => Some(Amount {
+            min: *value,
+            max: *value,
+        }),
+        // only ranges, "5 ECTS / 4 SWS" is ambiguous
+        [min, max] if is_range(text) => Some(Amount {
+            min: min.min(*max),
+            max: min.max(*max),
+        }),
+        _ => None,
+    }
+}
+
+fn is_range(text: &str) -> bool {
+    let text = text.to_lowercase();
+    text.contains('-') || text.contains('–') || text.contains(" bis ") || text.contains(" to ")
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+// how fast the crawler sends requests. a constant pacer keeps the configured
+// rate from start to end, a ramped one starts slow, speeds up while requests
+// succeed, backs off when they fail and slows down again once only leaves are
+// left, so the crawl doesn't hit the server at full rate out of nowhere
+
+// the ramp starts at this fraction of the configured rate and never drops below it
+const WARMUP_START: f64 = 0.1;
+// outcomes between two rate adjustments
+const RAMP_WINDOW: u32 = 20;
+const RAMP_STEP: f64 = 1.25;
+// more failures than this in a window halve the rate
+const MAX_ERROR_RATE: f64 = 0.1;
+// fraction of the configured rate once only leaves are queued
+const COOLDOWN: f64 = 0.5;
+
+#[derive(Clone, Debug)]
+pub struct Pacer {
+    target: f64,
+    current: f64,
+    ramp: bool,
+    successes: u32,
+    failures: u32,
+}
+
+impl Pacer {
+    /// `requests_per_second` from the first request to the last.
+    pub fn constant(requests_per_second: f64) -> Self {
+        Self {
+            target: requests_per_second,
+            current: requests_per_second,
+            ramp: false,
+            successes: 0,
+            failures: 0,
+        }
+    }
+
+    /// Ramps up to `requests_per_second` while the error rate stays low.
+    pub fn ramp(requests_per_second: f64) -> Self {
+        Self {
+            current: requests_per_second * WARMUP_START,
+            ramp: true,
+            ..Self::constant(requests_per_second)
+        }
+    }
+
+    /// Records the outcome of a request, adjusting the rate after every window.
+    pub fn record(&mut self, ok: bool) {
+        if !self.ramp {
+            return;
+        }
+        if ok {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+        let total = self.successes + self.failures;
+        if total < RAMP_WINDOW {
+            return;
+        }
+        self.current = if self.failures as f64 / total as f64 > MAX_ERROR_RATE {
+            (self.current / 2.0).max(self.target * WARMUP_START)
+        } else {
+            (self.current * RAMP_STEP).min(self.target)
+        };
+        self.successes = 0;
+        self.failures = 0;
+    }
+
+    /// Current requests per second, lower once `leaves_only` for a ramped pacer.
+    pub fn rate(&self, leaves_only: bool) -> f64 {
+        if self.ramp && leaves_only {
+            self.current.min(self.target * COOLDOWN)
+        } else {
+            self.current
+        }
+    }
+
+    /// Time to wait before the next request.
+    pub fn delay(&self, leaves_only: bool) -> Duration {
+        Duration::from_secs_f64(1.0 / self.rate(leaves_only))
+    }
+}
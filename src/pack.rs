@@ -0,0 +1,150 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+// a whole run in one tar.zst, to hand a scrape to someone else: manifest.json,
+// status.json, the outputs listed in the manifest, the semester*.json files of
+// convertjson and, if asked for, the html cache under cache/. failed pages are
+// part of the state files, status.json says whether there were too many
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PackEntry {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// Writes the run in `dir` to `archive`, returns what was packed.
+pub fn pack(dir: &Path, archive: &Path, cache_dir: Option<&Path>) -> io::Result<Vec<PackEntry>> {
+    let files = run_files(dir)?;
+    if files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no manifest.json or state.json in {}", dir.display()),
+        ));
+    }
+    let encoder = zstd::Encoder::new(File::create(archive)?, 0)?;
+    let mut builder = tar::Builder::new(encoder);
+    let mut packed = Vec::new();
+    for (name, path) in files {
+        builder.append_path_with_name(&path, &name)?;
+        packed.push(PackEntry {
+            path: name,
+            bytes: fs::metadata(&path)?.len(),
+        });
+    }
+    if let Some(cache_dir) = cache_dir {
+        let mut pages = fs::read_dir(cache_dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        // half written pages end in .partial
+        pages.retain(|page| {
+            page.extension()
+                .is_some_and(|extension| extension == "html")
+        });
+        pages.sort();
+        for page in pages {
+            let name = format!("cache/{}", page.file_name().unwrap().to_string_lossy());
+            builder.append_path_with_name(&page, &name)?;
+            packed.push(PackEntry {
+                path: name,
+                bytes: fs::metadata(&page)?.len(),
+            });
+        }
+    }
+    builder.into_inner()?.finish()?;
+    Ok(packed)
+}
+
+// archive name and location of every file of the run in `dir`
+fn run_files(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut add = |path: PathBuf| {
+        let name = path
+            .strip_prefix(dir)
+            .ok()
+            .filter(|name| !name.as_os_str().is_empty())
+            .or_else(|| path.file_name().map(Path::new))
+            .map(|name| name.to_string_lossy().replace('\\', "/"));
+        if let Some(name) = name {
+            if path.is_file() && !files.iter().any(|(known, _)| *known == name) {
+                files.push((name, path));
+            }
+        }
+    };
+    let manifest = dir.join("manifest.json");
+    match fs::read_to_string(&manifest) {
+        Ok(content) => {
+            let parsed: serde_json::Value = serde_json::from_str(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            add(manifest);
+            add(dir.join("status.json"));
+            for path in parsed["files"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|file| file["path"].as_str())
+            {
+                // the paths are as the crawler wrote them, relative to where it ran.
+                // the copy in dir wins, from elsewhere the path can name another run's file
+                let path = PathBuf::from(path);
+                match path.file_name().map(|name| dir.join(name)) {
+                    Some(in_dir) if in_dir.exists() => add(in_dir),
+                    _ => add(path),
+                }
+            }
+        }
+        // written before there were manifests
+        Err(_) => add(dir.join("state.json")),
+    }
+    let mut converted = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with("semester") && name.ends_with(".json")
+        })
+        .collect::<Vec<_>>();
+    converted.sort();
+    converted.into_iter().for_each(add);
+    Ok(files)
+}
+
+/// Lists the files in `archive` without extracting them.
+pub fn inspect(archive: &Path) -> io::Result<(Vec<PackEntry>, Option<serde_json::Value>)> {
+    let mut tar = tar::Archive::new(zstd::Decoder::new(File::open(archive)?)?);
+    let mut entries = Vec::new();
+    let mut manifest = None;
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().to_string();
+        if path == "manifest.json" {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            manifest = serde_json::from_str(&content).ok();
+        }
+        entries.push(PackEntry {
+            path,
+            bytes: entry.header().size()?,
+        });
+    }
+    Ok((entries, manifest))
+}
+
+/// Extracts `archive` into `dir`. Entries pointing outside of it are skipped.
+pub fn unpack(archive: &Path, dir: &Path) -> io::Result<Vec<PackEntry>> {
+    fs::create_dir_all(dir)?;
+    let mut tar = tar::Archive::new(zstd::Decoder::new(File::open(archive)?)?);
+    let mut unpacked = Vec::new();
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().to_string();
+        let bytes = entry.header().size()?;
+        if entry.unpack_in(dir)? {
+            unpacked.push(PackEntry { path, bytes });
+        }
+    }
+    Ok(unpacked)
+}
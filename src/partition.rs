@@ -0,0 +1,83 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{CatalogEntry, StateSerializable};
+
+// a crawl spread over several machines: a shallow crawl lists the courses,
+// `partition` splits them into shards, every worker resumes from its shard
+// and `merge` puts the workers' state files back together
+
+/// Splits the courses of a shallow crawl into `shards` parts of about equal
+/// size, keeping the courses of a faculty together. Courses listed under
+/// several categories are only assigned once.
+pub fn partition_by_faculty(entries: &[CatalogEntry], shards: usize) -> Vec<Vec<CatalogEntry>> {
+    let mut seen = HashSet::new();
+    let mut faculties: BTreeMap<&str, Vec<CatalogEntry>> = BTreeMap::new();
+    for entry in entries {
+        if seen.insert(entry.url.as_str()) {
+            faculties
+                .entry(entry.path.faculty().unwrap_or_default())
+                .or_default()
+                .push(entry.clone());
+        }
+    }
+    // largest first onto the smallest shard, ties by name so runs agree
+    let mut faculties = faculties.into_iter().collect::<Vec<_>>();
+    faculties.sort_by(|(a, a_entries), (b, b_entries)| {
+        b_entries.len().cmp(&a_entries.len()).then(a.cmp(b))
+    });
+    let mut partition = vec![Vec::new(); shards.max(1)];
+    for (_, entries) in faculties {
+        let smallest = partition
+            .iter_mut()
+            .min_by_key(|shard: &&mut Vec<CatalogEntry>| shard.len())
+            .unwrap();
+        smallest.extend(entries);
+    }
+    partition
+}
+
+/// Combines the state files of the workers of one semester. A page is kept
+/// once, and a page one worker failed on is dropped if another one got it.
+pub fn merge_states(states: Vec<StateSerializable>) -> Result<StateSerializable, String> {
+    let mut states = states.into_iter();
+    let Some(mut merged) = states.next() else {
+        return Err("nothing to merge".to_string());
+    };
+    let mut courses = merged
+        .courses
+        .iter()
+        .map(|course| course.url.clone())
+        .collect::<HashSet<_>>();
+    let mut small_groups = merged
+        .small_groups
+        .iter()
+        .map(|small_group| small_group.url.clone())
+        .collect::<HashSet<_>>();
+    for state in states {
+        if state.semester != merged.semester {
+            return Err(format!(
+                "{} and {} are different semesters",
+                merged.semester, state.semester
+            ));
+        }
+        merged.start_time = merged.start_time.min(state.start_time);
+        for course in state.courses {
+            if courses.insert(course.url.clone()) {
+                merged.courses.push(course);
+            }
+        }
+        for small_group in state.small_groups {
+            if small_groups.insert(small_group.url.clone()) {
+                merged.small_groups.push(small_group);
+            }
+        }
+        merged.failed_pages.extend(state.failed_pages);
+    }
+    let mut failed = HashSet::new();
+    merged.failed_pages.retain(|page| {
+        !courses.contains(&page.url)
+            && !small_groups.contains(&page.url)
+            && failed.insert(page.url.clone())
+    });
+    Ok(merged)
+}
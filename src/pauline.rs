@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Location;
+
+// the output format consumed by Pauline frontends (semester.json)
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Clone, Debug)]
+pub struct Semester {
+    pub name: String,
+    pub created: String,
+    pub courses: Vec<PaulineCourse>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Clone, Debug)]
+pub struct PaulineCourse {
+    pub cid: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub ou: Option<String>,
+    pub instructors: Option<String>,
+    pub small_groups: Vec<PaulineSmallGroup>,
+    pub appointments: Vec<PaulineAppointment>,
+    // from convertjson --tag-rules, left out when empty so the format stays the same
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // when PAUL says the course was last edited, e.g. "2024-03-12T14:35:00"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_updated_at: Option<String>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Clone, Debug)]
+pub struct PaulineSmallGroup {
+    pub name: String,
+    pub appointments: Vec<PaulineAppointment>,
+    // "Übung 03" => 3, left out when the name has no number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_number: Option<u32>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Clone, Debug)]
+pub struct PaulineAppointment {
+    pub start_time: String,
+    pub end_time: String,
+    pub room: String,
+    // canonical room from convertjson --normalize-rooms, room stays as PAUL has it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+    pub instructors: String,
+    // room, online or hybrid, with the meeting link found in the room column
+    #[serde(default, skip_serializing_if = "Location::is_unknown")]
+    pub location: Location,
+    // "fällt aus" or struck through on PAUL, kept so consumers can show it
+    #[serde(default)]
+    pub cancelled: bool,
+}
@@ -0,0 +1,110 @@
+use crate::{Course, PathSegmentKind};
+
+/// Enriches or modifies a [`Course`] after it was parsed and before it is serialized.
+pub trait CoursePostProcessor: Send + Sync {
+    /// Name used to select the processor on the command line.
+    fn name(&self) -> &'static str;
+
+    fn process(&self, course: &mut Course);
+}
+
+// for ScrapeConfig's Debug, a processor is shown by its name
+impl std::fmt::Debug for dyn CoursePostProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// collapses runs of whitespace PAUL leaves in instructors and units
+pub struct NormalizeWhitespace;
+
+impl CoursePostProcessor for NormalizeWhitespace {
+    fn name(&self) -> &'static str {
+        "normalize-whitespace"
+    }
+
+    fn process(&self, course: &mut Course) {
+        course.instructors = normalize(&course.instructors);
+        course.ou = course.ou.as_deref().map(normalize);
+    }
+}
+
+// fills a missing organisational unit with the top level category (the faculty)
+pub struct OuFromPath;
+
+impl CoursePostProcessor for OuFromPath {
+    fn name(&self) -> &'static str {
+        "ou-from-path"
+    }
+
+    fn process(&self, course: &mut Course) {
+        if course.ou.is_some() {
+            return;
+        }
+        course.ou = course
+            .path
+            .segments
+            .iter()
+            .find(|segment| segment.kind == PathSegmentKind::Category)
+            .map(|segment| segment.title.clone());
+    }
+}
+
+/// All post processors compiled into the crate.
+pub fn builtin_post_processors() -> Vec<Box<dyn CoursePostProcessor>> {
+    vec![Box::new(NormalizeWhitespace), Box::new(OuFromPath)]
+}
+
+/// The post processors that can be picked by name, the builtin ones and
+/// those a downstream crate registers.
+pub struct PostProcessorRegistry {
+    // a processor per call, the same name can be picked twice
+    factories: Vec<fn() -> Box<dyn CoursePostProcessor>>,
+}
+
+impl Default for PostProcessorRegistry {
+    fn default() -> Self {
+        Self {
+            factories: vec![|| Box::new(NormalizeWhitespace), || Box::new(OuFromPath)],
+        }
+    }
+}
+
+impl PostProcessorRegistry {
+    /// Makes the processor built by `factory` selectable by its name, checked
+    /// before the ones registered earlier and the builtin ones.
+    pub fn register(&mut self, factory: fn() -> Box<dyn CoursePostProcessor>) {
+        self.factories.insert(0, factory);
+    }
+
+    /// Picks the post processors with the given names, in the given order.
+    pub fn by_name(&self, names: &[String]) -> Result<Vec<Box<dyn CoursePostProcessor>>, String> {
+        names
+            .iter()
+            .map(|name| {
+                self.factories
+                    .iter()
+                    .map(|factory| factory())
+                    .find(|processor| processor.name() == name)
+                    .ok_or_else(|| format!("Unknown plugin {}", name))
+            })
+            .collect()
+    }
+}
+
+/// Picks the builtin post processors with the given names, in the given order.
+pub fn post_processors_by_name(
+    names: &[String],
+) -> Result<Vec<Box<dyn CoursePostProcessor>>, String> {
+    PostProcessorRegistry::default().by_name(names)
+}
+
+pub fn apply_post_processors(processors: &[Box<dyn CoursePostProcessor>], course: &mut Course) {
+    for processor in processors {
+        processor.process(course);
+    }
+}
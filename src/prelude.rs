@@ -0,0 +1,34 @@
+// the types and functions most users of the library need, `use
+// paul_scrape_rs::prelude::*` keeps working when they move between modules.
+// everything reachable from here follows semver, the rest of the crate may
+// change with internal refactors
+
+pub use crate::{
+    error::Error,
+    links::{CourseUrl, ModuleUrl, SmallGroupUrl, TreeUrl},
+    modules::{parse_module_page, parse_module_page_with, Module},
+    parse_course_page, parse_small_group, parse_tree_page,
+    pauline::{PaulineAppointment, PaulineCourse, PaulineSmallGroup, Semester},
+    postprocess::{CoursePostProcessor, PostProcessorRegistry},
+    sink::{open_sink, Sink},
+    table::extract_table_by_caption,
+    Appointment, AppointmentsTable, Course, CoursePage, FailedPage, Path, SmallGroup,
+    StateSerializable, Warning,
+};
+
+#[cfg(feature = "blocking")]
+pub use crate::crawl::scrape_semester_blocking;
+// the whole crawl api, with what its config and callbacks are made of
+#[cfg(feature = "scrape")]
+pub use crate::{
+    config::{HttpConfig, Politeness, TlsConfig},
+    crawl::{
+        scrape_courses, scrape_semester, scrape_semester_cancellable, scrape_semester_into,
+        scrape_semester_with, CancellationToken, ScrapeConfig,
+    },
+    links::LinkClassifier,
+    pacing::Pacer,
+    progress::{FacultyCounts, NoProgress, ProgressKind, ProgressSink},
+    sink::{AsyncSink, Item},
+    AppointmentColumns,
+};
@@ -0,0 +1,263 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::Path;
+
+// how a crawl reports its progress, so embedders aren't tied to terminal bars.
+// the methods take &self since they are called from many tasks at once
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressKind {
+    Tree,
+    Leaf,
+}
+
+pub trait ProgressSink: Send + Sync {
+    // a page was queued, message says which
+    fn queued(&self, kind: ProgressKind, message: &str);
+    // a queued page is being fetched
+    fn started(&self, kind: ProgressKind);
+    // the counts of a top level category changed
+    fn faculty(&self, _faculty: &str, _counts: &FacultyCounts) {}
+    fn finish(&self) {}
+}
+
+// pages and courses below one top level category, a faculty whose subtree
+// silently failed shows up with few pages or no courses
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FacultyCounts {
+    pub queued: usize,
+    pub started: usize,
+    pub failed: usize,
+    pub courses: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FacultyEvent {
+    Queued,
+    Started,
+    Failed,
+    Course,
+}
+
+impl FacultyCounts {
+    pub fn record(&mut self, event: FacultyEvent) {
+        match event {
+            FacultyEvent::Queued => self.queued += 1,
+            FacultyEvent::Started => self.started += 1,
+            FacultyEvent::Failed => self.failed += 1,
+            FacultyEvent::Course => self.courses += 1,
+        }
+    }
+}
+
+// the counts of every faculty of a crawl, passed on to the progress sink
+#[derive(Default)]
+pub struct FacultyTally(Mutex<BTreeMap<String, FacultyCounts>>);
+
+impl FacultyTally {
+    pub fn record(&self, path: &Path, event: FacultyEvent, progress: &dyn ProgressSink) {
+        // the semester page itself belongs to no faculty
+        let Some(faculty) = path.faculty() else {
+            return;
+        };
+        let mut faculties = self.0.lock().unwrap();
+        let counts = faculties.entry(faculty.to_string()).or_default();
+        counts.record(event);
+        progress.faculty(faculty, counts);
+    }
+
+    pub fn counts(&self) -> BTreeMap<String, FacultyCounts> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn queued(&self, _: ProgressKind, _: &str) {}
+    fn started(&self, _: ProgressKind) {}
+}
+
+// the tree and leaf bars of the command line crawler, and a bar per faculty
+pub struct BarProgress {
+    bars: MultiProgress,
+    tree_bar: ProgressBar,
+    leaf_bar: ProgressBar,
+    faculty_bars: Mutex<BTreeMap<String, ProgressBar>>,
+}
+
+impl BarProgress {
+    pub fn new() -> Self {
+        let bars = MultiProgress::new();
+        let bar = |prefix: &'static str| {
+            let bar = bars.add(ProgressBar::new(0));
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template(
+                        "{prefix:.bold.dim} {bar} {pos:>7}/{len:7} ({elapsed}:{eta}) {wide_msg}",
+                    )
+                    .unwrap(),
+            );
+            bar.set_prefix(prefix);
+            bar
+        };
+        let tree_bar = bar("Tree: ");
+        let leaf_bar = bar("Leaf: ");
+        Self {
+            bars,
+            tree_bar,
+            leaf_bar,
+            faculty_bars: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn bar(&self, kind: ProgressKind) -> &ProgressBar {
+        match kind {
+            ProgressKind::Tree => &self.tree_bar,
+            ProgressKind::Leaf => &self.leaf_bar,
+        }
+    }
+}
+
+impl Default for BarProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressSink for BarProgress {
+    fn queued(&self, kind: ProgressKind, message: &str) {
+        let bar = self.bar(kind);
+        bar.inc_length(1);
+        bar.set_message(message.to_string());
+        bar.tick();
+    }
+
+    fn started(&self, kind: ProgressKind) {
+        self.bar(kind).inc(1);
+    }
+
+    fn faculty(&self, faculty: &str, counts: &FacultyCounts) {
+        let mut faculty_bars = self.faculty_bars.lock().unwrap();
+        let bar = faculty_bars.entry(faculty.to_string()).or_insert_with(|| {
+            let bar = self.bars.add(ProgressBar::new(0));
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template("  {prefix:30!} {bar:20} {pos:>6}/{len:6} {wide_msg}")
+                    .unwrap(),
+            );
+            bar.set_prefix(faculty.to_string());
+            bar
+        });
+        bar.set_length(counts.queued as u64);
+        bar.set_position(counts.started as u64);
+        bar.set_message(format!(
+            "{} courses, {} failed",
+            counts.courses, counts.failed
+        ));
+    }
+
+    fn finish(&self) {
+        self.tree_bar.finish();
+        self.leaf_bar.finish();
+        for bar in self.faculty_bars.lock().unwrap().values() {
+            bar.finish();
+        }
+    }
+}
+
+// a status line on stderr every `every` started pages, for logs and ci
+pub struct LogProgress {
+    every: usize,
+    // queued and started, tree and leaf
+    counts: Mutex<[[usize; 2]; 2]>,
+}
+
+impl LogProgress {
+    pub fn new(every: usize) -> Self {
+        Self {
+            every: every.max(1),
+            counts: Mutex::new([[0; 2]; 2]),
+        }
+    }
+
+    fn log(&self, counts: [[usize; 2]; 2]) {
+        let [[tree_queued, tree_started], [leaf_queued, leaf_started]] = counts;
+        eprintln!(
+            "[{}] tree {}/{} leaf {}/{}",
+            chrono::Utc::now(),
+            tree_started,
+            tree_queued,
+            leaf_started,
+            leaf_queued
+        );
+    }
+}
+
+impl ProgressSink for LogProgress {
+    fn queued(&self, kind: ProgressKind, _: &str) {
+        self.counts.lock().unwrap()[kind as usize][0] += 1;
+    }
+
+    fn started(&self, kind: ProgressKind) {
+        let counts = {
+            let mut counts = self.counts.lock().unwrap();
+            counts[kind as usize][1] += 1;
+            *counts
+        };
+        if (counts[0][1] + counts[1][1]).is_multiple_of(self.every) {
+            self.log(counts);
+        }
+    }
+
+    fn finish(&self) {
+        self.log(*self.counts.lock().unwrap());
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    Queued {
+        kind: ProgressKind,
+        message: String,
+    },
+    Started {
+        kind: ProgressKind,
+    },
+    Faculty {
+        faculty: String,
+        counts: FacultyCounts,
+    },
+    Finished,
+}
+
+// forwards every update, e.g. to a gui thread. a dropped receiver is ignored
+pub struct ChannelProgress(pub UnboundedSender<ProgressEvent>);
+
+impl ProgressSink for ChannelProgress {
+    fn queued(&self, kind: ProgressKind, message: &str) {
+        let _ = self.0.send(ProgressEvent::Queued {
+            kind,
+            message: message.to_string(),
+        });
+    }
+
+    fn started(&self, kind: ProgressKind) {
+        let _ = self.0.send(ProgressEvent::Started { kind });
+    }
+
+    fn faculty(&self, faculty: &str, counts: &FacultyCounts) {
+        let _ = self.0.send(ProgressEvent::Faculty {
+            faculty: faculty.to_string(),
+            counts: counts.clone(),
+        });
+    }
+
+    fn finish(&self) {
+        let _ = self.0.send(ProgressEvent::Finished);
+    }
+}
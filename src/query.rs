@@ -0,0 +1,444 @@
+use std::fmt;
+
+use crate::pauline::Semester;
+
+// a tiny sql subset over semester.json:
+// select <columns|*> from <table> [where <condition>] [limit <n>]
+// conditions compare columns with string literals using = != < <= > >= like,
+// combined with and/or (and binds stronger), like supports % and _ and
+// ignores case like sqlite's
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(String),
+    Op(String),
+    Comma,
+    Star,
+}
+
+#[derive(Debug)]
+pub struct QueryError(String);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+fn error<T>(message: impl Into<String>) -> Result<T, QueryError> {
+    Err(QueryError(message.into()))
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == ',' {
+            chars.next();
+            tokens.push(Token::Comma);
+        } else if c == '*' {
+            chars.next();
+            tokens.push(Token::Star);
+        } else if c == '\'' {
+            chars.next();
+            let mut literal = String::new();
+            loop {
+                match chars.next() {
+                    // '' is an escaped quote
+                    Some('\'') if chars.peek() == Some(&'\'') => {
+                        chars.next();
+                        literal.push('\'');
+                    }
+                    Some('\'') => break,
+                    Some(c) => literal.push(c),
+                    None => return error("unterminated string literal"),
+                }
+            }
+            tokens.push(Token::Str(literal));
+        } else if "=!<>".contains(c) {
+            chars.next();
+            let mut op = c.to_string();
+            if let Some(&next) = chars.peek() {
+                if next == '=' || (c == '<' && next == '>') {
+                    chars.next();
+                    op.push(next);
+                }
+            }
+            if op == "!" {
+                return error("expected !=");
+            }
+            tokens.push(Token::Op(if op == "<>" { "!=".to_string() } else { op }));
+        } else if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_ascii_digit() && c != '.' {
+                    break;
+                }
+                number.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Number(number));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_alphanumeric() && c != '_' {
+                    break;
+                }
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else {
+            return error(format!("unexpected character {}", c));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Condition {
+    Compare {
+        column: String,
+        op: String,
+        value: String,
+    },
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+#[derive(Debug)]
+pub struct Query {
+    columns: Option<Vec<String>>,
+    table: String,
+    condition: Option<Condition>,
+    limit: Option<usize>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if !self.is_keyword(keyword) {
+            return error(format!("expected {}", keyword));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String, QueryError> {
+        match self.next() {
+            Some(Token::Ident(ident)) => Ok(ident.to_lowercase()),
+            token => error(format!("expected a name, got {:?}", token)),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, QueryError> {
+        self.expect_keyword("select")?;
+        let columns = if self.peek() == Some(&Token::Star) {
+            self.next();
+            None
+        } else {
+            let mut columns = vec![self.ident()?];
+            while self.peek() == Some(&Token::Comma) {
+                self.next();
+                columns.push(self.ident()?);
+            }
+            Some(columns)
+        };
+        self.expect_keyword("from")?;
+        let table = self.ident()?;
+        let condition = if self.is_keyword("where") {
+            self.next();
+            Some(self.or()?)
+        } else {
+            None
+        };
+        let limit = if self.is_keyword("limit") {
+            self.next();
+            match self.next() {
+                Some(Token::Number(number)) => Some(
+                    number
+                        .parse()
+                        .or_else(|_| error(format!("invalid limit {}", number)))?,
+                ),
+                token => return error(format!("expected a number, got {:?}", token)),
+            }
+        } else {
+            None
+        };
+        if let Some(token) = self.peek() {
+            return error(format!("unexpected {:?}", token));
+        }
+        Ok(Query {
+            columns,
+            table,
+            condition,
+            limit,
+        })
+    }
+
+    fn or(&mut self) -> Result<Condition, QueryError> {
+        let mut condition = self.and()?;
+        while self.is_keyword("or") {
+            self.next();
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition, QueryError> {
+        let mut condition = self.compare()?;
+        while self.is_keyword("and") {
+            self.next();
+            condition = Condition::And(Box::new(condition), Box::new(self.compare()?));
+        }
+        Ok(condition)
+    }
+
+    fn compare(&mut self) -> Result<Condition, QueryError> {
+        let column = self.ident()?;
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("like") => "like".to_string(),
+            token => return error(format!("expected an operator, got {:?}", token)),
+        };
+        let value = match self.next() {
+            Some(Token::Str(value)) | Some(Token::Number(value)) => value,
+            token => return error(format!("expected a value, got {:?}", token)),
+        };
+        Ok(Condition::Compare { column, op, value })
+    }
+}
+
+pub fn parse_query(query: &str) -> Result<Query, QueryError> {
+    Parser {
+        tokens: tokenize(query)?,
+        position: 0,
+    }
+    .query()
+}
+
+// the usual wildcard match: on a mismatch go back to the last % and let it
+// take one more character, linear in the value for every pattern
+fn like(value: &str, pattern: &str) -> bool {
+    let value = value
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let pattern = pattern
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let (mut v, mut p) = (0, 0);
+    // the position of the last % and the value position it matched up to
+    let mut percent = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                percent = Some((p, v));
+                p += 1;
+            }
+            Some(&c) if c == '_' || c == value[v] => {
+                v += 1;
+                p += 1;
+            }
+            _ => match percent {
+                Some((percent_p, percent_v)) => {
+                    percent = Some((percent_p, percent_v + 1));
+                    p = percent_p + 1;
+                    v = percent_v + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
+impl Condition {
+    fn matches(&self, columns: &[&str], row: &[String]) -> Result<bool, QueryError> {
+        match self {
+            Condition::And(a, b) => Ok(a.matches(columns, row)? && b.matches(columns, row)?),
+            Condition::Or(a, b) => Ok(a.matches(columns, row)? || b.matches(columns, row)?),
+            Condition::Compare { column, op, value } => {
+                let index = columns
+                    .iter()
+                    .position(|c| c == column)
+                    .map_or_else(|| error(format!("unknown column {}", column)), Ok)?;
+                let field = row[index].as_str();
+                // compare numerically when both sides are numbers
+                let ordering = match (field.parse::<f64>(), value.parse::<f64>()) {
+                    (Ok(a), Ok(b)) => a.partial_cmp(&b),
+                    _ => Some(field.cmp(value.as_str())),
+                };
+                Ok(match op.as_str() {
+                    "=" => field == value,
+                    "!=" => field != value,
+                    "<" => ordering == Some(std::cmp::Ordering::Less),
+                    "<=" => ordering != Some(std::cmp::Ordering::Greater),
+                    ">" => ordering == Some(std::cmp::Ordering::Greater),
+                    ">=" => ordering != Some(std::cmp::Ordering::Less),
+                    "like" => like(field, value),
+                    _ => return error(format!("unknown operator {}", op)),
+                })
+            }
+        }
+    }
+}
+
+fn table(
+    semester: &Semester,
+    name: &str,
+) -> Result<(&'static [&'static str], Vec<Vec<String>>), QueryError> {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    match name {
+        "courses" => Ok((
+            &[
+                "cid",
+                "name",
+                "description",
+                "ou",
+                "instructors",
+                "small_groups",
+                "appointments",
+            ],
+            semester
+                .courses
+                .iter()
+                .map(|course| {
+                    vec![
+                        course.cid.clone(),
+                        course.name.clone(),
+                        optional(&course.description),
+                        optional(&course.ou),
+                        optional(&course.instructors),
+                        course.small_groups.len().to_string(),
+                        course.appointments.len().to_string(),
+                    ]
+                })
+                .collect(),
+        )),
+        "small_groups" => Ok((
+            &["cid", "name", "small_group", "appointments"],
+            semester
+                .courses
+                .iter()
+                .flat_map(|course| {
+                    course.small_groups.iter().map(|small_group| {
+                        vec![
+                            course.cid.clone(),
+                            course.name.clone(),
+                            small_group.name.clone(),
+                            small_group.appointments.len().to_string(),
+                        ]
+                    })
+                })
+                .collect(),
+        )),
+        "appointments" => Ok((
+            &[
+                "cid",
+                "name",
+                "small_group",
+                "start_time",
+                "end_time",
+                "room",
+                "instructors",
+            ],
+            semester
+                .courses
+                .iter()
+                .flat_map(|course| {
+                    // course appointments have an empty small_group
+                    let course_appointments = course
+                        .appointments
+                        .iter()
+                        .map(|appointment| ("", appointment));
+                    let small_group_appointments =
+                        course.small_groups.iter().flat_map(|small_group| {
+                            small_group
+                                .appointments
+                                .iter()
+                                .map(|appointment| (small_group.name.as_str(), appointment))
+                        });
+                    course_appointments.chain(small_group_appointments).map(
+                        |(small_group, appointment)| {
+                            vec![
+                                course.cid.clone(),
+                                course.name.clone(),
+                                small_group.to_string(),
+                                appointment.start_time.clone(),
+                                appointment.end_time.clone(),
+                                appointment.room.clone(),
+                                appointment.instructors.clone(),
+                            ]
+                        },
+                    )
+                })
+                .collect(),
+        )),
+        _ => error(format!(
+            "unknown table {}, expected courses, small_groups or appointments",
+            name
+        )),
+    }
+}
+
+/// Runs a query, returning the selected column names and the matching rows.
+pub fn run_query(
+    semester: &Semester,
+    query: &Query,
+) -> Result<(Vec<String>, Vec<Vec<String>>), QueryError> {
+    let (columns, rows) = table(semester, &query.table)?;
+    let selected = match &query.columns {
+        Some(selected) => selected
+            .iter()
+            .map(|column| {
+                columns
+                    .iter()
+                    .position(|c| c == column)
+                    .map_or_else(|| error(format!("unknown column {}", column)), Ok)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => (0..columns.len()).collect(),
+    };
+    let mut result = Vec::new();
+    for row in rows {
+        if query.limit.is_some_and(|limit| result.len() >= limit) {
+            break;
+        }
+        if let Some(condition) = &query.condition {
+            if !condition.matches(columns, &row)? {
+                continue;
+            }
+        }
+        result.push(selected.iter().map(|&i| row[i].clone()).collect());
+    }
+    Ok((
+        selected.iter().map(|&i| columns[i].to_string()).collect(),
+        result,
+    ))
+}
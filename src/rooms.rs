@@ -0,0 +1,95 @@
+use regex::Regex;
+use serde::Deserialize;
+
+// rooms are typed by hand in PAUL, "O2.267", "O 2.267" and "O2 267" are the same room.
+// known names and aliases come from a dictionary, the Paderborn one is bundled and
+// other campuses can add their own. building/room numbers are recognized by pattern
+
+const BUNDLED: &str = include_str!("rooms.json");
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RoomEntry {
+    pub id: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+pub struct RoomNormalizer {
+    // normalized key and canonical id
+    keys: Vec<(String, String)>,
+    room_number: Regex,
+}
+
+fn key(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl RoomNormalizer {
+    pub fn new(entries: Vec<RoomEntry>) -> Self {
+        let keys = entries
+            .into_iter()
+            .flat_map(|entry| {
+                let id = entry.id.clone();
+                std::iter::once(entry.id)
+                    .chain(entry.aliases)
+                    .map(move |name| (key(&name), id.clone()))
+            })
+            .collect();
+        Self {
+            keys,
+            // building letter, optional building number, three digit room
+            room_number: Regex::new(r"^([A-Za-z])\s*([0-9])?\s*[.\s-]?\s*([0-9]{3})$").unwrap(),
+        }
+    }
+
+    /// The bundled Paderborn dictionary.
+    pub fn bundled() -> Self {
+        Self::new(serde_json::from_str(BUNDLED).unwrap())
+    }
+
+    /// The bundled dictionary plus `entries`, which win on conflicts.
+    pub fn extended(entries: Vec<RoomEntry>) -> Self {
+        let mut all = entries;
+        all.extend(serde_json::from_str::<Vec<RoomEntry>>(BUNDLED).unwrap());
+        Self::new(all)
+    }
+
+    /// Returns the canonical id of a room as written in PAUL, if it can be recognized.
+    pub fn canonical(&self, raw: &str) -> Option<String> {
+        // "O2.267 (Hörsaal)", the remark isn't part of the name
+        let text = raw.split(" (").next().unwrap_or_default().trim();
+        let text_key = key(text);
+        if text_key.is_empty() {
+            return None;
+        }
+        if let Some((_, id)) = self.keys.iter().find(|(key, _)| *key == text_key) {
+            return Some(id.clone());
+        }
+        if let Some(captures) = self.room_number.captures(text) {
+            return Some(format!(
+                "{}{}.{}",
+                captures[1].to_uppercase(),
+                captures.get(2).map_or("", |number| number.as_str()),
+                &captures[3]
+            ));
+        }
+        // one typo, but not between short names like H1 and H2 and never in the digits,
+        // "Hörsaal H7" is not H1. a typo that fits several rooms matches none of them
+        let text_digits = digits(&text_key);
+        let mut candidates = self
+            .keys
+            .iter()
+            .filter(|(key, _)| key.chars().count() >= 4 && digits(key) == text_digits)
+            .filter(|(key, _)| strsim::levenshtein(key, &text_key) <= 1)
+            .map(|(_, id)| id);
+        let id = candidates.next()?;
+        candidates.all(|other| other == id).then(|| id.clone())
+    }
+}
+
+fn digits(key: &str) -> String {
+    key.chars().filter(char::is_ascii_digit).collect()
+}
@@ -0,0 +1,68 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::{Course, StateSerializable};
+
+// free text search over scraped courses for `paul-scrape-rs find`
+
+/// Lowercases and strips diacritics, "Einführung" and "einfuhrung" fold the same.
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .replace('ß', "ss")
+}
+
+pub struct Hit<'a> {
+    pub semester: &'a str,
+    pub course: &'a Course,
+    // 1.0 for substring matches, the similarity of the best window for fuzzy ones
+    pub score: f64,
+}
+
+// fuzzy matches need at least this similarity
+const FUZZY_THRESHOLD: f64 = 0.8;
+
+fn fuzzy_score(query: &str, text: &str) -> f64 {
+    // compare the query with every run of as many words in the text
+    let query_words = query.split_whitespace().count().max(1);
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    words
+        .windows(query_words.min(words.len()).max(1))
+        .map(|window| strsim::normalized_damerau_levenshtein(query, &window.join(" ")))
+        .fold(0.0, f64::max)
+}
+
+/// Courses whose name, instructors or path contain `query`, or come close to it with `fuzzy`.
+///
+/// Best matches first, ties in the order of the states.
+pub fn find_courses<'a>(states: &'a [StateSerializable], query: &str, fuzzy: bool) -> Vec<Hit<'a>> {
+    let query = fold(query.trim());
+    let mut hits = Vec::new();
+    for state in states {
+        for course in &state.courses {
+            // the path ends with number and name of the course
+            let text = fold(&format!(
+                "{} {}",
+                course.path.fragments.join(" "),
+                course.instructors
+            ));
+            let score = if text.contains(&query) {
+                1.0
+            } else if fuzzy {
+                fuzzy_score(&query, &text)
+            } else {
+                0.0
+            };
+            if score >= FUZZY_THRESHOLD {
+                hits.push(Hit {
+                    semester: &state.semester,
+                    course,
+                    score,
+                });
+            }
+        }
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits
+}
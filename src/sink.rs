@@ -0,0 +1,361 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    error::Error,
+    snapshot::{write_snapshot, Format},
+    Course, FailedPage, SmallGroup, StateSerializable,
+};
+
+// where a finished crawl goes, one run can write to several sinks:
+//   state.json or json:state.json  the usual state file
+//   msgpack:state.msgpack          the same as zstd compressed messagepack, needs the msgpack feature
+//   ndjson:stream.ndjson           one line per course, small group and failed page
+//   sqlite:paul.db                 one table each, needs the sqlite feature
+
+// writing a finished semester at once needs it all in memory, see AsyncSink
+// for sinks written to while crawling
+pub trait Sink {
+    // called once per scraped semester
+    fn write(&mut self, state: &StateSerializable) -> Result<(), Error>;
+    // called after the last semester
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+    // the files written so far
+    fn files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Opens the sink described by `spec`, relative paths are resolved against `dir`.
+pub fn open_sink(spec: &str, dir: Option<&str>) -> Result<Box<dyn Sink>, Error> {
+    let (kind, path) = match spec.split_once(':') {
+        Some((kind @ ("json" | "msgpack" | "ndjson" | "sqlite"), path)) => (kind, path),
+        _ => ("json", spec),
+    };
+    let path = match dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).map_err(|e| output_error(Path::new(dir), e))?;
+            Path::new(dir).join(path)
+        }
+        None => PathBuf::from(path),
+    };
+    match kind {
+        "ndjson" => Ok(Box::new(NdjsonSink::create(path)?)),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Box::new(SqliteSink::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => Err(Error::Output(format!(
+            "{}: built without the sqlite feature",
+            path.display()
+        ))),
+        #[cfg(feature = "msgpack")]
+        "msgpack" => Ok(Box::new(JsonSink::with_format(path, Format::Msgpack))),
+        #[cfg(not(feature = "msgpack"))]
+        "msgpack" => Err(Error::Output(format!(
+            "{}: built without the msgpack feature",
+            path.display()
+        ))),
+        _ => Ok(Box::new(JsonSink::new(path))),
+    }
+}
+
+fn output_error(path: &Path, e: impl std::fmt::Display) -> Error {
+    Error::Output(format!("{}: {}", path.display(), e))
+}
+
+/// Turns a semester or organisational unit into a file name part,
+/// "Winter 2023/24" => Winter-2023-24.
+pub fn slug(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// the state file, with several semesters state.json becomes
+// state.Sommer-2023.json, state.Winter-2023-24.json, ...
+pub struct JsonSink {
+    path: PathBuf,
+    format: Format,
+    states: Vec<StateSerializable>,
+    written: Vec<PathBuf>,
+}
+
+impl JsonSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self::with_format(path, Format::Json)
+    }
+
+    pub fn with_format(path: impl Into<PathBuf>, format: Format) -> Self {
+        Self {
+            path: path.into(),
+            format,
+            states: Vec::new(),
+            written: Vec::new(),
+        }
+    }
+}
+
+impl Sink for JsonSink {
+    fn write(&mut self, state: &StateSerializable) -> Result<(), Error> {
+        // the file names depend on how many semesters there are
+        self.states.push(state.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let single = self.states.len() == 1;
+        for state in self.states.drain(..) {
+            let path = if single {
+                self.path.clone()
+            } else {
+                let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+                let extension = self.path.extension().unwrap_or_default().to_string_lossy();
+                self.path.with_file_name(format!(
+                    "{}.{}.{}",
+                    stem,
+                    slug(&state.semester),
+                    extension
+                ))
+            };
+            write_snapshot(&path, &state, self.format)?;
+            self.written.push(path);
+        }
+        Ok(())
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        self.written.clone()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    Course {
+        semester: &'a str,
+        #[serde(flatten)]
+        course: &'a Course,
+    },
+    SmallGroup {
+        semester: &'a str,
+        #[serde(flatten)]
+        small_group: &'a SmallGroup,
+    },
+    FailedPage {
+        semester: &'a str,
+        #[serde(flatten)]
+        failed_page: &'a FailedPage,
+    },
+}
+
+// one parsed page, as handed to an AsyncSink
+#[derive(Clone)]
+pub enum Item {
+    // boxed, a course is several times the size of the others
+    Course(Box<Course>),
+    SmallGroup(SmallGroup),
+    FailedPage(FailedPage),
+}
+
+#[cfg(feature = "scrape")]
+impl Item {
+    fn record<'a>(&'a self, semester: &'a str) -> Record<'a> {
+        match self {
+            Item::Course(course) => Record::Course { semester, course },
+            Item::SmallGroup(small_group) => Record::SmallGroup {
+                semester,
+                small_group,
+            },
+            Item::FailedPage(failed_page) => Record::FailedPage {
+                semester,
+                failed_page,
+            },
+        }
+    }
+}
+
+// written to while crawling, see crawl::scrape_semester_into. the crawler
+// waits for every write, so a slow sink (a database over a weak link) slows
+// the crawl down instead of pages piling up in memory
+#[cfg(feature = "scrape")]
+#[async_trait::async_trait]
+pub trait AsyncSink: Send {
+    async fn write(&mut self, semester: &str, item: Item) -> Result<(), Error>;
+    // called once the crawl is done
+    async fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+// collects everything, scrape_semester returns the state built this way
+#[cfg(feature = "scrape")]
+#[async_trait::async_trait]
+impl AsyncSink for StateSerializable {
+    async fn write(&mut self, _semester: &str, item: Item) -> Result<(), Error> {
+        match item {
+            Item::Course(course) => self.courses.push(*course),
+            Item::SmallGroup(small_group) => self.small_groups.push(small_group),
+            Item::FailedPage(failed_page) => self.failed_pages.push(failed_page),
+        }
+        Ok(())
+    }
+}
+
+pub struct NdjsonSink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl NdjsonSink {
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let file = File::create(&path).map_err(|e| output_error(&path, e))?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+        })
+    }
+
+    fn record(&mut self, record: Record) -> Result<(), Error> {
+        serde_json::to_writer(&mut self.writer, &record)
+            .map_err(|e| output_error(&self.path, e))?;
+        self.writer
+            .write_all(b"\n")
+            .map_err(|e| output_error(&self.path, e))
+    }
+}
+
+#[cfg(feature = "scrape")]
+#[async_trait::async_trait]
+impl AsyncSink for NdjsonSink {
+    async fn write(&mut self, semester: &str, item: Item) -> Result<(), Error> {
+        // a BufWriter on a local file, blocking for it is fine
+        self.record(item.record(semester))
+    }
+
+    async fn finish(&mut self) -> Result<(), Error> {
+        Sink::finish(self)
+    }
+}
+
+impl Sink for NdjsonSink {
+    fn write(&mut self, state: &StateSerializable) -> Result<(), Error> {
+        let semester = state.semester.as_str();
+        for course in &state.courses {
+            self.record(Record::Course { semester, course })?;
+        }
+        for small_group in &state.small_groups {
+            self.record(Record::SmallGroup {
+                semester,
+                small_group,
+            })?;
+        }
+        for failed_page in &state.failed_pages {
+            self.record(Record::FailedPage {
+                semester,
+                failed_page,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(|e| output_error(&self.path, e))
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+}
+
+// rows are keyed by semester and url, the whole record is kept as json.
+// writing a semester replaces its rows from earlier runs
+#[cfg(feature = "sqlite")]
+pub struct SqliteSink {
+    path: PathBuf,
+    connection: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSink {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let connection = rusqlite::Connection::open(&path).map_err(|e| output_error(&path, e))?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS courses (semester TEXT, url TEXT, json TEXT);
+                CREATE TABLE IF NOT EXISTS small_groups (semester TEXT, url TEXT, json TEXT);
+                CREATE TABLE IF NOT EXISTS failed_pages (semester TEXT, url TEXT, json TEXT);",
+            )
+            .map_err(|e| output_error(&path, e))?;
+        Ok(Self { path, connection })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Sink for SqliteSink {
+    fn write(&mut self, state: &StateSerializable) -> Result<(), Error> {
+        let path = self.path.clone();
+        let error = |e: rusqlite::Error| output_error(&path, e);
+        let transaction = self.connection.transaction().map_err(error)?;
+        let tables = [
+            (
+                "courses",
+                state
+                    .courses
+                    .iter()
+                    .map(|course| (&course.url, serde_json::to_string(course)))
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                "small_groups",
+                state
+                    .small_groups
+                    .iter()
+                    .map(|small_group| (&small_group.url, serde_json::to_string(small_group)))
+                    .collect(),
+            ),
+            (
+                "failed_pages",
+                state
+                    .failed_pages
+                    .iter()
+                    .map(|failed_page| (&failed_page.url, serde_json::to_string(failed_page)))
+                    .collect(),
+            ),
+        ];
+        for (table, rows) in tables {
+            transaction
+                .execute(
+                    &format!("DELETE FROM {} WHERE semester = ?1", table),
+                    [&state.semester],
+                )
+                .map_err(error)?;
+            let mut insert = transaction
+                .prepare(&format!(
+                    "INSERT INTO {} (semester, url, json) VALUES (?1, ?2, ?3)",
+                    table
+                ))
+                .map_err(error)?;
+            for (url, json) in rows {
+                let json = json.map_err(|e| output_error(&path, e))?;
+                insert
+                    .execute([&state.semester, url, &json])
+                    .map_err(error)?;
+            }
+        }
+        transaction.commit().map_err(error)
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+}
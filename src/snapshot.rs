@@ -0,0 +1,78 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read},
+    path::Path,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::Error;
+
+// state files are json, or with the msgpack feature zstd compressed
+// messagepack: a fraction of the size and much quicker to load for the diff
+// and history tools that read old snapshots over and over. the readers tell
+// them apart by the zstd magic number, either can be passed anywhere
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Msgpack,
+}
+
+/// Writes `value` to `path`, pretty printed json or compressed messagepack.
+pub fn write_snapshot<T: Serialize>(path: &Path, value: &T, format: Format) -> Result<(), Error> {
+    let output_error =
+        |e: &dyn std::fmt::Display| Error::Output(format!("{}: {}", path.display(), e));
+    match format {
+        Format::Json => {
+            let file = File::create(path).map_err(|e| output_error(&e))?;
+            serde_json::to_writer_pretty(BufWriter::new(file), value).map_err(|e| output_error(&e))
+        }
+        #[cfg(feature = "msgpack")]
+        Format::Msgpack => {
+            let file = File::create(path).map_err(|e| output_error(&e))?;
+            let mut encoder = zstd::Encoder::new(file, 0).map_err(|e| output_error(&e))?;
+            // with the field names, the default leaves out fields and has
+            // skipped ones shift the rest
+            rmp_serde::encode::write_named(&mut encoder, value).map_err(|e| output_error(&e))?;
+            encoder.finish().map_err(|e| output_error(&e))?;
+            Ok(())
+        }
+        #[cfg(not(feature = "msgpack"))]
+        Format::Msgpack => Err(output_error(&"built without the msgpack feature")),
+    }
+}
+
+/// The format of a file written by `write_snapshot`, told by its first bytes.
+pub fn snapshot_format(path: impl AsRef<Path>) -> Result<Format, Error> {
+    let path = path.as_ref();
+    let mut magic = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(ZSTD_MAGIC.len() as u64).read_to_end(&mut magic))
+        .map_err(|e| Error::Input(format!("{}: {}", path.display(), e)))?;
+    if magic == ZSTD_MAGIC {
+        Ok(Format::Msgpack)
+    } else {
+        Ok(Format::Json)
+    }
+}
+
+/// Reads a file written by `write_snapshot` in either format.
+pub fn read_snapshot<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, Error> {
+    let path = path.as_ref();
+    let input_error =
+        |e: &dyn std::fmt::Display| Error::Input(format!("{}: {}", path.display(), e));
+    let bytes = std::fs::read(path).map_err(|e| input_error(&e))?;
+    if bytes.starts_with(&ZSTD_MAGIC) {
+        #[cfg(feature = "msgpack")]
+        {
+            let decoder = zstd::Decoder::new(&bytes[..]).map_err(|e| input_error(&e))?;
+            return rmp_serde::from_read(decoder).map_err(|e| input_error(&e));
+        }
+        #[cfg(not(feature = "msgpack"))]
+        return Err(input_error(&"msgpack, built without the msgpack feature"));
+    }
+    serde_json::from_slice(&bytes).map_err(|e| input_error(&e))
+}
@@ -0,0 +1,66 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom, Write},
+    marker::PhantomData,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// overflow of a bounded in-memory queue, one json line per entry in an anonymous
+// temporary file. entries come back in the order they were spilled and the file
+// is truncated whenever it runs empty, so it only grows with the backlog
+
+pub struct Spill<T> {
+    file: File,
+    // where the next entry to read starts
+    read_offset: u64,
+    len: usize,
+    entry: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Spill<T> {
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            file: tempfile::tempfile()?,
+            read_offset: 0,
+            len: 0,
+            entry: PhantomData,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, entry: &T) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.write_all(&line)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Reads back up to `count` entries, oldest first.
+    pub fn pop_many(&mut self, count: usize) -> std::io::Result<Vec<T>> {
+        self.file.seek(SeekFrom::Start(self.read_offset))?;
+        let mut reader = BufReader::new(&self.file);
+        let mut entries = Vec::new();
+        let mut line = String::new();
+        while entries.len() < count.min(self.len) {
+            line.clear();
+            self.read_offset += reader.read_line(&mut line)? as u64;
+            entries.push(serde_json::from_str(&line)?);
+        }
+        self.len -= entries.len();
+        if self.len == 0 {
+            self.file.set_len(0)?;
+            self.read_offset = 0;
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{error::ErrorKind, FailedPage};
+
+// the outcome of a run for monitoring, written to status.json. a redesign of
+// PAUL tends to break a part of the pages quietly, the thresholds turn a run
+// with too many failures of one kind into a degraded one
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Ok,
+    Degraded,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Exceeded {
+    pub kind: ErrorKind,
+    // percent of all pages
+    pub rate: f64,
+    pub threshold: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunStatus {
+    pub status: Outcome,
+    pub pages: usize,
+    pub failures: BTreeMap<ErrorKind, usize>,
+    pub exceeded: Vec<Exceeded>,
+}
+
+/// Counts the failed pages by kind and checks them against `thresholds`,
+/// the highest allowed percentage of `pages` per kind.
+pub fn run_status(
+    pages: usize,
+    failed_pages: &[FailedPage],
+    thresholds: &[(ErrorKind, f64)],
+) -> RunStatus {
+    let mut failures = BTreeMap::new();
+    for failed_page in failed_pages {
+        // state files from before kinds were recorded
+        let kind = failed_page.kind.unwrap_or(ErrorKind::Other);
+        *failures.entry(kind).or_default() += 1;
+    }
+    let exceeded = thresholds
+        .iter()
+        .filter_map(|&(kind, threshold)| {
+            let count = failures.get(&kind).copied().unwrap_or_default();
+            let rate = 100.0 * count as f64 / pages.max(1) as f64;
+            (rate > threshold).then_some(Exceeded {
+                kind,
+                rate,
+                threshold,
+            })
+        })
+        .collect::<Vec<_>>();
+    RunStatus {
+        status: if exceeded.is_empty() {
+            Outcome::Ok
+        } else {
+            Outcome::Degraded
+        },
+        pages,
+        failures,
+        exceeded,
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::normalize_text;
+
+// PAUL's data tables: a <caption> like "Termine", a header row and one row per entry
+
+/// A table found by its caption. The first row is the header, PAUL's tables always have one.
+pub struct Table<'a> {
+    pub header: Vec<String>,
+    element: ElementRef<'a>,
+    rows: Vec<ElementRef<'a>>,
+}
+
+impl<'a> Table<'a> {
+    /// The `<table>` itself.
+    pub fn element(&self) -> ElementRef<'a> {
+        self.element
+    }
+
+    /// The index of the column whose header is `name`, ignoring case and a trailing colon.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.header
+            .iter()
+            .position(|header| header.trim_end_matches(':').eq_ignore_ascii_case(name))
+    }
+
+    /// The data rows with the text of their cells.
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        self.row_elements().map(|(_, cells)| cells).collect()
+    }
+
+    /// The data rows with their elements, for parsers looking at more than the text.
+    pub fn row_elements(&self) -> impl Iterator<Item = (ElementRef<'a>, Vec<String>)> + '_ {
+        self.rows.iter().map(|row| (*row, cells(row)))
+    }
+
+    /// The data rows by header, cells under an empty header are left out.
+    pub fn records(&self) -> Vec<BTreeMap<String, String>> {
+        self.rows()
+            .into_iter()
+            .map(|row| {
+                self.header
+                    .iter()
+                    .zip(row)
+                    .filter(|(header, _)| !header.is_empty())
+                    .map(|(header, cell)| (header.clone(), cell))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// the text of the data cells, header cells in a data row are row labels, not data
+fn cells(row: &ElementRef) -> Vec<String> {
+    row.select(&Selector::parse("td").unwrap())
+        .map(|td| td.text().collect::<Vec<_>>().join(" ").trim().to_string())
+        .collect()
+}
+
+/// Every table below `root` captioned `caption`, ignoring case and a trailing colon.
+pub fn find_tables_by_caption<'a>(root: ElementRef<'a>, caption: &str) -> Vec<Table<'a>> {
+    let caption_selector = Selector::parse("caption").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let row_selector = Selector::parse("tr").unwrap();
+    root.select(&Selector::parse("table").unwrap())
+        .filter(|table| {
+            table.select(&caption_selector).next().is_some_and(|found| {
+                normalize_text(found.text())
+                    .trim_end_matches(':')
+                    .eq_ignore_ascii_case(caption)
+            })
+        })
+        .map(|table| {
+            let mut rows = table.select(&row_selector);
+            let header = rows
+                .next()
+                .map(|row| {
+                    row.select(&cell_selector)
+                        .map(|cell| normalize_text(cell.text()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            Table {
+                header,
+                element: table,
+                rows: rows.collect(),
+            }
+        })
+        .collect()
+}
+
+/// The data rows of every table captioned `caption`, header rows left out.
+pub fn extract_table_by_caption(document: &Html, caption: &str) -> Vec<Vec<String>> {
+    find_tables_by_caption(document.root_element(), caption)
+        .iter()
+        .flat_map(Table::rows)
+        .collect()
+}
@@ -0,0 +1,65 @@
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+// keyword tagging of courses from a rules file like
+// [{"tag": "online", "pattern": "online|digital"},
+//  {"tag": "english", "keywords": ["english", "englischsprachig"]}]
+// patterns and keywords are case insensitive
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TagRule {
+    pub tag: String,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+pub struct TagRules {
+    rules: Vec<(String, Option<Regex>, Vec<String>)>,
+}
+
+impl TagRules {
+    pub fn compile(rules: Vec<TagRule>) -> Result<Self, regex::Error> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let pattern = rule
+                    .pattern
+                    .map(|pattern| RegexBuilder::new(&pattern).case_insensitive(true).build())
+                    .transpose()?;
+                let keywords = rule
+                    .keywords
+                    .iter()
+                    .map(|keyword| keyword.to_lowercase())
+                    .collect();
+                Ok((rule.tag, pattern, keywords))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Returns the tags of every rule matching one of `texts`, sorted and without duplicates.
+    pub fn tags(&self, texts: &[&str]) -> Vec<String> {
+        let lowercase = texts
+            .iter()
+            .map(|text| text.to_lowercase())
+            .collect::<Vec<_>>();
+        let mut tags = self
+            .rules
+            .iter()
+            .filter(|(_, pattern, keywords)| {
+                pattern
+                    .as_ref()
+                    .is_some_and(|pattern| texts.iter().any(|text| pattern.is_match(text)))
+                    || keywords
+                        .iter()
+                        .any(|keyword| lowercase.iter().any(|text| text.contains(keyword.as_str())))
+            })
+            .map(|(tag, _, _)| tag.clone())
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::pauline::{PaulineAppointment, Semester};
+
+// sanity checks of a semester.json before it replaces a published one. a
+// scrape that ran into a maintenance page or a redesign still writes a valid
+// file, just with far fewer courses or dates than there are
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum Problem {
+    NoCourses,
+    // fewer courses than `min_share` percent of the published ones
+    CoursesDropped {
+        published: usize,
+        fresh: usize,
+    },
+    // the share of courses with any appointment fell by more than half
+    AppointmentsDropped {
+        published: f64,
+        fresh: f64,
+    },
+    DuplicateCid {
+        cid: String,
+    },
+    EmptyCid {
+        name: String,
+    },
+    // not "%Y-%m-%dT%H:%M:%S" or ending before it starts
+    InvalidAppointment {
+        cid: String,
+        start: String,
+        end: String,
+    },
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::NoCourses => write!(f, "no courses"),
+            Problem::CoursesDropped { published, fresh } => {
+                write!(f, "{} courses, {} published", fresh, published)
+            }
+            Problem::AppointmentsDropped { published, fresh } => write!(
+                f,
+                "{:.1}% of the courses have appointments, {:.1}% published",
+                fresh, published
+            ),
+            Problem::DuplicateCid { cid } => write!(f, "cid {} is used twice", cid),
+            Problem::EmptyCid { name } => write!(f, "course {:?} has no cid", name),
+            Problem::InvalidAppointment { cid, start, end } => {
+                write!(f, "invalid appointment of {}: {} to {}", cid, start, end)
+            }
+        }
+    }
+}
+
+/// Checks `fresh` on its own and, if given, against the `published` snapshot
+/// it would replace. `min_share` is the percentage of the published courses
+/// `fresh` needs to have. Empty if it's fine to publish.
+pub fn validate_semester(
+    fresh: &Semester,
+    published: Option<&Semester>,
+    min_share: f64,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if fresh.courses.is_empty() {
+        problems.push(Problem::NoCourses);
+    }
+    let mut cids = HashSet::new();
+    for course in &fresh.courses {
+        if course.cid.is_empty() {
+            problems.push(Problem::EmptyCid {
+                name: course.name.clone(),
+            });
+        } else if !cids.insert(course.cid.as_str()) {
+            problems.push(Problem::DuplicateCid {
+                cid: course.cid.clone(),
+            });
+        }
+        let mut appointments = course.appointments.iter().chain(
+            course
+                .small_groups
+                .iter()
+                .flat_map(|small_group| &small_group.appointments),
+        );
+        // one per course, a broken date parser would list every appointment
+        if let Some(appointment) = appointments.find(|a| !is_valid(a)) {
+            problems.push(Problem::InvalidAppointment {
+                cid: course.cid.clone(),
+                start: appointment.start_time.clone(),
+                end: appointment.end_time.clone(),
+            });
+        }
+    }
+    if let Some(published) = published.filter(|published| !published.courses.is_empty()) {
+        let (before, after) = (published.courses.len(), fresh.courses.len());
+        if (100 * after) as f64 / (before as f64) < min_share {
+            problems.push(Problem::CoursesDropped {
+                published: before,
+                fresh: after,
+            });
+        }
+        let (before, after) = (with_appointments(published), with_appointments(fresh));
+        if after < before / 2.0 {
+            problems.push(Problem::AppointmentsDropped {
+                published: before,
+                fresh: after,
+            });
+        }
+    }
+    problems
+}
+
+fn is_valid(appointment: &PaulineAppointment) -> bool {
+    let parse = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S").ok();
+    match (parse(&appointment.start_time), parse(&appointment.end_time)) {
+        (Some(start), Some(end)) => start <= end,
+        _ => false,
+    }
+}
+
+// percent of the courses with an appointment of their own or a small group's
+fn with_appointments(semester: &Semester) -> f64 {
+    let count = semester
+        .courses
+        .iter()
+        .filter(|course| {
+            !course.appointments.is_empty()
+                || course
+                    .small_groups
+                    .iter()
+                    .any(|small_group| !small_group.appointments.is_empty())
+        })
+        .count();
+    100.0 * count as f64 / semester.courses.len().max(1) as f64
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+use url::Url;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    links::{CourseUrl, SmallGroupUrl},
+    Course, Path, SmallGroup,
+};
+
+// javascript bindings, everything is passed back as json strings
+// so the js side can just JSON.parse the result
+
+#[derive(Serialize)]
+struct ParsedCoursePage {
+    course: Course,
+    small_groups: Vec<SmallGroupLink>,
+}
+
+#[derive(Serialize)]
+struct SmallGroupLink {
+    url: String,
+    path: Path,
+}
+
+fn to_path(fragments: Vec<String>) -> Path {
+    fragments
+        .into_iter()
+        .fold(Path::new(), |path, fragment| path.push(fragment))
+}
+
+#[wasm_bindgen(js_name = parseCoursePage)]
+pub fn parse_course_page(html: String, url: &str, path: Vec<String>) -> Result<String, JsError> {
+    let url = CourseUrl::new(Url::parse(url)?);
+    let (course, small_groups) = crate::parse_course_page(html, &url, &to_path(path));
+    let parsed = ParsedCoursePage {
+        course,
+        small_groups: small_groups
+            .into_iter()
+            .map(|(url, path)| SmallGroupLink {
+                url: url.to_string(),
+                path,
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string(&parsed)?)
+}
+
+#[wasm_bindgen(js_name = parseSmallGroup)]
+pub fn parse_small_group(html: String, url: &str, path: Vec<String>) -> Result<String, JsError> {
+    let url = SmallGroupUrl::new(Url::parse(url)?);
+    let small_group: SmallGroup = crate::parse_small_group(html, &url, &to_path(path));
+    Ok(serde_json::to_string(&small_group)?)
+}
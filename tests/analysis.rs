@@ -0,0 +1,47 @@
+mod common;
+
+use common::{state_appointment, state_course, state_small_group};
+use paul_scrape_rs::{analysis::find_free_slots, StateSerializable};
+use serde_json::json;
+
+#[test]
+fn free_slots_around_chosen_courses() {
+    let state: StateSerializable = serde_json::from_value(json!({
+        "semester": "Sommer 2023",
+        "start_time": "2023-04-01T00:00:00Z",
+        "courses": [
+            state_course("L.1", vec![state_appointment("Mo, 17. Apr. 2023", "09:00", "11:00")], &[]),
+            // overlaps L.1
+            state_course("L.2", vec![state_appointment("Mo, 17. Apr. 2023", "10:00", "12:00")], &[]),
+            // right after it
+            state_course("L.3", vec![state_appointment("Mo, 17. Apr. 2023", "11:00", "13:00")], &["sg-a", "sg-b"]),
+            // none of its small groups fit
+            state_course("L.4", vec![state_appointment("Di, 18. Apr. 2023", "09:00", "11:00")], &["sg-c"]),
+            state_course("L.5", vec![], &[]),
+        ],
+        "small_groups": [
+            state_small_group("sg-a", vec![state_appointment("Mo, 17. Apr. 2023", "09:30", "10:30")]),
+            state_small_group("sg-b", vec![state_appointment("Mi, 19. Apr. 2023", "09:30", "10:30")]),
+            state_small_group("sg-c", vec![state_appointment("Mo, 17. Apr. 2023", "08:00", "10:00")]),
+        ],
+    }))
+    .unwrap();
+
+    let fits = find_free_slots(&state, &["L.1".to_string()]);
+    let fitting = fits
+        .iter()
+        .map(|fit| {
+            (
+                fit.course.url.as_str(),
+                fit.small_groups
+                    .iter()
+                    .map(|small_group| small_group.url.as_str())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fitting,
+        vec![("https://paul.uni-paderborn.de/L.3", vec!["sg-b"])]
+    );
+}
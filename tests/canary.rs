@@ -0,0 +1,33 @@
+use std::collections::BTreeMap;
+
+use paul_scrape_rs::canary::{compare, extract_fields, CanaryKind, CanaryPage};
+use url::Url;
+
+const SMALL_GROUP: &str = r#"<form name="courseform"><h2>Kleingruppe: Gruppe 1</h2>
+    <table><caption>Termine</caption><tr><th></th></tr>
+        <tr><td>1</td><td>Mo, 17. Apr. 2023</td><td>09:00</td><td>11:00</td><td>H1</td><td></td></tr>
+    </table></form>"#;
+
+#[test]
+fn canary_fields_are_compared() {
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let fields = extract_fields(CanaryKind::SmallGroup, &url, SMALL_GROUP.to_string()).unwrap();
+    assert_eq!(fields["title"], "Kleingruppe: Gruppe 1");
+    assert_eq!(fields["appointments"], "1");
+
+    let page = CanaryPage {
+        kind: CanaryKind::SmallGroup,
+        url: url.clone(),
+        expected: BTreeMap::from([
+            ("title".to_string(), "Kleingruppe: Gruppe 1".to_string()),
+            ("appointments".to_string(), "2".to_string()),
+        ]),
+    };
+    let mismatches = compare(&page, &fields);
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].field, "appointments");
+    assert_eq!(mismatches[0].actual, "1");
+
+    // the parsers panic without the course form, that's a markup change too
+    assert!(extract_fields(CanaryKind::SmallGroup, &url, "<p></p>".to_string()).is_err());
+}
@@ -0,0 +1,38 @@
+use paul_scrape_rs::{charset::decode_html, links::CourseUrl, parse_course_page, Path};
+use url::Url;
+
+#[test]
+fn latin1_page() {
+    let body = include_bytes!("fixtures/latin1_course.html");
+    // the meta tag is enough without a header
+    let html = decode_html(body, None);
+    assert!(html.contains("Einführung in die Softwaretechnik"));
+    assert_eq!(
+        decode_html(body, Some("text/html; charset=iso-8859-1")),
+        html
+    );
+
+    let url =
+        CourseUrl::new(Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap());
+    let (course, _) = parse_course_page(html, &url, &Path::new());
+    assert_eq!(
+        course.recommended_knowledge.as_deref(),
+        Some("Grundkenntnisse der Programmierung, Übungsblätter zur Prüfung")
+    );
+}
+
+#[test]
+fn mixed_utf8_and_latin1() {
+    // declared utf-8, but one umlaut slipped in as latin-1
+    let body = b"<p>\xc3\x9cbung und Pr\xfcfung</p>";
+    assert_eq!(
+        decode_html(body, Some("text/html; charset=UTF-8")),
+        "<p>Übung und Prüfung</p>"
+    );
+    // the header wins over the meta tag
+    let body = b"<meta charset=\"utf-8\"><p>Pr\xfcfung</p>";
+    assert_eq!(
+        decode_html(body, Some("text/html; charset=windows-1252")),
+        "<meta charset=\"utf-8\"><p>Prüfung</p>"
+    );
+}
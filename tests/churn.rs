@@ -0,0 +1,101 @@
+mod common;
+
+use common::semester;
+use paul_scrape_rs::{
+    diff::{diff_semesters, semester_churn, ChangeKind, DiffFilter},
+    pauline::PaulineCourse,
+};
+
+fn course(cid: &str, ou: &str, instructors: &str) -> PaulineCourse {
+    PaulineCourse {
+        ou: Some(ou.to_string()),
+        instructors: Some(instructors.to_string()),
+        ..common::course(cid, &format!("Course {}", cid))
+    }
+}
+
+#[test]
+fn churn_per_ou() {
+    let old = semester(
+        "SoSe 2023",
+        vec![
+            course("L.079.001|ab", "Informatik", "Meyer"),
+            course("L.079.002|cd", "Informatik", "Meyer; Schmidt"),
+            course("L.105.001|ef", "Mathematik", "Müller"),
+        ],
+    );
+    let new = semester(
+        "SoSe 2024",
+        vec![
+            // the name hash changed, the number didn't
+            course("L.079.001|12", "Informatik", "Meyer"),
+            course("L.079.002|cd", "Informatik", "Schmidt; Weber"),
+            course("L.105.002|ef", "Mathematik", "Müller"),
+        ],
+    );
+    let churn = semester_churn(&old, &new);
+    assert_eq!(churn.len(), 2);
+
+    let informatik = &churn[0];
+    assert_eq!(informatik.ou.as_deref(), Some("Informatik"));
+    assert!(informatik.new.is_empty() && informatik.discontinued.is_empty());
+    assert_eq!(informatik.instructors_changed[0].number, "L.079.002");
+    assert_eq!(
+        informatik.instructors_changed[0].old,
+        vec!["Meyer", "Schmidt"]
+    );
+    assert_eq!(
+        informatik.instructors_changed[0].new,
+        vec!["Schmidt", "Weber"]
+    );
+
+    let mathematik = &churn[1];
+    assert_eq!(mathematik.new[0].number, "L.105.002");
+    assert_eq!(mathematik.discontinued[0].number, "L.105.001");
+}
+
+#[test]
+fn filtered_diff() {
+    let old = semester(
+        "SoSe 2023",
+        vec![
+            course("L.079.001|ab", "Informatik", "Meyer"),
+            course("L.105.001|ef", "Mathematik", "Müller"),
+        ],
+    );
+    let new = semester(
+        "SoSe 2023",
+        vec![
+            course("L.079.001|ab", "Informatik", "Schmidt"),
+            course("L.079.003|gh", "Informatik", "Meyer"),
+        ],
+    );
+    let diff = diff_semesters(&old, &new);
+    // one added, one removed and one changed of two courses
+    assert_eq!(diff.changed_share(&old), 150.0);
+
+    let informatik = diff.filtered(&DiffFilter {
+        ous: vec!["Informatik".to_string()],
+        ..DiffFilter::default()
+    });
+    assert_eq!(informatik.added.len(), 1);
+    assert!(informatik.removed.is_empty());
+    assert_eq!(informatik.changed.len(), 1);
+
+    let one_course = diff.filtered(&DiffFilter {
+        cids: vec!["L.079.001".to_string()],
+        changes: vec![ChangeKind::Changed],
+        ..DiffFilter::default()
+    });
+    assert!(one_course.added.is_empty());
+    assert_eq!(one_course.changed[0].cid, "L.079.001|ab");
+
+    assert!(diff
+        .filtered(&DiffFilter {
+            changes: vec![ChangeKind::Removed],
+            ..DiffFilter::default()
+        })
+        .removed
+        .iter()
+        .all(|course| course.ou.as_deref() == Some("Mathematik")));
+}
@@ -0,0 +1,59 @@
+use paul_scrape_rs::cid::{event_id, CidStrategy, CourseNumber, NameHash, UuidV5};
+use uuid::Uuid;
+
+#[test]
+fn strategies() {
+    let (number, name, instructors) = ("L.079.05401", "Grundlagen der Programmierung 1", "Meyer");
+    assert_eq!(CourseNumber.cid(number, name, instructors), "L.079.05401");
+
+    let hashed = NameHash.cid(number, name, instructors);
+    assert!(hashed.starts_with("L.079.05401|"));
+    assert_eq!(hashed.len(), "L.079.05401|".len() + 2);
+    assert_ne!(NameHash.cid(number, name, "Schmidt"), hashed);
+
+    let uuid = UuidV5 {
+        namespace: Uuid::NAMESPACE_URL,
+    };
+    let cid = uuid.cid(number, name, instructors);
+    assert_eq!(Uuid::parse_str(&cid).unwrap().get_version_num(), 5);
+    // stable, and instructors don't matter
+    assert_eq!(uuid.cid(number, name, "Schmidt"), cid);
+    assert_ne!(uuid.cid(number, "Other", instructors), cid);
+}
+
+#[test]
+fn event_ids_ignore_the_session() {
+    let url = |session: &str| {
+        format!(
+            "https://paul.uni-paderborn.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=COURSEDETAILS&ARGUMENTS=-N{},-N000443,-N379207311355588,-N0",
+            session
+        )
+    };
+    let id = event_id(&url("000000000000001"));
+    assert_eq!(id, "coursedetails:000443,379207311355588,0");
+    assert_eq!(event_id(&url("599184726536118")), id);
+    assert_eq!(event_id("not a url"), "not a url");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn id_map_keeps_first_cid() {
+    use paul_scrape_rs::cid::IdMap;
+    let path = std::env::temp_dir().join(format!("paul-ids-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let id_map = IdMap::open(&path).unwrap();
+    assert_eq!(id_map.cid("a", "L.1|3f").unwrap(), "L.1|3f");
+    // another event deriving a taken cid
+    assert_eq!(id_map.cid("b", "L.1|3f").unwrap(), "L.1|3f:1");
+    id_map.save().unwrap();
+
+    // renamed, so derived differently, but keeps its cid
+    let id_map = IdMap::open(&path).unwrap();
+    assert_eq!(id_map.cid("a", "L.1|a0").unwrap(), "L.1|3f");
+    assert_eq!(id_map.len().unwrap(), 2);
+    // dropped without saving
+    id_map.cid("c", "L.2|00").unwrap();
+    drop(id_map);
+    assert_eq!(IdMap::open(&path).unwrap().len().unwrap(), 2);
+    std::fs::remove_file(&path).unwrap();
+}
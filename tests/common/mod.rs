@@ -0,0 +1,77 @@
+// fixture builders shared by the test files, each of them uses only some
+#![allow(dead_code)]
+
+use paul_scrape_rs::{
+    pauline::{PaulineAppointment, PaulineCourse, Semester},
+    Location,
+};
+use serde_json::json;
+
+// in O2.267 with Prof. Dr. Meyer, change the fields a test is about
+pub fn appointment(start: &str, end: &str) -> PaulineAppointment {
+    PaulineAppointment {
+        start_time: start.to_string(),
+        end_time: end.to_string(),
+        room: "O2.267".to_string(),
+        room_id: None,
+        instructors: "Prof. Dr. Meyer".to_string(),
+        location: Location::Room,
+        cancelled: false,
+    }
+}
+
+// no ou, instructors, appointments or small groups
+pub fn course(cid: &str, name: &str) -> PaulineCourse {
+    PaulineCourse {
+        cid: cid.to_string(),
+        name: name.to_string(),
+        description: None,
+        ou: None,
+        instructors: None,
+        small_groups: Vec::new(),
+        appointments: Vec::new(),
+        tags: Vec::new(),
+        source_updated_at: None,
+    }
+}
+
+pub fn semester(name: &str, courses: Vec<PaulineCourse>) -> Semester {
+    Semester {
+        name: name.to_string(),
+        created: "2023-10-01T12:00:00".to_string(),
+        courses,
+    }
+}
+
+// the same for the scraped state, which is only built from json
+pub fn state_appointment(date: &str, start: &str, end: &str) -> serde_json::Value {
+    json!({
+        "start_time": [date, start],
+        "end_time": [date, end],
+        "room": "O2.267",
+        "instructors": "",
+    })
+}
+
+pub fn state_course(
+    number: &str,
+    appointments: Vec<serde_json::Value>,
+    small_groups: &[&str],
+) -> serde_json::Value {
+    json!({
+        "url": format!("https://paul.uni-paderborn.de/{}", number),
+        "path": { "fragments": [format!("{}\nCourse {}", number, number)] },
+        "instructors": "",
+        "ou": null,
+        "appointments": appointments,
+        "small_groups": small_groups,
+    })
+}
+
+pub fn state_small_group(url: &str, appointments: Vec<serde_json::Value>) -> serde_json::Value {
+    json!({
+        "url": url,
+        "path": { "fragments": [url] },
+        "appointments": appointments,
+    })
+}
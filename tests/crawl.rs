@@ -0,0 +1,92 @@
+#![cfg(feature = "scrape")]
+
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+};
+
+use futures::StreamExt;
+use paul_scrape_rs::{error::ErrorKind, prelude::*};
+use url::Url;
+
+// answers every request with `html`, for as long as the test runs
+fn serve(html: &'static str) -> Url {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                html.len(),
+                html
+            );
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn scrape_courses_in_order() {
+    let course = serve(
+        r#"<form name="courseform"><h1>L.079.05401 Grundlagen der Programmierung 1</h1>
+        <span name="courseOrgUnit">Institut für Informatik</span></form>"#,
+    );
+    // nothing listens there anymore
+    let closed = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap()
+    };
+    let client = reqwest::Client::new();
+    let mut config = ScrapeConfig::new(course.clone(), "Sommer 2023");
+    config.retries = 0;
+    let results = scrape_courses(&client, vec![course.clone(), closed.clone()], &config)
+        .collect::<Vec<_>>()
+        .await;
+
+    let course = results[0].as_ref().ok().unwrap();
+    assert_eq!(course.heading()[0], "L.079.05401");
+    assert_eq!(course.ou.as_deref(), Some("Institut für Informatik"));
+    let failed = results[1].as_ref().err().unwrap();
+    assert_eq!(failed.url, closed.as_str());
+    assert_eq!(failed.kind, Some(ErrorKind::Network));
+}
+
+// what a downstream crate would add without touching this one
+struct Shout;
+
+impl CoursePostProcessor for Shout {
+    fn name(&self) -> &'static str {
+        "shout"
+    }
+
+    fn process(&self, course: &mut Course) {
+        course.instructors = course.instructors.to_uppercase();
+    }
+}
+
+#[tokio::test]
+async fn registered_post_processors() {
+    let course = serve(
+        r#"<form name="courseform"><h1>L.079.05401 Grundlagen der Programmierung 1</h1>
+        <span id="dozenten">Prof. Dr. Meyer</span></form>"#,
+    );
+    let mut registry = PostProcessorRegistry::default();
+    registry.register(|| Box::new(Shout));
+    assert!(registry.by_name(&["unknown".to_string()]).is_err());
+    let processors = registry
+        .by_name(&["shout".to_string(), "ou-from-path".to_string()])
+        .unwrap();
+
+    let mut config = ScrapeConfig::new(course.clone(), "Sommer 2023");
+    config.post_processors = Arc::new(processors);
+    let client = reqwest::Client::new();
+    let results = scrape_courses(&client, vec![course], &config)
+        .collect::<Vec<_>>()
+        .await;
+    let course = results[0].as_ref().ok().unwrap();
+    assert_eq!(course.instructors, "PROF. DR. MEYER");
+}
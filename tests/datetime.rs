@@ -0,0 +1,95 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use paul_scrape_rs::datetime::{parse_date, parse_datetime, parse_time};
+use proptest::prelude::*;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mrz", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+];
+
+fn weekday(date: NaiveDate) -> &'static str {
+    match date.weekday() {
+        Weekday::Mon => "Mo",
+        Weekday::Tue => "Di",
+        Weekday::Wed => "Mi",
+        Weekday::Thu => "Do",
+        Weekday::Fri => "Fr",
+        Weekday::Sat => "Sa",
+        Weekday::Sun => "So",
+    }
+}
+
+// "Di, 18. Apr. 2023", with or without the dot after the month
+fn format_paul(date: NaiveDate, month_dot: bool) -> String {
+    format!(
+        "{}, {}. {}{} {}",
+        weekday(date),
+        date.day(),
+        MONTHS[date.month0() as usize],
+        if month_dot { "." } else { "" },
+        date.year()
+    )
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let mut date = NaiveDate::from_ymd_opt(year, month + 1, 1).unwrap() - Duration::days(1);
+    while date.weekday() != Weekday::Sun {
+        date -= Duration::days(1);
+    }
+    date
+}
+
+fn dates() -> impl Strategy<Value = NaiveDate> {
+    (1970i32..2100, 1u32..=12, 1u32..=31).prop_filter_map("no such day", |(year, month, day)| {
+        NaiveDate::from_ymd_opt(year, month, day)
+    })
+}
+
+proptest! {
+    #[test]
+    fn date_round_trip(date in dates(), month_dot in any::<bool>()) {
+        prop_assert_eq!(parse_date(&format_paul(date, month_dot)), Some(date));
+    }
+
+    #[test]
+    fn datetime_round_trip(date in dates(), hour in 0u32..24, minute in 0u32..60) {
+        let time = format!("{:02}:{:02}", hour, minute);
+        let parsed = parse_datetime(&format_paul(date, true), &time).unwrap();
+        prop_assert_eq!(
+            parsed.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            format!("{}T{}:00", date.format("%Y-%m-%d"), time)
+        );
+    }
+
+    #[test]
+    fn leap_days(year in 1970i32..2100) {
+        let leap_day = format!("Mo, 29. Feb. {}", year);
+        prop_assert_eq!(
+            parse_date(&leap_day).is_some(),
+            NaiveDate::from_ymd_opt(year, 2, 29).is_some()
+        );
+    }
+
+    #[test]
+    fn dst_boundaries(year in 1996i32..2100, october in any::<bool>(), hour in 0u32..24, minute in 0u32..60) {
+        // PAUL times are wall clock times, the switch days must not shift them,
+        // not even the hour that doesn't exist in march or exists twice in october
+        let date = last_sunday(year, if october { 10 } else { 3 });
+        let time = format!("{:02}:{:02}", hour, minute);
+        let parsed = parse_datetime(&format_paul(date, true), &time).unwrap();
+        prop_assert_eq!(parsed.date(), date);
+        prop_assert_eq!(parsed.format("%H:%M").to_string(), time);
+    }
+}
+
+#[test]
+fn alternative_month_spellings() {
+    for (month, number) in [("Mär", 3), ("Mar", 3), ("May", 5), ("Oct", 10), ("Dec", 12)] {
+        let date = parse_date(&format!("Mo, 1. {}. 2024", month)).unwrap();
+        assert_eq!(date.month(), number);
+    }
+}
+
+#[test]
+fn midnight_end_time() {
+    assert_eq!(parse_time("24:00"), parse_time("23:59"));
+}
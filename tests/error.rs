@@ -0,0 +1,52 @@
+use paul_scrape_rs::{
+    error::{Error, ErrorKind},
+    status::{run_status, Outcome},
+    FailedPage, Path,
+};
+
+#[test]
+fn retryable_errors() {
+    assert!(Error::Status(503).is_retryable());
+    assert!(Error::Status(429).is_retryable());
+    assert!(!Error::LoginPage.is_retryable());
+    assert!(!Error::Status(404).is_retryable());
+    assert!(!Error::ContentType("application/pdf".to_string()).is_retryable());
+    assert!(!Error::TooLarge { limit: 10 }.is_retryable());
+}
+
+#[test]
+fn degraded_above_threshold() {
+    assert_eq!(Error::Status(404).kind(), ErrorKind::Status);
+    assert_eq!("parse".parse::<ErrorKind>(), Ok(ErrorKind::Parse));
+    assert!("parsing".parse::<ErrorKind>().is_err());
+
+    let failed = |kind| FailedPage {
+        url: "https://paul.uni-paderborn.de/broken".to_string(),
+        path: Path::new(),
+        error: "no title".to_string(),
+        kind,
+        html: None,
+        excerpt: None,
+    };
+    let failed_pages = vec![
+        failed(Some(ErrorKind::Parse)),
+        failed(Some(ErrorKind::Parse)),
+        failed(Some(ErrorKind::Status)),
+        failed(None),
+    ];
+
+    let status = run_status(100, &failed_pages, &[(ErrorKind::Parse, 5.0)]);
+    assert_eq!(status.status, Outcome::Ok);
+    assert_eq!(status.failures[&ErrorKind::Parse], 2);
+    assert_eq!(status.failures[&ErrorKind::Other], 1);
+
+    let status = run_status(
+        100,
+        &failed_pages,
+        &[(ErrorKind::Parse, 1.0), (ErrorKind::Status, 1.0)],
+    );
+    assert_eq!(status.status, Outcome::Degraded);
+    assert_eq!(status.exceeded.len(), 1);
+    assert_eq!(status.exceeded[0].kind, ErrorKind::Parse);
+    assert_eq!(status.exceeded[0].rate, 2.0);
+}
@@ -0,0 +1,47 @@
+use paul_scrape_rs::{
+    excerpt::{page_excerpt, COURSE_PAGE, EXCERPT_CHARS},
+    FailedPage, Path,
+};
+
+#[test]
+fn excerpt_of_failed_course_page() {
+    // the course form without a title, as after a redesign
+    let html = r#"<html><head><script>var token = "secret";</script></head><body>
+        <div id="menu">Vorlesungsverzeichnis</div>
+        <form name="courseform" action="/scripts/mgrqispi.dll">
+          <input type="hidden" name="sessionno" value="379207311355588">
+          <a href="?APPNAME=CampusNet&amp;PRGNAME=COURSEDETAILS&amp;ARGUMENTS=-N379207311355588,-N000443">Details</a>
+          <span id="dozenten">Prof. Dr. Meyer (meyer@uni-paderborn.de)</span>
+        </form></body></html>"#;
+    let excerpt = page_excerpt(html, COURSE_PAGE);
+    assert!(excerpt.starts_with("<form ") && excerpt.contains(r#"name="courseform""#));
+    assert!(!excerpt.contains("379207311355588"));
+    assert!(!excerpt.contains("meyer@"));
+    assert!(excerpt.contains("ARGUMENTS=-N0,-N000443"));
+    assert!(excerpt.contains("Prof. Dr. Meyer"));
+
+    // without the form the start of the body, minus scripts
+    let excerpt = page_excerpt(&html.replace("courseform", "other"), COURSE_PAGE);
+    assert!(excerpt.starts_with(r#"<div id="menu">"#));
+    assert!(!excerpt.contains("secret"));
+
+    let long = format!("<body><p>{}</p></body>", "Termine ".repeat(200));
+    assert_eq!(
+        page_excerpt(&long, COURSE_PAGE).chars().count(),
+        EXCERPT_CHARS + 1
+    );
+
+    let failed = FailedPage {
+        url: "https://paul.uni-paderborn.de/course".to_string(),
+        path: Path::new().push("Sommer 2023".to_string()),
+        error: "no title".to_string(),
+        kind: None,
+        html: None,
+        excerpt: Some(page_excerpt(html, COURSE_PAGE)),
+    };
+    let report = failed.to_string();
+    assert!(
+        report.starts_with("no title\n  at https://paul.uni-paderborn.de/course\n  in Sommer 2023")
+    );
+    assert!(report.contains("\n  page: <form"));
+}
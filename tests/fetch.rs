@@ -0,0 +1,60 @@
+#![cfg(feature = "scrape")]
+
+use std::io::{Read, Write};
+
+use flate2::{write::GzEncoder, Compression};
+use paul_scrape_rs::{
+    error::Error,
+    fetch::{fetch_page_timed, ResponseLimits, Timings},
+};
+use url::Url;
+
+// answers every request with a gzipped `body`
+fn serve_gzip(body: Vec<u8>) -> Url {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&body).unwrap();
+    let compressed = encoder.finish().unwrap();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                compressed.len()
+            );
+            let _ = stream.write_all(&compressed);
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn compressed_pages_are_decoded_and_counted() {
+    let html = format!(
+        "<html><body>{}</body></html>",
+        "<p>Termine</p>".repeat(1000)
+    );
+    let url = serve_gzip(html.clone().into_bytes());
+    let client = reqwest::Client::new();
+    let timings = Timings::default();
+    let page = fetch_page_timed(&client, &url, &ResponseLimits::default(), &timings)
+        .await
+        .unwrap();
+    assert_eq!(page, html);
+    let transfer = timings.transfer();
+    assert_eq!(transfer.decoded, html.len() as u64);
+    assert!(transfer.transferred < transfer.decoded / 10);
+    assert_eq!(transfer.compressed_responses, 1);
+
+    // the limit holds for what the body unpacks to
+    let url = serve_gzip(vec![b' '; 1_000_000]);
+    let limits = ResponseLimits {
+        max_body_size: 100_000,
+    };
+    let result = fetch_page_timed(&client, &url, &limits, &timings).await;
+    assert!(matches!(result, Err(Error::TooLarge { limit: 100_000 })));
+}
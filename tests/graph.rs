@@ -0,0 +1,25 @@
+use paul_scrape_rs::graph::{CrawlGraph, Edge, NodeKind};
+
+#[test]
+fn dangling_edges_are_left_out() {
+    let mut graph = CrawlGraph::default();
+    graph.add_node("https://paul/main", NodeKind::Main);
+    graph.add_edge(
+        "https://paul/main",
+        "https://paul/tree",
+        NodeKind::Tree,
+        "Vorlesungsverzeichnis",
+    );
+    // as resumed from a checkpoint without the source page
+    graph.edges.push(Edge {
+        from: "https://paul/unknown".to_string(),
+        to: "https://paul/tree".to_string(),
+        label: "lost".to_string(),
+    });
+
+    let dot = graph.to_dot();
+    assert!(dot.contains("n0 -> n1 [label=\"Vorlesungsverzeichnis\"];"));
+    assert!(!dot.contains("lost"));
+    let graphml = graph.to_graphml();
+    assert_eq!(graphml.matches("<edge ").count(), 1);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::{appointment, course};
+use paul_scrape_rs::{
+    ics::{semester_to_ics, semester_to_ics_in, TimeWindow},
+    pauline::{PaulineAppointment, PaulineCourse, Semester},
+    Location,
+};
+
+fn semester(appointments: Vec<PaulineAppointment>) -> Semester {
+    common::semester(
+        "Winter 2023/24",
+        vec![PaulineCourse {
+            appointments,
+            ..course("L.079.05401", "Programmierung, Teil 1")
+        }],
+    )
+}
+
+fn lines_starting_with<'a>(ics: &'a str, prefix: &str) -> Vec<&'a str> {
+    ics.lines()
+        .filter(|line| line.starts_with(prefix))
+        .collect()
+}
+
+#[test]
+fn dst_transition_weeks_keep_local_times() {
+    // the week before, of and after the october and march switches
+    let ics = semester_to_ics(
+        &semester(vec![
+            appointment("2023-10-24T11:00:00", "2023-10-24T13:00:00"),
+            appointment("2023-10-29T02:30:00", "2023-10-29T03:30:00"),
+            appointment("2023-10-31T11:00:00", "2023-10-31T13:00:00"),
+            appointment("2024-03-26T11:00:00", "2024-03-26T13:00:00"),
+            appointment("2024-03-31T02:30:00", "2024-03-31T03:30:00"),
+        ]),
+        |_| true,
+    );
+    assert_eq!(
+        lines_starting_with(&ics, "DTSTART;"),
+        vec![
+            "DTSTART;TZID=Europe/Berlin:20231024T110000",
+            "DTSTART;TZID=Europe/Berlin:20231029T023000",
+            "DTSTART;TZID=Europe/Berlin:20231031T110000",
+            "DTSTART;TZID=Europe/Berlin:20240326T110000",
+            "DTSTART;TZID=Europe/Berlin:20240331T023000",
+        ]
+    );
+}
+
+#[test]
+fn timezone_is_defined_once() {
+    let ics = semester_to_ics(
+        &semester(vec![appointment(
+            "2023-10-24T11:00:00",
+            "2023-10-24T13:00:00",
+        )]),
+        |_| true,
+    );
+    assert_eq!(lines_starting_with(&ics, "BEGIN:VTIMEZONE").len(), 1);
+    assert_eq!(
+        lines_starting_with(&ics, "TZID:"),
+        vec!["TZID:Europe/Berlin"]
+    );
+    assert!(ics.contains("RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\n"));
+    assert!(ics.contains("RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r\n"));
+    // only the zone rules have floating times, the events reference the zone
+    assert_eq!(lines_starting_with(&ics, "DTSTART:").len(), 2);
+    assert!(ics.contains("SUMMARY:Programmierung\\, Teil 1\r\n"));
+}
+
+#[test]
+fn time_window_keeps_whole_days() {
+    let semester = semester(vec![
+        appointment("2024-03-31T11:00:00", "2024-03-31T13:00:00"),
+        appointment("2024-04-01T08:00:00", "2024-04-01T10:00:00"),
+        appointment("2024-04-30T18:00:00", "2024-04-30T20:00:00"),
+        appointment("2024-05-01T11:00:00", "2024-05-01T13:00:00"),
+    ]);
+    let window = TimeWindow {
+        from: chrono::NaiveDate::from_ymd_opt(2024, 4, 1),
+        to: chrono::NaiveDate::from_ymd_opt(2024, 4, 30),
+    };
+    let ics = semester_to_ics_in(&semester, |_| true, &window);
+    assert_eq!(
+        lines_starting_with(&ics, "DTSTART;"),
+        vec![
+            "DTSTART;TZID=Europe/Berlin:20240401T080000",
+            "DTSTART;TZID=Europe/Berlin:20240430T180000",
+        ]
+    );
+    // the uids are the same as in the full export
+    assert_eq!(
+        lines_starting_with(&ics, "UID:"),
+        vec![
+            "UID:L.079.05401-1@paul-scrape-rs",
+            "UID:L.079.05401-2@paul-scrape-rs"
+        ]
+    );
+}
+
+#[test]
+fn meeting_links_go_to_url() {
+    let mut online = appointment("2023-10-24T11:00:00", "2023-10-24T13:00:00");
+    online.room = "https://uni-paderborn.zoom.us/j/123456".to_string();
+    online.location = Location::parse(&online.room);
+    let ics = semester_to_ics(
+        &semester(vec![
+            online,
+            appointment("2023-10-31T11:00:00", "2023-10-31T13:00:00"),
+        ]),
+        |_| true,
+    );
+    assert_eq!(
+        lines_starting_with(&ics, "URL:"),
+        vec!["URL:https://uni-paderborn.zoom.us/j/123456"]
+    );
+    assert_eq!(
+        lines_starting_with(&ics, "LOCATION:"),
+        vec![
+            "LOCATION:https://uni-paderborn.zoom.us/j/123456",
+            "LOCATION:O2.267"
+        ]
+    );
+}
+
+#[test]
+fn cancelled_appointments_are_marked() {
+    let mut cancelled = appointment("2023-10-31T11:00:00", "2023-10-31T13:00:00");
+    cancelled.cancelled = true;
+    let ics = semester_to_ics(
+        &semester(vec![
+            appointment("2023-10-24T11:00:00", "2023-10-24T13:00:00"),
+            cancelled,
+        ]),
+        |_| true,
+    );
+    assert_eq!(lines_starting_with(&ics, "BEGIN:VEVENT").len(), 2);
+    assert_eq!(
+        lines_starting_with(&ics, "STATUS:"),
+        vec!["STATUS:CANCELLED"]
+    );
+    // on the cancelled date's event
+    let status = ics.find("STATUS:CANCELLED").unwrap();
+    assert!(ics[..status].ends_with("SUMMARY:Programmierung\\, Teil 1\r\n"));
+    assert!(ics[..status].contains("DTSTART;TZID=Europe/Berlin:20231031T110000"));
+}
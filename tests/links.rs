@@ -0,0 +1,95 @@
+use paul_scrape_rs::{
+    links::{classify_link, CourseUrl, Link, LinkClassifier, LinkKind, ModuleUrl, TreeUrl},
+    modules::{parse_module_page, parse_module_page_with},
+    Path, PathSegmentKind,
+};
+use url::Url;
+
+#[test]
+fn classify() {
+    let base = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let cluster = "?APPNAME=CampusNet&PRGNAME=COURSEOFFERINGCLUSTER&ARGUMENTS=-N1";
+    let details = "?APPNAME=CampusNet&PRGNAME=COURSEDETAILS&ARGUMENTS=-N2";
+    assert_eq!(
+        classify_link(&base, cluster),
+        Some(Link::Tree(TreeUrl::new(base.join(cluster).unwrap())))
+    );
+    assert_eq!(
+        classify_link(&base, details),
+        Some(Link::Course(CourseUrl::new(base.join(details).unwrap())))
+    );
+    assert_eq!(classify_link(&base, "?PRGNAME=EXTERNALPAGES"), None);
+}
+
+#[test]
+fn serialized_as_plain_url() {
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll?x=1").unwrap();
+    let json = serde_json::to_string(&CourseUrl::new(url.clone())).unwrap();
+    assert_eq!(json, serde_json::to_string(&url).unwrap());
+    let parsed: CourseUrl = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.as_url(), &url);
+}
+
+#[test]
+fn registered_markers() {
+    let base = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let mut classifier = LinkClassifier::default();
+    assert_eq!(
+        classifier.kind("?PRGNAME=MODULEDETAILS&ARGUMENTS=-N3"),
+        Some(LinkKind::Module)
+    );
+    assert!(matches!(
+        classifier.classify(&base, "?PRGNAME=ROOMDETAILS"),
+        Some(Link::Other(LinkKind::Room, _))
+    ));
+    assert_eq!(classifier.kind("?PRGNAME=EVENTDETAILS"), None);
+
+    classifier.register("EVENTDETAILS", LinkKind::Course);
+    assert!(matches!(
+        classifier.classify(&base, "?PRGNAME=EVENTDETAILS"),
+        Some(Link::Course(_))
+    ));
+    assert_eq!("exam".parse::<LinkKind>(), Ok(LinkKind::Exam));
+    assert!("nonsense".parse::<LinkKind>().is_err());
+}
+
+#[test]
+fn registered_markers_on_module_pages() {
+    let page = r#"<h1>M.079.4010 Softwaretechnik</h1>
+        <a href="?PRGNAME=EVENTDETAILS&amp;ARGUMENTS=-N1">L.079.05401 Softwaretechnik</a>"#;
+    let url = ModuleUrl::new(
+        Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll?PRGNAME=MODULEDETAILS")
+            .unwrap(),
+    );
+    assert!(parse_module_page(page, &url).courses.is_empty());
+
+    let mut classifier = LinkClassifier::default();
+    classifier.register("EVENTDETAILS", LinkKind::Course);
+    let module = parse_module_page_with(page, &url, &classifier);
+    assert_eq!(module.courses[0].title, "L.079.05401 Softwaretechnik");
+}
+
+#[test]
+fn path_faculty() {
+    let semester = Path::new().push_segment(PathSegmentKind::Semester, "SoSe 2024".into(), None);
+    assert_eq!(semester.faculty(), None);
+    let course = semester
+        .push_segment(
+            PathSegmentKind::Category,
+            "Fakultät für Kulturwissenschaften".into(),
+            None,
+        )
+        .push_segment(PathSegmentKind::Category, "Germanistik".into(), None)
+        .push_segment(
+            PathSegmentKind::CourseListing,
+            "L.01.001 Einführung".into(),
+            None,
+        );
+    assert_eq!(course.faculty(), Some("Fakultät für Kulturwissenschaften"));
+    // state files without typed segments
+    let untyped = Path::new()
+        .push("SoSe 2024".into())
+        .push("Fakultät für Maschinenbau".into())
+        .push("L.02.001 Mechanik".into());
+    assert_eq!(untyped.faculty(), Some("Fakultät für Maschinenbau"));
+}
@@ -0,0 +1,26 @@
+use paul_scrape_rs::numbers::{parse_amount, Amount};
+
+fn amount(min: f64, max: f64) -> Option<Amount> {
+    Some(Amount { min, max })
+}
+
+#[test]
+fn amounts() {
+    assert_eq!(parse_amount("5"), amount(5.0, 5.0));
+    assert_eq!(parse_amount("5 ECTS."), amount(5.0, 5.0));
+    // comma decimals
+    assert_eq!(parse_amount("2,5 SWS"), amount(2.5, 2.5));
+    // thousands separators only next to a decimal comma, a lone dot is a decimal point
+    assert_eq!(parse_amount("1.000,5"), amount(1000.5, 1000.5));
+    assert_eq!(parse_amount("1.5 SWS"), amount(1.5, 1.5));
+    // ranges, in either order
+    assert_eq!(parse_amount("5-10 ECTS"), amount(5.0, 10.0));
+    assert_eq!(parse_amount("10 – 5"), amount(5.0, 10.0));
+    assert_eq!(parse_amount("2,5 bis 4"), amount(2.5, 4.0));
+    assert_eq!(parse_amount("3 to 6 credits"), amount(3.0, 6.0));
+    // two numbers that aren't a range
+    assert_eq!(parse_amount("5 ECTS / 4 SWS"), None);
+    assert_eq!(parse_amount("1 2 3"), None);
+    assert_eq!(parse_amount("nach Absprache"), None);
+    assert_eq!(parse_amount(""), None);
+}
@@ -0,0 +1,32 @@
+use paul_scrape_rs::pacing::Pacer;
+
+#[test]
+fn constant_rate() {
+    let mut pacer = Pacer::constant(20.0);
+    for _ in 0..100 {
+        pacer.record(false);
+    }
+    assert_eq!(pacer.rate(false), 20.0);
+    assert_eq!(pacer.rate(true), 20.0);
+}
+
+#[test]
+fn ramp_up_back_off_cool_down() {
+    let mut pacer = Pacer::ramp(20.0);
+    assert_eq!(pacer.rate(false), 2.0);
+    for _ in 0..20 * 20 {
+        pacer.record(true);
+    }
+    assert_eq!(pacer.rate(false), 20.0);
+    assert_eq!(pacer.rate(true), 10.0);
+
+    for _ in 0..20 {
+        pacer.record(false);
+    }
+    assert_eq!(pacer.rate(false), 10.0);
+    // never below the warm-up rate
+    for _ in 0..20 * 20 {
+        pacer.record(false);
+    }
+    assert_eq!(pacer.rate(false), 2.0);
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "scrape")]
+
+use paul_scrape_rs::pack::{inspect, pack, unpack};
+
+#[test]
+fn pack_round_trip() {
+    let dir = std::env::temp_dir().join(format!("paul-pack-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let run = dir.join("run");
+    let cache = dir.join("cache");
+    std::fs::create_dir_all(&run).unwrap();
+    std::fs::create_dir_all(&cache).unwrap();
+    std::fs::write(run.join("state.json"), r#"{"semester":"SoSe 2024"}"#).unwrap();
+    std::fs::write(run.join("semester.json"), "{}").unwrap();
+    std::fs::write(run.join("status.json"), "{}").unwrap();
+    // the manifest lists outputs as the crawler saw them
+    std::fs::write(
+        run.join("manifest.json"),
+        r#"{"version":"0.1.0","semesters":["SoSe 2024"],"files":[{"path":"elsewhere/state.json"}]}"#,
+    )
+    .unwrap();
+    std::fs::write(cache.join("ab.html"), "<html></html>").unwrap();
+    std::fs::write(cache.join("cd.partial"), "<ht").unwrap();
+
+    let archive = dir.join("run.tar.zst");
+    let packed = pack(&run, &archive, Some(&cache)).unwrap();
+    let names = packed
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "manifest.json",
+            "status.json",
+            "state.json",
+            "semester.json",
+            "cache/ab.html"
+        ]
+    );
+
+    let (entries, manifest) = inspect(&archive).unwrap();
+    assert_eq!(entries, packed);
+    assert_eq!(manifest.unwrap()["semesters"][0], "SoSe 2024");
+
+    let unpacked = dir.join("unpacked");
+    unpack(&archive, &unpacked).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(unpacked.join("state.json")).unwrap(),
+        r#"{"semester":"SoSe 2024"}"#
+    );
+    assert!(unpacked.join("cache/ab.html").is_file());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,92 @@
+use paul_scrape_rs::{
+    links::CourseUrl,
+    parse_course_page,
+    partition::{merge_states, partition_by_faculty},
+    CatalogEntry, FailedPage, Path, PathSegmentKind, StateSerializable,
+};
+use url::Url;
+
+fn entry(faculty: &str, number: usize) -> CatalogEntry {
+    let path = Path::new()
+        .push_segment(PathSegmentKind::Semester, "SoSe 2024".into(), None)
+        .push_segment(PathSegmentKind::Category, faculty.into(), None)
+        .push_segment(
+            PathSegmentKind::CourseListing,
+            format!("L.{} Kurs", number),
+            None,
+        );
+    CatalogEntry {
+        number: format!("L.{}", number),
+        title: "Kurs".to_string(),
+        path,
+        url: format!("https://paul.uni-paderborn.de/?course={}", number),
+        course_type: None,
+    }
+}
+
+#[test]
+fn partition_keeps_faculties_together() {
+    let mut entries = (0..6).map(|i| entry("Informatik", i)).collect::<Vec<_>>();
+    entries.extend((6..10).map(|i| entry("Maschinenbau", i)));
+    entries.extend((10..12).map(|i| entry("Chemie", i)));
+    // listed under a second category
+    entries.push(entry("Informatik", 0));
+
+    let shards = partition_by_faculty(&entries, 2);
+    let sizes = shards.iter().map(Vec::len).collect::<Vec<_>>();
+    assert_eq!(sizes, [6, 6]);
+    assert!(shards[0]
+        .iter()
+        .all(|entry| entry.path.faculty() == Some("Informatik")));
+    assert_eq!(partition_by_faculty(&entries, 5)[3].len(), 0);
+}
+
+fn state(courses: &[usize], failed: &[usize]) -> StateSerializable {
+    let url = |number: &usize| format!("https://paul.uni-paderborn.de/?course={}", number);
+    StateSerializable {
+        semester: "SoSe 2024".to_string(),
+        start_time: chrono::Utc::now(),
+        courses: courses
+            .iter()
+            .map(|number| {
+                let html = format!(
+                    r#"<form name="courseform"><h1>L.{} Kurs</h1></form>"#,
+                    number
+                );
+                let url = CourseUrl::new(Url::parse(&url(number)).unwrap());
+                parse_course_page(html, &url, &Path::new()).0
+            })
+            .collect(),
+        small_groups: Vec::new(),
+        failed_pages: failed
+            .iter()
+            .map(|number| FailedPage {
+                url: url(number),
+                path: Path::new(),
+                error: "unexpected status 503".to_string(),
+                kind: None,
+                html: None,
+                excerpt: None,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn merge_workers() {
+    let merged = merge_states(vec![state(&[1, 2], &[3, 4]), state(&[3, 2], &[4])]).unwrap();
+    let courses = merged
+        .courses
+        .iter()
+        .map(|course| course.heading()[0].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(courses, ["L.1", "L.2", "L.3"]);
+    // 3 failed on one worker and worked on the other, 4 failed on both
+    assert_eq!(merged.failed_pages.len(), 1);
+    assert!(merged.failed_pages[0].url.ends_with("course=4"));
+
+    let mut other = state(&[5], &[]);
+    other.semester = "WiSe 2024/25".to_string();
+    assert!(merge_states(vec![state(&[1], &[]), other]).is_err());
+    assert!(merge_states(Vec::new()).is_err());
+}
@@ -0,0 +1,23 @@
+use paul_scrape_rs::{pauline::PaulineAppointment, Location};
+
+#[test]
+fn cancelled_appointments_round_trip() {
+    let appointment = PaulineAppointment {
+        start_time: "2023-05-09T11:00:00".to_string(),
+        end_time: "2023-05-09T13:00:00".to_string(),
+        room: "O2.267".to_string(),
+        room_id: None,
+        instructors: "Prof. Dr. Meyer".to_string(),
+        location: Location::Room,
+        cancelled: true,
+    };
+    let json = serde_json::to_string(&appointment).unwrap();
+    let read: PaulineAppointment = serde_json::from_str(&json).unwrap();
+    assert!(read.cancelled);
+    assert_eq!(read, appointment);
+
+    // semester.json files written before it was converted
+    let json = json.replace(r#","cancelled":true"#, "");
+    let read: PaulineAppointment = serde_json::from_str(&json).unwrap();
+    assert!(!read.cancelled);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use common::appointment;
+use paul_scrape_rs::{
+    pauline::{PaulineAppointment, PaulineCourse, PaulineSmallGroup, Semester},
+    query::{parse_query, run_query},
+};
+
+fn course(cid: &str, name: &str, appointments: usize) -> PaulineCourse {
+    let room = |room: &str| PaulineAppointment {
+        room: room.to_string(),
+        ..appointment("2024-04-15T09:00:00", "2024-04-15T11:00:00")
+    };
+    PaulineCourse {
+        ou: Some("Institut für Informatik".to_string()),
+        small_groups: vec![PaulineSmallGroup {
+            name: "Übung 1".to_string(),
+            appointments: vec![room("O2.267")],
+            group_number: Some(1),
+        }],
+        appointments: (0..appointments).map(|_| room("H1")).collect(),
+        ..common::course(cid, name)
+    }
+}
+
+fn semester() -> Semester {
+    common::semester(
+        "Sommer 2024",
+        vec![
+            course("L.079.05401", "Modellierung", 2),
+            course("L.079.05402", "Datenbanksysteme", 10),
+            course("L.079.05403", "Rechnernetze", 9),
+        ],
+    )
+}
+
+fn names(query: &str) -> Vec<String> {
+    let (_, rows) = run_query(&semester(), &parse_query(query).unwrap()).unwrap();
+    rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn parse_errors() {
+    let message = |query| parse_query(query).unwrap_err().to_string();
+    assert_eq!(
+        message("select name from courses where name = 'x"),
+        "unterminated string literal"
+    );
+    assert_eq!(
+        message("select name from courses where name ! 'x'"),
+        "expected !="
+    );
+    assert_eq!(message("select name courses"), "expected from");
+    assert_eq!(
+        message("select name from courses limit ten")
+            .split(',')
+            .next(),
+        Some("expected a number")
+    );
+    assert!(message("select name from courses where name = 'x' order").starts_with("unexpected"));
+    assert!(message("select name from courses where name 'x'").starts_with("expected an operator"));
+    assert_eq!(
+        message("select name from courses; drop"),
+        "unexpected character ;"
+    );
+
+    let run = |query| {
+        run_query(&semester(), &parse_query(query).unwrap())
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(run("select name from rooms").starts_with("unknown table rooms"));
+    assert_eq!(run("select room from courses"), "unknown column room");
+    assert_eq!(
+        run("select name from courses where room = 'H1'"),
+        "unknown column room"
+    );
+}
+
+#[test]
+fn operators() {
+    let (columns, rows) = run_query(
+        &semester(),
+        &parse_query("SELECT cid, name FROM courses WHERE name = 'Modellierung'").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(columns, ["cid", "name"]);
+    assert_eq!(rows, [["L.079.05401", "Modellierung"]]);
+
+    assert_eq!(
+        names("select name from courses where name != 'Modellierung'").len(),
+        2
+    );
+    assert_eq!(
+        names("select name from courses where name <> 'Modellierung'").len(),
+        2
+    );
+    // numbers compare as numbers, 10 > 9
+    assert_eq!(
+        names("select name from courses where appointments > 9"),
+        ["Datenbanksysteme"]
+    );
+    assert_eq!(
+        names("select name from courses where appointments >= 9").len(),
+        2
+    );
+    assert_eq!(
+        names("select name from courses where appointments < 9"),
+        ["Modellierung"]
+    );
+    assert_eq!(
+        names("select name from courses where appointments <= 2"),
+        ["Modellierung"]
+    );
+    // and binds stronger than or
+    assert_eq!(
+        names("select name from courses where name = 'Modellierung' or appointments > 2 and name = 'Rechnernetze'"),
+        ["Modellierung", "Rechnernetze"]
+    );
+    assert_eq!(names("select name from courses limit 2").len(), 2);
+    assert_eq!(
+        names("select small_group from small_groups where cid = 'L.079.05402'"),
+        ["Übung 1"]
+    );
+    assert_eq!(
+        names("select * from appointments where room = 'O2.267'"),
+        ["L.079.05401", "L.079.05402", "L.079.05403"]
+    );
+    // '' is a quote inside a literal
+    let mut semester = semester();
+    semester.courses[0].name = "Rock 'n' Roll".to_string();
+    let query = parse_query("select cid from courses where name = 'Rock ''n'' Roll'").unwrap();
+    assert_eq!(run_query(&semester, &query).unwrap().1, [["L.079.05401"]]);
+}
+
+#[test]
+fn like() {
+    let like = |pattern: &str| {
+        names(&format!(
+            "select name from courses where name like '{}'",
+            pattern
+        ))
+    };
+    assert_eq!(like("Modell%"), ["Modellierung"]);
+    assert_eq!(like("%NETZE"), ["Rechnernetze"]);
+    assert_eq!(like("%e%e%e%"), ["Datenbanksysteme", "Rechnernetze"]);
+    assert_eq!(like("Rechnernetz_"), ["Rechnernetze"]);
+    assert_eq!(like("Rechnernetz"), Vec::<String>::new());
+    assert_eq!(like("_"), Vec::<String>::new());
+    assert_eq!(like("%").len(), 3);
+    // exponential when every % tries every split of the rest
+    let mut semester = semester();
+    semester.courses[0].name = "a".repeat(100);
+    let query = format!(
+        "select name from courses where name like '{}%b'",
+        "%a".repeat(30)
+    );
+    let (_, rows) = run_query(&semester, &parse_query(&query).unwrap()).unwrap();
+    assert!(rows.is_empty());
+}
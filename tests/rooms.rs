@@ -0,0 +1,69 @@
+use paul_scrape_rs::{
+    rooms::{RoomEntry, RoomNormalizer},
+    Location,
+};
+
+#[test]
+fn spellings_of_the_same_room() {
+    let rooms = RoomNormalizer::bundled();
+    for raw in [
+        "O2.267",
+        "O 2.267",
+        "O2 267",
+        "o2-267",
+        "O2.267 (Seminarraum)",
+    ] {
+        assert_eq!(rooms.canonical(raw).as_deref(), Some("O2.267"), "{}", raw);
+    }
+    assert_eq!(rooms.canonical("Hörsaal O2").as_deref(), Some("O2"));
+    assert_eq!(rooms.canonical("Audimaxx").as_deref(), Some("Audimax"));
+    // short names are too close to each other for fuzzy matching
+    assert_eq!(rooms.canonical("H7"), None);
+    assert_eq!(rooms.canonical("Hörsal H1").as_deref(), Some("H1"));
+    // a typo in the number is another room, not a misspelling
+    assert_eq!(rooms.canonical("Hörsaal H7"), None);
+    assert_eq!(rooms.canonical("Audimax 2"), None);
+    // as close to H1 as to L1, C1 and O1
+    assert_eq!(rooms.canonical("Hörsaal 1"), None);
+    assert_eq!(rooms.canonical(""), None);
+}
+
+#[test]
+fn user_dictionary() {
+    let entries: Vec<RoomEntry> =
+        serde_json::from_str(r#"[{"id": "Pohlweg", "aliases": ["Pohlweg 51"]}]"#).unwrap();
+    let rooms = RoomNormalizer::extended(entries);
+    assert_eq!(rooms.canonical("Pohlweg 51").as_deref(), Some("Pohlweg"));
+    assert_eq!(rooms.canonical("Hörsaal H1").as_deref(), Some("H1"));
+}
+
+#[test]
+fn online_and_hybrid_locations() {
+    assert_eq!(Location::parse("O2.267 (Hörsaal)"), Location::Room);
+    assert_eq!(
+        Location::parse("Online-Veranstaltung"),
+        Location::Online { url: None }
+    );
+    assert_eq!(
+        Location::parse("Zoom: https://uni-paderborn.zoom.us/j/123456."),
+        Location::Online {
+            url: Some("https://uni-paderborn.zoom.us/j/123456".to_string())
+        }
+    );
+    assert_eq!(
+        Location::parse("O2.267 / https://bbb.uni-paderborn.de/b/abc-def"),
+        Location::Hybrid {
+            url: Some("https://bbb.uni-paderborn.de/b/abc-def".to_string())
+        }
+    );
+    assert_eq!(Location::parse("MS Teams"), Location::Online { url: None });
+    // the markers are words, not parts of room names
+    assert_eq!(Location::parse("Steamsaal"), Location::Room);
+    assert_eq!(Location::parse("Digitallabor O1.224"), Location::Room);
+    assert_eq!(
+        Location::parse("Hybrid, H1"),
+        Location::Hybrid { url: None }
+    );
+    assert_eq!(Location::parse(""), Location::Unknown);
+    assert_eq!(Location::parse("N.N."), Location::Unknown);
+}
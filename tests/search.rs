@@ -0,0 +1,51 @@
+use paul_scrape_rs::{
+    search::{find_courses, fold},
+    StateSerializable,
+};
+
+fn state() -> StateSerializable {
+    serde_json::from_value(serde_json::json!({
+        "semester": "Sommer 2023",
+        "start_time": "2023-04-01T00:00:00Z",
+        "courses": [
+            {
+                "path": {"fragments": ["Sommer 2023", "Informatik", "L.079.05401\nEinführung in Machine Learning"]},
+                "instructors": "Prof. Dr. Müller",
+                "ou": null,
+                "appointments": [],
+                "small_groups": []
+            },
+            {
+                "path": {"fragments": ["Sommer 2023", "Mathematik", "L.105.00101\nAnalysis I"]},
+                "instructors": "Dr. Weiß",
+                "ou": null,
+                "appointments": [],
+                "small_groups": []
+            }
+        ],
+        "small_groups": []
+    }))
+    .unwrap()
+}
+
+#[test]
+fn folding() {
+    assert_eq!(fold("Einführung Straße"), "einfuhrung strasse");
+}
+
+#[test]
+fn substring_and_fuzzy() {
+    let states = vec![state()];
+    let names = |query: &str, fuzzy: bool| {
+        find_courses(&states, query, fuzzy)
+            .iter()
+            .map(|hit| hit.course.path.fragments[1].clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("machine learning", false), vec!["Informatik"]);
+    assert_eq!(names("EINFUHRUNG", false), vec!["Informatik"]);
+    assert_eq!(names("weiss", false), vec!["Mathematik"]);
+    assert_eq!(names("mathematik", false), vec!["Mathematik"]);
+    assert!(names("machine lerning", false).is_empty());
+    assert_eq!(names("machine lerning", true), vec!["Informatik"]);
+}
@@ -0,0 +1,41 @@
+use chrono::NaiveDate;
+use paul_scrape_rs::resolve_semester;
+use url::Url;
+
+fn listed(titles: &[&str]) -> Vec<(String, Url)> {
+    titles
+        .iter()
+        .map(|title| {
+            let url = Url::parse("https://paul.uni-paderborn.de/semester").unwrap();
+            (title.to_string(), url)
+        })
+        .collect()
+}
+
+#[test]
+fn resolve_semester_specs() {
+    let semesters = listed(&["Winter 2023/24", "Sommer 2024", "Winter 2024/25"]);
+    let today = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    let resolve = |spec| resolve_semester(spec, today, &semesters);
+
+    assert_eq!(resolve("current").as_deref(), Some("Sommer 2024"));
+    assert_eq!(resolve("next").as_deref(), Some("Winter 2024/25"));
+    assert_eq!(resolve("Sommer 2024").as_deref(), Some("Sommer 2024"));
+    assert_eq!(resolve("SoSe 2024").as_deref(), Some("Sommer 2024"));
+    assert_eq!(resolve("ss 24").as_deref(), Some("Sommer 2024"));
+    assert_eq!(resolve("WS 23/24").as_deref(), Some("Winter 2023/24"));
+    assert_eq!(resolve("WiSe 2023/2024").as_deref(), Some("Winter 2023/24"));
+    assert_eq!(resolve("2023").as_deref(), Some("Winter 2023/24"));
+    // summer and winter term of 2024
+    assert_eq!(resolve("2024"), None);
+    // a term that isn't listed
+    assert_eq!(resolve("SoSe 2023"), None);
+    // not a term, left for the crawl to report
+    assert_eq!(resolve("Sondersemester").as_deref(), Some("Sondersemester"));
+
+    let today = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+    assert_eq!(
+        resolve_semester("current", today, &semesters).as_deref(),
+        Some("Winter 2023/24")
+    );
+}
@@ -0,0 +1,163 @@
+use paul_scrape_rs::{
+    sink::{open_sink, slug, Sink},
+    FailedPage, Path, StateSerializable,
+};
+
+fn state(semester: &str) -> StateSerializable {
+    StateSerializable {
+        semester: semester.to_string(),
+        start_time: chrono::Utc::now(),
+        courses: Vec::new(),
+        small_groups: Vec::new(),
+        failed_pages: vec![FailedPage {
+            url: "https://paul.uni-paderborn.de/broken".to_string(),
+            path: Path::new().push(semester.to_string()),
+            error: "no title".to_string(),
+            kind: None,
+            html: None,
+            excerpt: None,
+        }],
+    }
+}
+
+fn write_all(sink: &mut Box<dyn Sink>, semesters: &[&str]) {
+    for semester in semesters {
+        sink.write(&state(semester)).unwrap();
+    }
+    sink.finish().unwrap();
+}
+
+#[test]
+fn json_and_ndjson_outputs() {
+    let dir = std::env::temp_dir().join(format!("paul-sink-{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+
+    let mut single = open_sink("state.json", Some(dir)).unwrap();
+    write_all(&mut single, &["Sommer 2023"]);
+    let written: StateSerializable =
+        serde_json::from_str(&std::fs::read_to_string(format!("{}/state.json", dir)).unwrap())
+            .unwrap();
+    assert_eq!(written.semester, "Sommer 2023");
+
+    // several semesters get one file each
+    let mut split = open_sink("json:split.json", Some(dir)).unwrap();
+    write_all(&mut split, &["Sommer 2023", "Winter 2023/24"]);
+    assert!(std::path::Path::new(&format!("{}/split.Sommer-2023.json", dir)).exists());
+    assert!(std::path::Path::new(&format!("{}/split.Winter-2023-24.json", dir)).exists());
+
+    let mut ndjson = open_sink("ndjson:stream.ndjson", Some(dir)).unwrap();
+    write_all(&mut ndjson, &["Sommer 2023", "Winter 2023/24"]);
+    let lines = std::fs::read_to_string(format!("{}/stream.ndjson", dir)).unwrap();
+    let records = lines
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1]["type"], "failed_page");
+    assert_eq!(records[1]["semester"], "Winter 2023/24");
+    assert_eq!(records[1]["error"], "no title");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_output_replaces_semester() {
+    let dir = std::env::temp_dir().join(format!("paul-sqlite-{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    // a second run must not duplicate the rows
+    for _ in 0..2 {
+        let mut sink = open_sink("sqlite:paul.db", Some(dir)).unwrap();
+        write_all(&mut sink, &["Sommer 2023"]);
+    }
+    let connection = rusqlite::Connection::open(format!("{}/paul.db", dir)).unwrap();
+    let rows: i64 = connection
+        .query_row("SELECT COUNT(*) FROM failed_pages", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(rows, 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn msgpack_output_reads_back() {
+    use paul_scrape_rs::{
+        links::CourseUrl,
+        parse_course_page,
+        snapshot::{read_snapshot, snapshot_format, Format},
+    };
+
+    let dir = std::env::temp_dir().join(format!("paul-msgpack-{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    let url = CourseUrl::new("https://paul.uni-paderborn.de/course".parse().unwrap());
+    let html = format!(
+        r#"<form name="courseform"><h1>L.104.12345 Analysis</h1>{}</form>"#,
+        include_str!("fixtures/appointments_table.html")
+    );
+    let mut written = state("Sommer 2023");
+    written
+        .courses
+        .push(parse_course_page(html, &url, &Path::new()).0);
+
+    let mut msgpack = open_sink("msgpack:state.msgpack", Some(dir)).unwrap();
+    msgpack.write(&written).unwrap();
+    msgpack.finish().unwrap();
+    let mut json = open_sink("state.json", Some(dir)).unwrap();
+    json.write(&written).unwrap();
+    json.finish().unwrap();
+
+    let path = format!("{}/state.msgpack", dir);
+    let read: StateSerializable = read_snapshot(&path).unwrap();
+    assert_eq!(
+        serde_json::to_value(&read).unwrap(),
+        serde_json::to_value(&written).unwrap()
+    );
+    assert!(!read.courses[0].appointments.is_empty());
+    // json files are read the same way
+    let read: StateSerializable = read_snapshot(format!("{}/state.json", dir)).unwrap();
+    assert_eq!(read.courses.len(), 1);
+    assert_eq!(snapshot_format(&path).unwrap(), Format::Msgpack);
+    assert_eq!(
+        snapshot_format(format!("{}/state.json", dir)).unwrap(),
+        Format::Json
+    );
+    let size = |file: &str| {
+        std::fs::metadata(format!("{}/{}", dir, file))
+            .unwrap()
+            .len()
+    };
+    assert!(size("state.msgpack") < size("state.json"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "scrape")]
+#[tokio::test]
+async fn async_sinks() {
+    use paul_scrape_rs::sink::{AsyncSink, Item, NdjsonSink};
+
+    let path = std::env::temp_dir().join(format!("paul-async-sink-{}.ndjson", std::process::id()));
+    let failed = state("Sommer 2023").failed_pages.remove(0);
+
+    let mut ndjson = NdjsonSink::create(&path).unwrap();
+    let mut collected = state("Sommer 2023");
+    for sink in [&mut ndjson as &mut dyn AsyncSink, &mut collected] {
+        sink.write("Sommer 2023", Item::FailedPage(failed.clone()))
+            .await
+            .unwrap();
+        AsyncSink::finish(sink).await.unwrap();
+    }
+
+    let lines = std::fs::read_to_string(&path).unwrap();
+    let record: serde_json::Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+    assert_eq!(record["type"], "failed_page");
+    assert_eq!(record["semester"], "Sommer 2023");
+    assert_eq!(collected.failed_pages.len(), 2);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn slugs() {
+    assert_eq!(slug("Winter 2023/24"), "Winter-2023-24");
+    assert_eq!(slug("Institut für Informatik"), "Institut-für-Informatik");
+    assert_eq!(slug(" -- "), "");
+}
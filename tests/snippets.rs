@@ -0,0 +1,542 @@
+use paul_scrape_rs::{
+    appointments_table_status, combined_small_groups_link,
+    links::{CourseUrl, ModuleUrl, SmallGroupUrl, TreeUrl},
+    modules::parse_module_page,
+    parse_appointments_table, parse_combined_small_groups, parse_course_page,
+    parse_registration_links, parse_small_group_listing, parse_small_group_title,
+    parse_small_group_with, parse_tree_page, split_title,
+    table::{extract_table_by_caption, find_tables_by_caption},
+    Appointment, AppointmentColumns, AppointmentsTable, CoursePage, Frequency, Location, Path,
+    RegistrationStatus, Warning,
+};
+use scraper::Html;
+use url::Url;
+
+#[test]
+fn appointments_table() {
+    let html = include_str!("fixtures/appointments_table.html");
+    assert_eq!(
+        parse_appointments_table(html),
+        vec![
+            Appointment {
+                start_time: ("Di, 18. Apr. 2023".to_string(), "11:00".to_string()),
+                end_time: ("Di, 18. Apr. 2023".to_string(), "13:00".to_string()),
+                room: "O2.267 (Hörsaal)".to_string(),
+                instructors: "Prof. Dr. Meyer".to_string(),
+                cancelled: false,
+                frequency: Frequency::Unknown,
+                location: Location::Room,
+            },
+            Appointment {
+                start_time: ("Di, 2. Mai 2023".to_string(), "11:00".to_string()),
+                end_time: ("Di, 2. Mai 2023".to_string(), "13:00".to_string()),
+                room: "H1".to_string(),
+                instructors: "Dr. Schmidt".to_string(),
+                cancelled: false,
+                frequency: Frequency::Unknown,
+                location: Location::Room,
+            },
+            Appointment {
+                start_time: ("Di, 9. Mai 2023".to_string(), "11:00".to_string()),
+                end_time: ("Di, 9. Mai 2023".to_string(), "13:00".to_string()),
+                room: "O2.267".to_string(),
+                instructors: "Prof. Dr. Meyer (fällt aus)".to_string(),
+                cancelled: true,
+                frequency: Frequency::Unknown,
+                location: Location::Room,
+            },
+        ]
+    );
+}
+
+#[test]
+fn table_by_caption() {
+    let document = Html::parse_document(include_str!("fixtures/appointments_table.html"));
+    let rows = extract_table_by_caption(&document, "termine");
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[0][1], "Di, 18. Apr. 2023");
+    assert!(extract_table_by_caption(&document, "Kleingruppe(n)").is_empty());
+
+    let tables = find_tables_by_caption(document.root_element(), "Termine");
+    let room = tables[0].column("raum").unwrap();
+    assert_eq!(rows[1][room], "O2.267");
+    assert_eq!(tables[0].records()[0]["Lehrende"], "Prof. Dr. Meyer");
+}
+
+#[test]
+fn appointments_table_without_caption() {
+    assert!(parse_appointments_table("<table><tr><td>1</td></tr></table>").is_empty());
+}
+
+#[test]
+fn appointments_table_status_without_dates() {
+    let html = include_str!("fixtures/appointments_table.html");
+    assert_eq!(appointments_table_status(html), AppointmentsTable::Listed);
+    assert_eq!(
+        appointments_table_status("<table><tr><td>1</td></tr></table>"),
+        AppointmentsTable::Absent
+    );
+    assert_eq!(
+        appointments_table_status(
+            "<table><caption>Termine</caption><tr><th></th></tr><tr><td>Keine Termine</td></tr></table>"
+        ),
+        AppointmentsTable::Empty
+    );
+    assert_eq!(
+        appointments_table_status(
+            "<table><caption>Termine</caption><tr><th></th></tr><tr><td>Mo</td><td>09:00</td></tr></table>"
+        ),
+        AppointmentsTable::Unparsable
+    );
+}
+
+#[test]
+fn appointment_frequency() {
+    let html = r#"<table><caption>Termine</caption><tr><th></th></tr>
+        <tr><td>14-täglich</td><td>Mo, 17. Apr. 2023</td><td>09:00</td><td>11:00</td><td>H1</td><td></td></tr>
+        <tr><td>Einzeltermin</td><td>Fr, 21. Apr. 2023</td><td>14:00</td><td>16:00</td><td>H2</td><td></td></tr>
+    </table>"#;
+    let frequencies = parse_appointments_table(html)
+        .into_iter()
+        .map(|appointment| appointment.frequency)
+        .collect::<Vec<_>>();
+    assert_eq!(frequencies, vec![Frequency::Biweekly, Frequency::Single]);
+}
+
+#[test]
+fn appointment_columns_by_header() {
+    // a column added in front of the room, and von/bis swapped
+    let html = r#"<table><caption>Termine</caption>
+        <tr><th></th><th>Datum</th><th>bis</th><th>von</th><th>Hinweis</th><th>Raum</th><th>Lehrende</th></tr>
+        <tr><td>wöchentlich</td><td>Mo, 17. Apr. 2023</td><td>11:00</td><td>09:00</td><td>neu</td><td>H1</td><td>Meyer</td></tr>
+    </table>"#;
+    let appointments = parse_appointments_table(html);
+    assert_eq!(appointments[0].start_time.1, "09:00");
+    assert_eq!(appointments[0].end_time.1, "11:00");
+    assert_eq!(appointments[0].room, "H1");
+    assert_eq!(appointments[0].instructors, "Meyer");
+    assert_eq!(appointments[0].frequency, Frequency::Weekly);
+
+    // renamed, found through the config's names
+    let page = format!(
+        r#"<form name="courseform"><h2>Übung 1</h2>{}</form>"#,
+        html.replace("<th>Raum</th>", "<th>Ort</th>")
+    );
+    let url = SmallGroupUrl::new(Url::parse("https://paul.uni-paderborn.de/?group=1").unwrap());
+    let small_group = parse_small_group_with(
+        page.clone(),
+        &url,
+        &Path::new(),
+        &AppointmentColumns::default(),
+    );
+    assert_eq!(small_group.appointments[0].room, "");
+    let columns = AppointmentColumns {
+        room: vec!["Ort".to_string()],
+        ..AppointmentColumns::default()
+    };
+    let small_group = parse_small_group_with(page, &url, &Path::new(), &columns);
+    assert_eq!(small_group.appointments[0].room, "H1");
+    assert!(small_group.warnings.is_empty());
+
+    // unknown headers fall back to the old positions, with a warning
+    let page = r#"<form name="courseform"><h2>Übung 1</h2><table><caption>Termine</caption>
+        <tr><th></th><th>Tag</th></tr>
+        <tr><td></td><td>Mo, 17. Apr. 2023</td><td>09:00</td><td>11:00</td><td>H1</td><td></td></tr>
+    </table></form>"#;
+    let small_group = parse_small_group_with(
+        page.to_string(),
+        &url,
+        &Path::new(),
+        &AppointmentColumns::default(),
+    );
+    assert_eq!(small_group.appointments[0].room, "H1");
+    assert!(matches!(
+        small_group.warnings[..],
+        [Warning::UnknownAppointmentColumns { .. }]
+    ));
+}
+
+#[test]
+fn registration_links() {
+    let html = include_str!("fixtures/registration_links.html");
+    let base_url = Url::parse("https://paul.uni-paderborn.de").unwrap();
+    let links = parse_registration_links(html, &base_url)
+        .into_iter()
+        .map(|(url, text)| (url.to_string(), text))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        links,
+        vec![
+            (
+                "https://paul.uni-paderborn.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=REGISTRATION&ARGUMENTS=-N000000000000001".to_string(),
+                "Fakultät für Elektrotechnik, Informatik und Mathematik".to_string()
+            ),
+            (
+                "https://paul.uni-paderborn.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=REGISTRATION&ARGUMENTS=-N000000000000002".to_string(),
+                "Fakultät für Kulturwissenschaften".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn registration_links_missing() {
+    let base_url = Url::parse("https://paul.uni-paderborn.de").unwrap();
+    assert!(parse_registration_links("<p>nothing here</p>", &base_url).is_empty());
+    // anchors without an href or with one that isn't a url are skipped
+    let html = r#"<div id="auditRegistration_list">
+        <a name="top">Anmeldung</a>
+        <a href="http://[::1">broken</a>
+        <a href="/registration">Fakultät für Kulturwissenschaften</a>
+    </div>"#;
+    let links = parse_registration_links(html, &base_url);
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].0.path(), "/registration");
+}
+
+#[test]
+fn paginated_small_group_listing() {
+    let html = r#"<div class="tb"><div class="tbhead">Kleingruppe(n)</div>
+        <a href="/scripts/mgrqispi.dll?group=1">Gruppe 1</a>
+        <a href="/scripts/mgrqispi.dll?group=2">Gruppe 2</a>
+        <a href="/scripts/mgrqispi.dll?page=1">1</a>
+        <a href="/scripts/mgrqispi.dll?page=2">2</a>
+        <a href="/scripts/mgrqispi.dll?page=2">&gt;</a>
+    </div>"#;
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll?page=1").unwrap();
+    let (links, next) = parse_small_group_listing(html, &url, &Path::new());
+    let links = links
+        .into_iter()
+        .map(|(url, _)| url.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        links,
+        vec![
+            "https://paul.uni-paderborn.de/scripts/mgrqispi.dll?group=1",
+            "https://paul.uni-paderborn.de/scripts/mgrqispi.dll?group=2",
+        ]
+    );
+    assert_eq!(
+        next.map(|url| url.to_string()),
+        Some("https://paul.uni-paderborn.de/scripts/mgrqispi.dll?page=2".to_string())
+    );
+}
+
+#[test]
+fn combined_small_groups() {
+    let course = r#"<div class="tb"><div class="tbhead">Kleingruppe(n)</div>
+        <a href="/scripts/mgrqispi.dll?group=1">Gruppe 1</a>
+        <a href="/scripts/mgrqispi.dll?group=2">Gruppe 2</a>
+        <a href="/scripts/mgrqispi.dll?groups=all">Alle Kleingruppen anzeigen</a>
+    </div>"#;
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let (links, _) = parse_small_group_listing(course, &url, &Path::new());
+    assert_eq!(links.len(), 2);
+    let combined = combined_small_groups_link(course, &url).unwrap();
+    assert_eq!(
+        combined.as_str(),
+        "https://paul.uni-paderborn.de/scripts/mgrqispi.dll?groups=all"
+    );
+
+    let table = include_str!("fixtures/appointments_table.html");
+    let page = format!(
+        r#"<div class="tb"><div class="tbhead"><a href="?group=1">Kleingruppe: Übung 1</a></div>{}</div>
+        <div class="tb"><div class="tbhead"><a href="?group=2">Kleingruppe: Übung 2</a></div>
+            <table><caption>Termine</caption><tr><th></th></tr></table></div>
+        <div class="tb"><div class="tbhead">Hinweise</div></div>"#,
+        table
+    );
+    let small_groups = parse_combined_small_groups(&page, &combined, &Path::new());
+    assert_eq!(
+        small_groups
+            .iter()
+            .map(|sg| (sg.url.as_str(), sg.group_number, sg.appointments.len()))
+            .collect::<Vec<_>>(),
+        vec![
+            (links[0].0.as_str(), Some(1), 3),
+            (links[1].0.as_str(), Some(2), 0),
+        ]
+    );
+    assert_eq!(
+        small_groups[1].appointments_table,
+        Some(AppointmentsTable::Empty)
+    );
+}
+
+#[test]
+fn course_types() {
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let listing = r#"<table class="nb eventTable">
+        <tr><td><a href="?PRGNAME=COURSEDETAILS&amp;ARGUMENTS=-N1">L.079.05401 Übung zur Vorlesung GP1</a></td><td>Übung</td></tr>
+        <tr><td><a href="?PRGNAME=COURSEDETAILS&amp;ARGUMENTS=-N2">L.079.05402 Datenbanken</a></td></tr>
+    </table>"#;
+    let (courses, _, _) = parse_tree_page(
+        listing.to_string(),
+        &TreeUrl::new(url.clone()),
+        &Path::new(),
+    );
+    let types = courses
+        .into_iter()
+        .map(|course| course.course_type)
+        .collect::<Vec<_>>();
+    assert_eq!(types, vec![Some("Übung".to_string()), None]);
+
+    let page = r#"<form name="courseform"><h1>L.079.05402 Datenbanken</h1>
+        <table><tr><td>Veranstaltungsart:</td><td>Vorlesung</td></tr></table></form>"#;
+    let (course, _) = parse_course_page(page.to_string(), &CourseUrl::new(url), &Path::new());
+    assert_eq!(course.course_type.as_deref(), Some("Vorlesung"));
+}
+
+#[test]
+fn course_page_round_trip() {
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let listing = r#"<table class="nb eventTable">
+        <tr><td><a href="?PRGNAME=COURSEDETAILS&amp;ARGUMENTS=-N1">L.079.05401 Übung zur Vorlesung GP1</a></td><td>Übung</td></tr>
+    </table>"#;
+    let (courses, _, _) = parse_tree_page(
+        listing.to_string(),
+        &TreeUrl::new(url.clone()),
+        &Path::new(),
+    );
+    let json = serde_json::to_string(&courses).unwrap();
+    let parsed: Vec<CoursePage> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    assert_eq!(parsed[0].url, courses[0].url);
+}
+
+#[test]
+fn small_group_titles() {
+    assert_eq!(
+        parse_small_group_title("Kleingruppe:\u{a0}Übung 03, Di 14-16"),
+        ("Übung 03, Di 14-16".to_string(), Some(3))
+    );
+    assert_eq!(
+        parse_small_group_title("Small group: Tutorial 12"),
+        ("Tutorial 12".to_string(), Some(12))
+    );
+    assert_eq!(
+        parse_small_group_title("Kleingruppe: Gruppe A, Mo 9-11"),
+        ("Gruppe A, Mo 9-11".to_string(), None)
+    );
+}
+
+#[test]
+fn course_title_lines() {
+    let url =
+        CourseUrl::new(Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap());
+    let page = "<form name=\"courseform\"><h1>\n\t\tL.079.05401\n\t\tGrundlagen  der\u{a0}Programmierung <span>1</span><br>Gruppe A\n</h1></form>";
+    let (course, _) = parse_course_page(page.to_string(), &url, &Path::new());
+    assert_eq!(
+        course.title_lines,
+        vec!["L.079.05401", "Grundlagen der Programmierung 1", "Gruppe A"]
+    );
+    assert_eq!(
+        course.display_title(),
+        "L.079.05401 Grundlagen der Programmierung 1 Gruppe A"
+    );
+
+    // number and name on one line, and state files without the lines
+    assert_eq!(
+        split_title("L.079.05401 Softwaretechnik"),
+        vec!["L.079.05401", "Softwaretechnik"]
+    );
+    let mut course = course;
+    course.title_lines.clear();
+    assert_eq!(course.heading().len(), 3);
+}
+
+#[test]
+fn course_last_updated() {
+    let url =
+        CourseUrl::new(Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap());
+    let page = r#"<form name="courseform"><h1>L.079.05401 Softwaretechnik</h1>
+        <p>Zuletzt geändert: 12.03.2024 14:35</p></form>"#;
+    let (course, _) = parse_course_page(page.to_string(), &url, &Path::new());
+    assert_eq!(
+        course.source_updated_at.map(|updated| updated.to_string()),
+        Some("2024-03-12 14:35:00".to_string())
+    );
+
+    let page = r#"<html lang="en"><form name="courseform"><h1>L.079.05401 Software Engineering</h1>
+        <p>Last modified 1.4.2024</p></form></html>"#;
+    let (course, _) = parse_course_page(page.to_string(), &url, &Path::new());
+    assert_eq!(
+        course.source_updated_at.map(|updated| updated.to_string()),
+        Some("2024-04-01 00:00:00".to_string())
+    );
+}
+
+#[test]
+fn module_page() {
+    let url = Url::parse("https://paul.uni-paderborn.de/scripts/mgrqispi.dll").unwrap();
+    let course = r#"<form name="courseform"><h1>L.079.05401 Softwaretechnik</h1>
+        <p>Modul: <a href="?PRGNAME=MODULEDETAILS&amp;ARGUMENTS=-N7">M.079.4010 Softwaretechnik und Programmierung</a></p></form>"#;
+    let (course, _) = parse_course_page(
+        course.to_string(),
+        &CourseUrl::new(url.clone()),
+        &Path::new(),
+    );
+    assert_eq!(
+        course.modules,
+        vec!["https://paul.uni-paderborn.de/scripts/mgrqispi.dll?PRGNAME=MODULEDETAILS&ARGUMENTS=-N7"]
+    );
+
+    let page = r#"<h1>M.079.4010 Softwaretechnik und Programmierung</h1>
+        <table>
+            <tr><td>Modulverantwortliche(r):</td><td>Prof. Dr. Meyer</td></tr>
+            <tr><td>Leistungspunkte:</td><td>10 ECTS</td></tr>
+        </table>
+        <table>
+            <tr><td><a href="?PRGNAME=COURSEDETAILS&amp;ARGUMENTS=-N1">L.079.05401 Softwaretechnik</a></td></tr>
+            <tr><td><a href="?PRGNAME=COURSEDETAILS&amp;ARGUMENTS=-N2">L.079.05402 Übung zu Softwaretechnik</a></td></tr>
+        </table>"#;
+    let module = parse_module_page(
+        page,
+        &ModuleUrl::new(Url::parse(&course.modules[0]).unwrap()),
+    );
+    assert_eq!(module.number, "M.079.4010");
+    assert_eq!(module.title, "Softwaretechnik und Programmierung");
+    assert_eq!(module.responsible.as_deref(), Some("Prof. Dr. Meyer"));
+    assert_eq!(module.credits.map(|credits| credits.min), Some(10.0));
+    assert_eq!(
+        module
+            .courses
+            .iter()
+            .map(|course| course.title.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "L.079.05401 Softwaretechnik",
+            "L.079.05402 Übung zu Softwaretechnik"
+        ]
+    );
+}
+
+#[test]
+fn free_text_sections() {
+    let html = include_str!("fixtures/course_sections.html");
+    let url = CourseUrl::new(Url::parse("https://paul.uni-paderborn.de/course").unwrap());
+    let (course, _) = parse_course_page(html.to_string(), &url, &Path::new());
+    // its own box
+    assert_eq!(
+        course.prerequisites.as_deref(),
+        Some("Keine, die Veranstaltung richtet sich an Erstsemester.")
+    );
+    // a bold heading nested in a paragraph, up to the next heading
+    assert_eq!(
+        course.recommended_knowledge.as_deref(),
+        Some("Schulmathematik Grundlagen der Logik")
+    );
+
+    let html = html
+        .replace("Voraussetzungen:", "Lernziele")
+        .replace("Empfohlene Vorkenntnisse", "Hinweise");
+    let (course, _) = parse_course_page(html, &url, &Path::new());
+    assert_eq!(course.prerequisites, None);
+    assert_eq!(course.recommended_knowledge, None);
+}
+
+#[test]
+fn my_status() {
+    let status = |label: &str| {
+        let html = format!(
+            r#"<form name="courseform"><h1>L.079.05401 Modellierung</h1>
+            <table><tr><td>Status</td><td><span>{}</span></td></tr></table></form>"#,
+            label
+        );
+        let url = CourseUrl::new(Url::parse("https://paul.uni-paderborn.de/course").unwrap());
+        parse_course_page(html, &url, &Path::new()).0.my_status
+    };
+    assert_eq!(status("angemeldet"), Some(RegistrationStatus::Registered));
+    assert_eq!(status("Zugelassen"), Some(RegistrationStatus::Accepted));
+    assert_eq!(status("accepted"), Some(RegistrationStatus::Accepted));
+    assert_eq!(
+        status("Warteliste Platz 3"),
+        Some(RegistrationStatus::Waitlisted { position: Some(3) })
+    );
+    assert_eq!(
+        status("Waiting list"),
+        Some(RegistrationStatus::Waitlisted { position: None })
+    );
+    assert_eq!(status("Abgelehnt"), Some(RegistrationStatus::Rejected));
+    assert_eq!(status("nicht angemeldet"), None);
+    // a description starting with one of the words
+    assert_eq!(
+        status("Angemeldete Studierende erhalten die Unterlagen per Mail"),
+        None
+    );
+    // only the course form counts
+    let html = r#"<div><span>Zugelassen</span></div>
+        <form name="courseform"><h1>L.079.05401 Modellierung</h1></form>"#;
+    let url = CourseUrl::new(Url::parse("https://paul.uni-paderborn.de/course").unwrap());
+    assert_eq!(
+        parse_course_page(html.to_string(), &url, &Path::new())
+            .0
+            .my_status,
+        None
+    );
+}
+
+#[test]
+fn related_courses() {
+    let link = |args: &str, title: &str| {
+        format!(
+            r#"<a href="/scripts/mgrqispi.dll?APPNAME=CampusNet&amp;PRGNAME={}">{}</a>"#,
+            args, title
+        )
+    };
+    let html = format!(
+        r#"<form name="courseform"><h1>L.079.05401 Modellierung</h1>
+        <div class="tb"><div class="tbhead">Zugehörige Veranstaltungen:</div>
+          <div>{} {} {} {}</div></div>
+        <table><caption>Zugehörige Veranstaltungen</caption><tr><td>{}</td></tr></table>
+        <div class="tb"><div class="tbhead">Weitere Informationen</div>{}</div>
+        </form>"#,
+        link(
+            "COURSEDETAILS&amp;ARGUMENTS=-N1,-N2",
+            "L.079.05402 Übung zur Modellierung"
+        ),
+        // listed twice
+        link(
+            "COURSEDETAILS&amp;ARGUMENTS=-N1,-N2",
+            "L.079.05402 Übung zur Modellierung"
+        ),
+        // not a course
+        link(
+            "MODULEDETAILS&amp;ARGUMENTS=-N1,-N3",
+            "M.079.4000 Modellierung"
+        ),
+        r#"<a name="anchor">no href</a>"#,
+        link(
+            "COURSEDETAILS&amp;ARGUMENTS=-N1,-N4",
+            "L.079.05403 Tutorium"
+        ),
+        // a course link in another box
+        link("COURSEDETAILS&amp;ARGUMENTS=-N1,-N5", "L.079.05404 Vorkurs"),
+    );
+    let url = CourseUrl::new(Url::parse("https://paul.uni-paderborn.de/course").unwrap());
+    let (course, _) = parse_course_page(html, &url, &Path::new());
+    let related = course
+        .related_courses
+        .iter()
+        .map(|related| {
+            (
+                related.url.rsplit('-').next().unwrap(),
+                related.title.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        related,
+        [
+            ("N2", "L.079.05402 Übung zur Modellierung"),
+            ("N4", "L.079.05403 Tutorium")
+        ]
+    );
+
+    let (course, _) = parse_course_page(
+        r#"<form name="courseform"><h1>L.079.05401 Modellierung</h1></form>"#.to_string(),
+        &url,
+        &Path::new(),
+    );
+    assert!(course.related_courses.is_empty());
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "scrape")]
+
+use paul_scrape_rs::spill::Spill;
+
+#[test]
+fn spill_round_trip() {
+    let mut spill = Spill::<(String, u32)>::new().unwrap();
+    for i in 0..5 {
+        spill.push(&(format!("entry {}", i), i)).unwrap();
+    }
+    assert_eq!(spill.len(), 5);
+    let first = spill.pop_many(2).unwrap();
+    assert_eq!(
+        first,
+        vec![("entry 0".to_string(), 0), ("entry 1".to_string(), 1)]
+    );
+
+    // pushing while partially read keeps the order
+    spill.push(&("entry 5".to_string(), 5)).unwrap();
+    let rest = spill.pop_many(usize::MAX).unwrap();
+    assert_eq!(
+        rest.iter().map(|(_, i)| *i).collect::<Vec<_>>(),
+        vec![2, 3, 4, 5]
+    );
+    assert!(spill.is_empty());
+    assert!(spill.pop_many(3).unwrap().is_empty());
+
+    // and the file is reused once empty
+    spill.push(&("again".to_string(), 6)).unwrap();
+    assert_eq!(spill.pop_many(1).unwrap()[0].1, 6);
+}
@@ -0,0 +1,19 @@
+use paul_scrape_rs::tags::{TagRule, TagRules};
+
+#[test]
+fn keywords_and_patterns() {
+    let rules: Vec<TagRule> = serde_json::from_str(
+        r#"[
+            {"tag": "online", "pattern": "\\b(online|digital)\\b"},
+            {"tag": "blockseminar", "keywords": ["Blockseminar", "Blockveranstaltung"]},
+            {"tag": "english", "keywords": ["english"], "pattern": "englischsprachig"}
+        ]"#,
+    )
+    .unwrap();
+    let rules = TagRules::compile(rules).unwrap();
+    assert_eq!(
+        rules.tags(&["Blockseminar: Digital Humanities", "held in English"]),
+        vec!["blockseminar", "english", "online"]
+    );
+    assert!(rules.tags(&["Analysis I", ""]).is_empty());
+}
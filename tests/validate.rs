@@ -0,0 +1,78 @@
+use paul_scrape_rs::{
+    pauline::{PaulineAppointment, PaulineCourse, Semester},
+    validate::{validate_semester, Problem},
+    Location,
+};
+
+fn course(cid: &str, appointments: usize) -> PaulineCourse {
+    PaulineCourse {
+        cid: cid.to_string(),
+        name: format!("Course {}", cid),
+        description: None,
+        ou: None,
+        instructors: None,
+        small_groups: Vec::new(),
+        appointments: (0..appointments)
+            .map(|_| PaulineAppointment {
+                start_time: "2024-04-15T09:00:00".to_string(),
+                end_time: "2024-04-15T11:00:00".to_string(),
+                room: "H1".to_string(),
+                room_id: None,
+                instructors: String::new(),
+                location: Location::Room,
+                cancelled: false,
+            })
+            .collect(),
+        tags: Vec::new(),
+        source_updated_at: None,
+    }
+}
+
+fn semester(courses: Vec<PaulineCourse>) -> Semester {
+    Semester {
+        name: "Sommer 2024".to_string(),
+        created: "2024-04-01T00:00:00".to_string(),
+        courses,
+    }
+}
+
+#[test]
+fn broken_snapshots_are_rejected() {
+    let published = semester((0..10).map(|i| course(&i.to_string(), 1)).collect());
+    let fresh = semester((0..9).map(|i| course(&i.to_string(), 1)).collect());
+    assert!(validate_semester(&fresh, Some(&published), 50.0).is_empty());
+    assert!(validate_semester(&published, None, 50.0).is_empty());
+
+    assert_eq!(
+        validate_semester(&semester(Vec::new()), Some(&published), 50.0),
+        [
+            Problem::NoCourses,
+            Problem::CoursesDropped {
+                published: 10,
+                fresh: 0
+            },
+            Problem::AppointmentsDropped {
+                published: 100.0,
+                fresh: 0.0
+            }
+        ]
+    );
+
+    // the appointments table went missing
+    let fresh = semester((0..10).map(|i| course(&i.to_string(), 0)).collect());
+    assert!(matches!(
+        validate_semester(&fresh, Some(&published), 50.0)[..],
+        [Problem::AppointmentsDropped { .. }]
+    ));
+
+    let mut fresh = semester(vec![course("a", 1), course("a", 0)]);
+    fresh.courses[0].appointments[0].end_time = "2024-04-15T08:00:00".to_string();
+    let problems = validate_semester(&fresh, None, 50.0);
+    assert!(matches!(
+        problems[..],
+        [
+            Problem::InvalidAppointment { .. },
+            Problem::DuplicateCid { .. }
+        ]
+    ));
+}